@@ -1,5 +1,7 @@
 use std::{
-    fs::File,
+    collections::HashSet,
+    fmt::Write as _,
+    fs::{self, File},
     io::{Read, Write},
     path::Path,
 };
@@ -24,6 +26,153 @@ fn resolve_include(
     }
 }
 
+/// Connection states as named in the protocol files, with their `ConnectionState` variants
+const STATES: [(&str, &str); 4] = [
+    ("handshaking", "Handshaking"),
+    ("status", "Status"),
+    ("login", "Login"),
+    ("play", "Play"),
+];
+
+/// Packet directions as named in the protocol files, with their `PacketDirection` variants
+const DIRECTIONS: [(&str, &str, &str); 2] = [
+    ("toClient", "clientbound", "Client"),
+    ("toServer", "serverbound", "Server"),
+];
+
+/// Names of the structs declared with `packet Name { .. }` in `packets.rs`
+fn defined_packets(path: &str) -> HashSet<String> {
+    let source = fs::read_to_string(path).expect("Failed to read packet definitions");
+    source
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix("packet "))
+        .filter_map(|l| l.split_whitespace().next())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Generates a `packet_ids!` module for every protocol file, along with the functions dispatching to them
+///
+/// The files map the packet ids of a version to the structs in `packets.rs`, with states and directions named like minecraft-data's:
+/// `{ "version": 340, "minecraftVersion": "1.12.2", "play": { "toClient": { "0x00": "SpawnEntity_107" } } }`
+fn generate_versions(protocol_dir: &str, packets_path: &str) -> String {
+    println!("cargo:rerun-if-changed={}", protocol_dir);
+    println!("cargo:rerun-if-changed={}", packets_path);
+
+    let defined = defined_packets(packets_path);
+    let mut versions = vec![];
+    for entry in fs::read_dir(protocol_dir).expect("Failed to read protocol directory") {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|e| e != "json") {
+            continue;
+        }
+
+        println!("cargo:rerun-if-changed={}", path.display());
+        let data: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).expect("Failed to read protocol file"))
+                .unwrap_or_else(|e| panic!("Invalid protocol file {}: {}", path.display(), e));
+        let name = path
+            .file_stem()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .replace('.', "_");
+        versions.push((name, path, data));
+    }
+    versions.sort_by_key(|(_, _, data)| data["version"].as_i64());
+
+    let mut undefined = vec![];
+    let mut out = String::new();
+    for (name, path, data) in &versions {
+        let version = data["version"]
+            .as_i64()
+            .unwrap_or_else(|| panic!("{} has no protocol version", path.display()));
+
+        writeln!(out, "pub mod v{} {{", name).unwrap();
+        writeln!(
+            out,
+            "crate::packet_ids! {{
+    version {},",
+            version
+        )
+        .unwrap();
+        for (state, tstate) in STATES {
+            writeln!(out, "    {} {} {{", state, tstate).unwrap();
+            for (dir, tdir_name, tdir) in DIRECTIONS {
+                writeln!(out, "        {} {} {{", tdir_name, tdir).unwrap();
+                let mut ids = vec![];
+                if let Some(packets) = data[state][dir].as_object() {
+                    for (id, packet) in packets {
+                        let packet = packet.as_str().unwrap_or_else(|| {
+                            panic!("{}: {} {} {} isn't a name", path.display(), state, dir, id)
+                        });
+                        let id = i32::from_str_radix(id.trim_start_matches("0x"), 16)
+                            .unwrap_or_else(|_| {
+                                panic!("{}: invalid packet id {}", path.display(), id)
+                            });
+                        if !defined.contains(packet) {
+                            undefined.push(format!(
+                                "{}: {} {} 0x{:02x} => {}",
+                                path.display(),
+                                state,
+                                dir,
+                                id,
+                                packet
+                            ));
+                        }
+                        ids.push((id, packet));
+                    }
+                }
+
+                ids.sort();
+                for (id, packet) in ids {
+                    writeln!(out, "            0x{:02x} => {},", id, packet).unwrap();
+                }
+                writeln!(out, "        }}").unwrap();
+            }
+            writeln!(out, "    }}").unwrap();
+        }
+        writeln!(
+            out,
+            "}}
+}}
+"
+        )
+        .unwrap();
+    }
+
+    // Caught here, as the macro would only report an unknown variant without saying which file it came from
+    if !undefined.is_empty() {
+        panic!(
+            "Packet structs referenced but never defined in {}:\n{}",
+            packets_path,
+            undefined.join("\n")
+        );
+    }
+
+    let arms = |call: &str| -> String {
+        versions
+            .iter()
+            .map(|(name, _, _)| {
+                format!(
+                    "        ProtocolVersion::Proto{0} => v{0}::{1},\n",
+                    name, call
+                )
+            })
+            .collect()
+    };
+
+    writeln!(out, "pub fn decode_packet(protocol: ProtocolVersion, p: &crate::net::codec::RawPacket, state: crate::net::ConnectionState, dir: crate::net::PacketDirection) -> crate::net::error::Result<crate::net::packets::Packet> {{").unwrap();
+    writeln!(out, "    let func = match protocol {{\n{}        _ => return Err(crate::net::Error::UnsupportedProtocol {{ protocol: protocol as i32 }}),\n    }};\n\n    func(p, state, dir)\n}}\n", arms("decode_packet")).unwrap();
+    writeln!(out, "/// Decodes a packet like `decode_packet`, appending the byte range of every field that was read to `fields`").unwrap();
+    writeln!(out, "pub fn decode_packet_traced(protocol: ProtocolVersion, p: &crate::net::codec::RawPacket, state: crate::net::ConnectionState, dir: crate::net::PacketDirection, fields: &mut Vec<crate::net::packet_helpers::FieldRange>) -> crate::net::error::Result<crate::net::packets::Packet> {{").unwrap();
+    writeln!(out, "    match protocol {{\n{}        _ => Err(crate::net::Error::UnsupportedProtocol {{ protocol: protocol as i32 }}),\n    }}\n}}\n", arms("decode_packet_traced(p, state, dir, fields)")).unwrap();
+    writeln!(out, "pub fn encode_packet(protocol: ProtocolVersion, p: &crate::net::packets::Packet, state: crate::net::ConnectionState, dir: crate::net::PacketDirection) -> crate::net::error::Result<crate::net::codec::RawPacket> {{").unwrap();
+    writeln!(out, "    let func = match protocol {{\n{}        _ => return Err(crate::net::Error::UnsupportedProtocol {{ protocol: protocol as i32 }}),\n    }};\n\n    func(p, state, dir)\n}}", arms("encode_packet")).unwrap();
+
+    out
+}
+
 fn main() -> std::io::Result<()> {
     let compiler = shaderc::Compiler::new().unwrap();
     let mut options = shaderc::CompileOptions::new().unwrap();
@@ -67,6 +216,13 @@ fn main() -> std::io::Result<()> {
     compile_shader!("src/shaders/debug_cube");
     compile_shader!("src/shaders/post/fxaa");
 
+    let versions = generate_versions("src/net/versions/protocol", "src/net/packets.rs");
+    fs::write(
+        Path::new(&std::env::var("OUT_DIR").unwrap()).join("versions.rs"),
+        versions,
+    )
+    .expect("Failed to write `versions.rs`");
+
     let path = Path::new(&std::env::var("OUT_DIR").unwrap()).join("build_info.rs");
     let mut output_file = File::create(&path).expect("Failed to create `build_info.rs`");
 
@@ -0,0 +1,392 @@
+//! Saves the chunks we receive as an Anvil world (the format used by 1.2 up to 1.12)
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{ensure, Context};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use flate2::{write::ZlibEncoder, Compression};
+use fnv::FnvHashMap;
+use nbt::Value;
+
+use crate::world::{ChunkColumn, ChunkManager, LevelInfo, CHUNK_SECTION_SIZE, CHUNK_SIZE_2D};
+
+const SECTOR_SIZE: usize = 4096;
+/// Chunks are stored in sectors, the size of which is stored in a single byte
+const MAX_CHUNK_SECTORS: usize = 255;
+/// level.dat version of the Anvil format
+const ANVIL_VERSION: i32 = 19133;
+
+/// Writes columns from a [ChunkManager] to a world directory, only saving the columns that changed since they were last saved
+pub struct WorldDownloader {
+    dir: PathBuf,
+    name: String,
+    /// Open region files, keyed by dimension and region coordinates
+    regions: FnvHashMap<(i32, i32, i32), RegionFile>,
+}
+
+impl WorldDownloader {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        Self {
+            name: dir
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "Downloaded world".to_string()),
+            dir,
+            regions: FnvHashMap::default(),
+        }
+    }
+
+    /// Saves every column that changed since it was last saved, returning the amount of columns saved
+    pub fn save_chunks(
+        &mut self,
+        chunks: &mut ChunkManager,
+        dimension: i32,
+    ) -> anyhow::Result<usize> {
+        let mut saved = 0;
+        for (coords, column) in chunks.chunks.iter_mut() {
+            if column.unsaved {
+                self.save_column(*coords, column, dimension)?;
+                column.unsaved = false;
+                saved += 1;
+            }
+        }
+
+        Ok(saved)
+    }
+
+    /// Saves the columns that are about to be removed, if they changed since they were last saved
+    pub fn save_evicted(
+        &mut self,
+        chunks: &mut ChunkManager,
+        dimension: i32,
+        evicted: &[(i32, i32)],
+    ) -> anyhow::Result<()> {
+        for coords in evicted {
+            if let Some(column) = chunks.chunks.get_mut(coords) {
+                if column.unsaved {
+                    self.save_column(*coords, column, dimension)?;
+                    column.unsaved = false;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn save_column(
+        &mut self,
+        coords: (i32, i32),
+        column: &ChunkColumn,
+        dimension: i32,
+    ) -> anyhow::Result<()> {
+        let region_coords = (dimension, coords.0 >> 5, coords.1 >> 5);
+        let region = match self.regions.get_mut(&region_coords) {
+            Some(region) => region,
+            None => {
+                let dir = self.dir.join(dimension_dir(dimension)).join("region");
+                std::fs::create_dir_all(&dir)?;
+                let path = dir.join(format!("r.{}.{}.mca", region_coords.1, region_coords.2));
+                let region = RegionFile::open(&path)
+                    .with_context(|| format!("Failed to open region file {}", path.display()))?;
+                self.regions.entry(region_coords).or_insert(region)
+            }
+        };
+
+        let mut data = ZlibEncoder::new(vec![], Compression::default());
+        column_to_nbt(coords, column)?.to_writer(&mut data)?;
+        region.write_chunk(coords.0, coords.1, &data.finish()?)
+    }
+
+    /// Writes level.dat, which holds the spawn position and time
+    pub fn save_level(&self, level: &LevelInfo) -> anyhow::Result<()> {
+        let last_played = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as i64;
+        let generator = match level.level_kind.as_str() {
+            "" => "default",
+            kind => kind,
+        };
+
+        let data = compound([
+            ("version", Value::Int(ANVIL_VERSION)),
+            ("initialized", Value::Byte(1)),
+            ("LevelName", Value::String(self.name.clone())),
+            ("generatorName", Value::String(generator.to_string())),
+            ("generatorVersion", Value::Int(1)),
+            ("RandomSeed", Value::Long(0)),
+            // Don't generate structures in the chunks we didn't download
+            ("MapFeatures", Value::Byte(0)),
+            ("GameType", Value::Int(level.gamemode as i32 & 0x07)),
+            ("hardcore", Value::Byte((level.gamemode & 0x08 != 0) as i8)),
+            ("allowCommands", Value::Byte(1)),
+            ("Difficulty", Value::Byte(level.difficulty as i8)),
+            ("SpawnX", Value::Int(level.spawn.0)),
+            ("SpawnY", Value::Int(level.spawn.1)),
+            ("SpawnZ", Value::Int(level.spawn.2)),
+            ("Time", Value::Long(level.world_age)),
+            ("DayTime", Value::Long(level.time_of_day.abs())),
+            ("LastPlayed", Value::Long(last_played)),
+            ("raining", Value::Byte(0)),
+            ("thundering", Value::Byte(0)),
+        ]);
+
+        let mut root = nbt::Blob::new();
+        root.insert("Data", Value::Compound(data))?;
+
+        std::fs::create_dir_all(&self.dir)?;
+        // Write to a temporary file first so a crash doesn't leave a corrupt level.dat behind
+        let tmp = self.dir.join("level.dat_new");
+        root.to_gzip_writer(&mut File::create(&tmp)?)?;
+        std::fs::rename(tmp, self.dir.join("level.dat"))?;
+
+        Ok(())
+    }
+}
+
+fn dimension_dir(dimension: i32) -> String {
+    match dimension {
+        0 => String::new(),
+        d => format!("DIM{}", d),
+    }
+}
+
+/// Converts a column to the NBT stored in region files
+pub fn column_to_nbt(coords: (i32, i32), column: &ChunkColumn) -> anyhow::Result<nbt::Blob> {
+    let mut sections = vec![];
+    for (y, section) in column.iter_sections() {
+        // The format only has room for y=0 to 255
+        if !(0..16).contains(&y) || section.blocks.iter().all(|b| b.is_air()) {
+            continue;
+        }
+
+        let mut blocks = vec![0i8; CHUNK_SECTION_SIZE];
+        let mut data = vec![0i8; CHUNK_SECTION_SIZE / 2];
+        let mut add = vec![0i8; CHUNK_SECTION_SIZE / 2];
+        for (i, block) in section.blocks.iter().enumerate() {
+            blocks[i] = block.id() as u8 as i8;
+            set_nibble(&mut data, i, block.meta());
+            set_nibble(&mut add, i, (block.id() >> 8) as u8);
+        }
+
+        let mut s = compound([
+            ("Y", Value::Byte(y as i8)),
+            ("Blocks", Value::ByteArray(blocks)),
+            ("Data", Value::ByteArray(data)),
+            ("BlockLight", Value::ByteArray(bytes(&section.light))),
+            ("SkyLight", Value::ByteArray(bytes(&section.skylight))),
+        ]);
+
+        // Only needed for ids above 255
+        if add.iter().any(|&a| a != 0) {
+            s.insert("Add".to_string(), Value::ByteArray(add));
+        }
+
+        sections.push(Value::Compound(s));
+    }
+
+    let mut block_entities = vec![];
+    for block_entity in column.block_entities.values() {
+        block_entities.push(blob_to_compound(&block_entity.nbt)?);
+    }
+
+    let level = compound([
+        ("xPos", Value::Int(coords.0)),
+        ("zPos", Value::Int(coords.1)),
+        ("LastUpdate", Value::Long(0)),
+        ("InhabitedTime", Value::Long(0)),
+        // Keep the game from generating terrain features or relighting on top of what we downloaded
+        ("TerrainPopulated", Value::Byte(1)),
+        ("LightPopulated", Value::Byte(1)),
+        ("V", Value::Byte(1)),
+        ("HeightMap", Value::IntArray(height_map(column))),
+        ("Biomes", Value::ByteArray(bytes(&column.biomes))),
+        ("Sections", Value::List(sections)),
+        ("Entities", Value::List(vec![])),
+        ("TileEntities", Value::List(block_entities)),
+    ]);
+
+    let mut root = nbt::Blob::new();
+    root.insert("Level", Value::Compound(level))?;
+
+    Ok(root)
+}
+
+/// Height of the highest non-air block + 1 for every x/z, used by the game for sky light and rain
+fn height_map(column: &ChunkColumn) -> Vec<i32> {
+    let mut heights = vec![0; CHUNK_SIZE_2D];
+    for (i, height) in heights.iter_mut().enumerate() {
+        let (x, z) = ((i & 0x0f) as i32, (i >> 4) as i32);
+        for (sy, section) in column.iter_sections().rev() {
+            if !(0..16).contains(&sy) {
+                continue;
+            }
+
+            if let Some(y) = (0..16)
+                .rev()
+                .find(|&y| !section.get_block(x, y, z).is_air())
+            {
+                *height = sy * 16 + y + 1;
+                break;
+            }
+        }
+    }
+
+    heights
+}
+
+fn compound<const N: usize>(entries: [(&str, Value); N]) -> nbt::Map<String, Value> {
+    entries
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect()
+}
+
+fn set_nibble(data: &mut [i8], index: usize, value: u8) {
+    let value = (value & 0x0f) as i8;
+    match index % 2 {
+        0 => data[index / 2] |= value,
+        _ => data[index / 2] |= value << 4,
+    }
+}
+
+fn bytes(data: &[u8]) -> Vec<i8> {
+    data.iter().map(|&b| b as i8).collect()
+}
+
+/// Block entity NBT is stored as a compound in the chunk, but [nbt::Blob] doesn't expose its contents
+fn blob_to_compound(blob: &nbt::Blob) -> anyhow::Result<Value> {
+    let mut data = vec![];
+    blob.to_writer(&mut data)?;
+
+    // Skip the tag type and name of the root compound
+    let mut r = &data[1..];
+    let name_length = r.read_u16::<BigEndian>()? as usize;
+    r = &r[name_length..];
+
+    Ok(Value::from_reader(0x0a, &mut r)?)
+}
+
+/// A region file, holding 32x32 chunks
+///
+/// The file starts with a table of the offset and size (in 4KiB sectors) of each chunk, followed by a table of timestamps.
+struct RegionFile {
+    file: File,
+    /// Offset and size in sectors of each chunk, indexed by `x + z * 32`
+    locations: Vec<(usize, usize)>,
+    /// Which sectors are in use, the first two hold the header
+    used: Vec<bool>,
+}
+
+impl RegionFile {
+    /// Opens a region file, creating it if it doesn't exist yet
+    fn open(path: &Path) -> anyhow::Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        let mut header = vec![0u8; SECTOR_SIZE * 2];
+        let length = file.metadata()?.len() as usize;
+        if length >= header.len() {
+            file.read_exact(&mut header)?;
+        } else {
+            file.set_len(0)?;
+            file.write_all(&header)?;
+        }
+
+        let mut used = vec![false; length.div_ceil(SECTOR_SIZE).max(2)];
+        used[0] = true;
+        used[1] = true;
+
+        let mut locations = Vec::with_capacity(1024);
+        let mut r = &header[..SECTOR_SIZE];
+        for _ in 0..1024 {
+            let location = r.read_u32::<BigEndian>()? as usize;
+            let (offset, count) = (location >> 8, location & 0xff);
+
+            // Ignore chunks pointing outside of the file, they'll be overwritten
+            if offset < 2 || offset + count > used.len() {
+                locations.push((0, 0));
+                continue;
+            }
+
+            used[offset..offset + count].fill(true);
+            locations.push((offset, count));
+        }
+
+        Ok(Self {
+            file,
+            locations,
+            used,
+        })
+    }
+
+    /// Writes zlib compressed chunk NBT, reusing the chunk's old sectors if it still fits
+    fn write_chunk(&mut self, chunk_x: i32, chunk_z: i32, data: &[u8]) -> anyhow::Result<()> {
+        let index = ((chunk_x & 31) + (chunk_z & 31) * 32) as usize;
+        // Length and compression type
+        let sectors = (data.len() + 5).div_ceil(SECTOR_SIZE);
+        ensure!(
+            sectors <= MAX_CHUNK_SECTORS,
+            "Chunk {}, {} is too large to store ({} bytes)",
+            chunk_x,
+            chunk_z,
+            data.len()
+        );
+
+        let (old_offset, old_count) = self.locations[index];
+        self.used[old_offset..old_offset + old_count].fill(false);
+
+        let offset = self.find_free(sectors);
+        if offset + sectors > self.used.len() {
+            self.used.resize(offset + sectors, false);
+        }
+        self.used[offset..offset + sectors].fill(true);
+
+        let mut buf = Vec::with_capacity(sectors * SECTOR_SIZE);
+        buf.write_u32::<BigEndian>(data.len() as u32 + 1)?;
+        // Zlib
+        buf.write_u8(2)?;
+        buf.extend_from_slice(data);
+        buf.resize(sectors * SECTOR_SIZE, 0);
+
+        self.file
+            .seek(SeekFrom::Start((offset * SECTOR_SIZE) as u64))?;
+        self.file.write_all(&buf)?;
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as u32;
+        self.file.seek(SeekFrom::Start(index as u64 * 4))?;
+        self.file
+            .write_u32::<BigEndian>(((offset << 8) | sectors) as u32)?;
+        self.file
+            .seek(SeekFrom::Start((SECTOR_SIZE + index * 4) as u64))?;
+        self.file.write_u32::<BigEndian>(timestamp)?;
+
+        self.locations[index] = (offset, sectors);
+
+        Ok(())
+    }
+
+    /// Returns the first run of `count` free sectors, which may extend past the end of the file
+    fn find_free(&self, count: usize) -> usize {
+        let mut run = 0;
+        for (i, used) in self.used.iter().enumerate() {
+            if *used {
+                run = 0;
+            } else {
+                run += 1;
+                if run == count {
+                    return i + 1 - count;
+                }
+            }
+        }
+
+        self.used.len() - run
+    }
+}
@@ -19,6 +19,12 @@ pub struct AudioManager {
     instances: Vec<ClipInstance>,
 }
 
+impl Default for AudioManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AudioManager {
     pub fn new() -> Self {
         let host = cpal::default_host();
@@ -0,0 +1,351 @@
+use std::{io::IsTerminal, path::PathBuf};
+
+use clap::{Parser, Subcommand, ValueEnum};
+
+use minecrab::{
+    net::{
+        capture::CaptureReader, codec::RawPacket, packet_helpers::FieldRange,
+        versions::decode_packet_traced, ConnectionState, PacketDirection, ProtocolVersion,
+    },
+    varint::ReadProtoExt,
+};
+
+/// Shows what the decoder makes of captured packets, field by field
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct CliArgs {
+    #[command(subcommand)]
+    input: Input,
+
+    /// Maximum number of bytes to show in hexdumps, 0 shows everything
+    #[arg(long, global = true, default_value_t = 256)]
+    max_bytes: usize,
+
+    /// Maximum number of lines to show of each decoded packet, 0 shows everything
+    #[arg(long, global = true, default_value_t = 64)]
+    max_lines: usize,
+
+    /// Don't highlight anything
+    #[arg(long, global = true)]
+    no_color: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Input {
+    /// Inspect the packets in a capture recorded with --capture
+    Capture {
+        path: PathBuf,
+
+        /// Only show packets with this id (eg. 0x21)
+        #[arg(long, value_parser = parse_packet_id)]
+        id: Option<i32>,
+
+        /// Only show packets going in this direction
+        #[arg(long, value_enum)]
+        direction: Option<Direction>,
+    },
+    /// Inspect a single packet given as hex, starting with the packet id
+    Hex {
+        hex: String,
+
+        #[arg(long)]
+        protocol: i32,
+
+        #[arg(long, value_enum)]
+        state: State,
+
+        #[arg(long, value_enum)]
+        direction: Direction,
+    },
+}
+
+#[derive(ValueEnum, Debug, Copy, Clone)]
+enum State {
+    Handshaking,
+    Status,
+    Login,
+    Play,
+}
+
+impl From<State> for ConnectionState {
+    fn from(s: State) -> Self {
+        match s {
+            State::Handshaking => ConnectionState::Handshaking,
+            State::Status => ConnectionState::Status,
+            State::Login => ConnectionState::Login,
+            State::Play => ConnectionState::Play,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Copy, Clone)]
+enum Direction {
+    Clientbound,
+    Serverbound,
+}
+
+impl From<Direction> for PacketDirection {
+    fn from(d: Direction) -> Self {
+        match d {
+            Direction::Clientbound => PacketDirection::Client,
+            Direction::Serverbound => PacketDirection::Server,
+        }
+    }
+}
+
+fn parse_packet_id(s: &str) -> anyhow::Result<i32> {
+    Ok(match s.strip_prefix("0x") {
+        Some(hex) => i32::from_str_radix(hex, 16)?,
+        None => s.parse()?,
+    })
+}
+
+const RED: &str = "\x1b[31m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// Bytes shown per field before the rest is elided
+const FIELD_PREVIEW_BYTES: usize = 16;
+
+struct Inspector {
+    max_bytes: usize,
+    max_lines: usize,
+    color: bool,
+}
+
+/// What happened when a packet was inspected, used for the capture summary
+struct Outcome {
+    decoded: bool,
+    leftover: usize,
+}
+
+impl Inspector {
+    fn paint(&self, color: &'static str, s: &str) -> String {
+        if self.color {
+            format!("{color}{s}{RESET}")
+        } else {
+            s.to_string()
+        }
+    }
+
+    fn inspect(
+        &self,
+        protocol: ProtocolVersion,
+        state: ConnectionState,
+        direction: PacketDirection,
+        rp: &RawPacket,
+    ) -> Outcome {
+        let mut fields = vec![];
+        let result = decode_packet_traced(protocol, rp, state, direction, &mut fields);
+        // Everything after the last field that was read is either left over or wasn't reached because of an error
+        let consumed = fields.iter().map(|f| f.range.end).max().unwrap_or(0);
+        let leftover = rp.data.len() - consumed.min(rp.data.len());
+
+        let name = match &result {
+            Ok(p) => <&'static str>::from(p),
+            Err(_) => "?",
+        };
+        println!(
+            "{:?}bound {:?} 0x{:02x} {} ({} bytes)",
+            direction,
+            state,
+            rp.id,
+            name,
+            rp.data.len()
+        );
+
+        if let Err(e) = &result {
+            println!("  {}", self.paint(RED, &format!("error: {}", e)));
+        }
+
+        self.print_fields(&rp.data, &fields);
+        self.print_hexdump(&rp.data, consumed);
+
+        if leftover > 0 {
+            let msg = match &result {
+                Ok(_) => format!("{} bytes left over at 0x{:04x}", leftover, consumed),
+                Err(_) => format!("{} bytes not read at 0x{:04x}", leftover, consumed),
+            };
+            println!("  {}", self.paint(RED, &msg));
+        }
+
+        if let Ok(p) = &result {
+            self.print_tree(&format!("{:#?}", p));
+        }
+
+        println!();
+
+        Outcome {
+            decoded: result.is_ok(),
+            leftover,
+        }
+    }
+
+    fn print_fields(&self, data: &[u8], fields: &[FieldRange]) {
+        let name_width = fields.iter().map(|f| f.name.len()).max().unwrap_or(0);
+        for f in fields {
+            let bytes = &data[f.range.clone()];
+            let mut preview = hex::encode(&bytes[..bytes.len().min(FIELD_PREVIEW_BYTES)]);
+            if bytes.len() > FIELD_PREVIEW_BYTES {
+                preview.push_str(&format!(" (+{} bytes)", bytes.len() - FIELD_PREVIEW_BYTES));
+            }
+
+            println!(
+                "  0x{:04x}..0x{:04x}  {:<name_width$}  {}",
+                f.range.start, f.range.end, f.name, preview
+            );
+        }
+    }
+
+    /// Prints 16 bytes per line, highlighting everything from `consumed` onwards
+    fn print_hexdump(&self, data: &[u8], consumed: usize) {
+        let limit = if self.max_bytes == 0 {
+            data.len()
+        } else {
+            self.max_bytes
+        };
+
+        // Make sure the leftover bytes are shown, even if they're past the limit
+        let leftover = if consumed < data.len() && consumed >= limit {
+            let start = consumed - consumed % 16;
+            Some(start..data.len().min(start + limit))
+        } else {
+            None
+        };
+
+        let mut shown_until = 0;
+        for range in std::iter::once(0..data.len().min(limit)).chain(leftover) {
+            if range.start > shown_until {
+                println!(
+                    "  {}",
+                    self.paint(DIM, &format!("... {} bytes", range.start - shown_until))
+                );
+            }
+
+            for (i, line) in data[range.clone()].chunks(16).enumerate() {
+                let offset = range.start + i * 16;
+                let mut hex = String::new();
+                let mut ascii = String::new();
+                for (j, b) in line.iter().enumerate() {
+                    let h = format!("{:02x} ", b);
+                    let c = if b.is_ascii_graphic() || *b == b' ' {
+                        (*b as char).to_string()
+                    } else {
+                        ".".to_string()
+                    };
+
+                    if offset + j >= consumed {
+                        hex.push_str(&self.paint(RED, &h));
+                        ascii.push_str(&self.paint(RED, &c));
+                    } else {
+                        hex.push_str(&h);
+                        ascii.push_str(&c);
+                    }
+                }
+
+                // Pad short lines so the ASCII column lines up
+                let padding = "   ".repeat(16 - line.len());
+                println!("  {:04x}  {}{} |{}|", offset, hex, padding, ascii);
+            }
+
+            shown_until = range.end;
+        }
+
+        if shown_until < data.len() {
+            println!(
+                "  {}",
+                self.paint(DIM, &format!("... {} bytes", data.len() - shown_until))
+            );
+        }
+    }
+
+    fn print_tree(&self, tree: &str) {
+        let lines: Vec<&str> = tree.lines().collect();
+        let limit = if self.max_lines == 0 {
+            lines.len()
+        } else {
+            self.max_lines
+        };
+
+        for line in lines.iter().take(limit) {
+            println!("  {}", line);
+        }
+
+        if lines.len() > limit {
+            println!(
+                "  {}",
+                self.paint(DIM, &format!("... {} more lines", lines.len() - limit))
+            );
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    pretty_env_logger::init();
+
+    let args = CliArgs::parse();
+    let inspector = Inspector {
+        max_bytes: args.max_bytes,
+        max_lines: args.max_lines,
+        color: !args.no_color && std::io::stdout().is_terminal(),
+    };
+
+    match args.input {
+        Input::Capture {
+            path,
+            id,
+            direction,
+        } => {
+            let capture = CaptureReader::open(&path)?;
+            let protocol = capture.protocol;
+            println!("Capture of protocol {:?}\n", protocol);
+
+            let direction = direction.map(PacketDirection::from);
+            let (mut shown, mut failed, mut overrun) = (0, 0, 0);
+            for (i, cp) in capture.enumerate() {
+                let cp = cp?;
+                if id.is_some_and(|id| id != cp.packet.id)
+                    || direction.is_some_and(|d| d != cp.direction)
+                {
+                    continue;
+                }
+
+                print!("#{} +{:.3}s ", i, cp.timestamp.as_secs_f64());
+                let outcome = inspector.inspect(protocol, cp.state, cp.direction, &cp.packet);
+
+                shown += 1;
+                if !outcome.decoded {
+                    failed += 1;
+                } else if outcome.leftover > 0 {
+                    overrun += 1;
+                }
+            }
+
+            println!(
+                "{} packets, {} failed to decode, {} with bytes left over",
+                shown, failed, overrun
+            );
+        }
+        Input::Hex {
+            hex,
+            protocol,
+            state,
+            direction,
+        } => {
+            let protocol = ProtocolVersion::from_protocol(protocol)?;
+            let hex: String = hex.chars().filter(|c| !c.is_whitespace()).collect();
+            let bytes = hex::decode(hex.trim_start_matches("0x"))?;
+
+            let mut c = std::io::Cursor::new(&bytes);
+            let id = c.read_varint()?;
+            let rp = RawPacket {
+                id,
+                data: bytes[c.position() as usize..].to_vec(),
+            };
+
+            inspector.inspect(protocol, state.into(), direction.into(), &rp);
+        }
+    }
+
+    Ok(())
+}
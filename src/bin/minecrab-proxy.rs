@@ -0,0 +1,380 @@
+#[macro_use]
+extern crate log;
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicI32, AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+};
+
+use clap::Parser;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use minecrab::{
+    ids::IdMap,
+    net::{
+        capture::CaptureWriter,
+        codec::{read_frame, MinecraftCodec},
+        packets::Packet,
+        versions::decode_packet,
+        wrapper::AbstractPacket,
+        ConnectionState, PacketDirection, ProtocolVersion,
+    },
+    varint::WriteProtoExt,
+};
+
+/// The handshake and status packets are the same in every version, so any protocol with packet mappings works
+const HANDSHAKE_PROTOCOL: ProtocolVersion = ProtocolVersion::Proto1_8;
+
+/// Forwards a client to a server, decoding and logging the packets going both ways
+///
+/// Only offline-mode servers can be proxied, as the proxy can't decrypt an online-mode session.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct CliArgs {
+    /// Address to listen on
+    #[arg(short, long, default_value = "127.0.0.1:25566")]
+    listen: String,
+
+    /// Address of the server to forward to
+    #[arg(short, long, default_value = "localhost:25565")]
+    upstream: String,
+
+    /// Only log packets with these names, either with the version suffix (MapChunk_47) or without (MapChunk)
+    #[arg(long, value_delimiter = ',')]
+    only: Vec<String>,
+
+    /// Don't log packets with these names
+    #[arg(long, value_delimiter = ',')]
+    hide: Vec<String>,
+
+    /// Cut off logged packets after this many characters, 0 logs everything
+    #[arg(long, default_value_t = 512)]
+    max_len: usize,
+
+    /// Record every session to this file, with the session number appended
+    #[arg(long)]
+    capture: Option<PathBuf>,
+}
+
+impl CliArgs {
+    fn should_log(&self, name: &str) -> bool {
+        let base_name = name.rsplit_once('_').map_or(name, |(base, _)| base);
+        let matches = |names: &[String]| names.iter().any(|n| n == name || n == base_name);
+
+        (self.only.is_empty() || matches(&self.only)) && !matches(&self.hide)
+    }
+}
+
+/// State shared between both directions of a proxied connection
+struct Session {
+    id: usize,
+    state: Mutex<ConnectionState>,
+    /// Set by the handshake, stays unset if the client uses a version we don't have mappings for
+    protocol: OnceLock<ProtocolVersion>,
+    /// Negative if compression is disabled
+    compression_threshold: AtomicI32,
+    capture: Mutex<Option<CaptureWriter>>,
+}
+
+impl Session {
+    fn new(id: usize) -> Self {
+        Self {
+            id,
+            state: Mutex::new(ConnectionState::Handshaking),
+            protocol: OnceLock::new(),
+            compression_threshold: AtomicI32::new(-1),
+            capture: Mutex::new(None),
+        }
+    }
+
+    fn state(&self) -> ConnectionState {
+        *self.state.lock().unwrap()
+    }
+
+    fn set_state(&self, state: ConnectionState) {
+        debug!("[{}] State switching to {:?}", self.id, state);
+        *self.state.lock().unwrap() = state;
+    }
+
+    /// Protocol used to decode packets in `state`
+    fn decode_protocol(&self, state: ConnectionState) -> Option<ProtocolVersion> {
+        match state {
+            ConnectionState::Handshaking | ConnectionState::Status => Some(HANDSHAKE_PROTOCOL),
+            ConnectionState::Login | ConnectionState::Play => self.protocol.get().copied(),
+        }
+    }
+
+    /// Applies the packets that change how the rest of the session is decoded
+    ///
+    /// They're translated to `AbstractPacket`s first, so every version's variant of them is handled.
+    fn update(&self, args: &CliArgs, packet: &Packet) -> anyhow::Result<()> {
+        let state = self.state();
+        // Only the handshake, login and compression packets matter here, which are small enough to clone
+        let ap = match (state, packet) {
+            (ConnectionState::Handshaking | ConnectionState::Login, _)
+            | (ConnectionState::Play, Packet::SetCompression_47(_)) => {
+                AbstractPacket::from_packet(packet.clone(), &IdMap::default())
+            }
+            _ => return Ok(()),
+        };
+
+        match ap {
+            Some(AbstractPacket::SetProtocol {
+                protocol_version,
+                next_state,
+                ..
+            }) => {
+                match ProtocolVersion::from_protocol(protocol_version.0) {
+                    Ok(protocol) => {
+                        let _ = self.protocol.set(protocol);
+                        if let Some(path) = &args.capture {
+                            let path = session_capture_path(path, self.id);
+                            *self.capture.lock().unwrap() =
+                                Some(CaptureWriter::create(&path, protocol)?);
+                            info!("[{}] Recording to {}", self.id, path.display());
+                        }
+                    }
+                    // Still forwarded, we just can't decode anything past the handshake
+                    Err(e) => warn!("[{}] {}", self.id, e),
+                }
+
+                self.set_state(next_state);
+            }
+            Some(AbstractPacket::SetCompression { threshold }) => self
+                .compression_threshold
+                .store(threshold.0, Ordering::Release),
+            Some(AbstractPacket::LoginSuccess { .. }) => self.set_state(ConnectionState::Play),
+            Some(AbstractPacket::EncryptionBeginClientbound { .. }) => {
+                anyhow::bail!(
+                    "Server requested encryption, only offline-mode servers can be proxied"
+                )
+            }
+            None if state == ConnectionState::Handshaking => {
+                anyhow::bail!("Invalid handshake {:?}", packet)
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Appends the session number to the capture path, eg. `capture.bin` becomes `capture-1.bin`
+fn session_capture_path(path: &Path, session: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{}-{}.{}", stem, session, ext.to_string_lossy()),
+        None => format!("{}-{}", stem, session),
+    };
+
+    path.with_file_name(name)
+}
+
+/// Forwards frames from `reader` to `writer` untouched, decoding them on the way
+async fn forward<R: AsyncRead + Unpin, W: AsyncWrite + Unpin>(
+    args: &CliArgs,
+    session: &Session,
+    direction: PacketDirection,
+    mut reader: R,
+    mut writer: W,
+) -> anyhow::Result<()> {
+    while let Some(frame) = read_frame(&mut reader).await? {
+        // The threshold is only read once the whole frame is in, as the other direction may have just changed it
+        let mut codec = MinecraftCodec::default();
+        codec.set_compression(session.compression_threshold.load(Ordering::Acquire));
+        let rp = codec.decode_frame(&frame)?;
+
+        let state = session.state();
+        let packet = session
+            .decode_protocol(state)
+            .map(|protocol| decode_packet(protocol, &rp, state, direction));
+
+        // State changes are applied before the packet is forwarded, so they're in place by the time the other side reacts to it
+        if let Some(Ok(p)) = &packet {
+            session.update(args, p)?;
+        }
+
+        // Recorded after the state changes, so the capture includes the handshake that started it
+        if let Some(capture) = session.capture.lock().unwrap().as_mut() {
+            capture.record(state, direction, &rp)?;
+        }
+
+        match &packet {
+            Some(Ok(p)) => {
+                let name: &'static str = p.into();
+                if args.should_log(name) {
+                    let mut s = format!("{:?}", p);
+                    if args.max_len != 0 && s.len() > args.max_len {
+                        let end = (0..=args.max_len)
+                            .rev()
+                            .find(|i| s.is_char_boundary(*i))
+                            .unwrap_or(0);
+                        s.truncate(end);
+                        s.push_str("...");
+                    }
+
+                    println!("[{}] {:?}bound {:?} {}", session.id, direction, state, s);
+                }
+            }
+            Some(Err(e)) => warn!("[{}] {}", session.id, e),
+            None => println!(
+                "[{}] {:?}bound {:?} 0x{:02x} ({} bytes, not decoded)",
+                session.id,
+                direction,
+                state,
+                rp.id,
+                rp.data.len()
+            ),
+        }
+
+        let mut buf = vec![];
+        buf.write_varint(frame.len() as i32)?;
+        buf.extend_from_slice(&frame);
+        writer.write_all(&buf).await?;
+    }
+
+    Ok(())
+}
+
+async fn proxy(args: Arc<CliArgs>, session: Arc<Session>, client: TcpStream) -> anyhow::Result<()> {
+    let upstream = TcpStream::connect(&args.upstream).await?;
+    client.set_nodelay(true)?;
+    upstream.set_nodelay(true)?;
+
+    let (client_read, client_write) = client.into_split();
+    let (upstream_read, upstream_write) = upstream.into_split();
+
+    // Whichever side closes first ends the session
+    tokio::select! {
+        r = forward(&args, &session, PacketDirection::Server, client_read, upstream_write) => r,
+        r = forward(&args, &session, PacketDirection::Client, upstream_read, client_write) => r,
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // Sessions opening and closing are logged at info level, so show them unless told otherwise
+    let mut logger = pretty_env_logger::formatted_builder();
+    match std::env::var("RUST_LOG") {
+        Ok(filters) => logger.parse_filters(&filters),
+        Err(_) => logger.filter_level(log::LevelFilter::Info),
+    };
+    logger.init();
+
+    let args = Arc::new(CliArgs::parse());
+    let listener = TcpListener::bind(&args.listen).await?;
+    info!("Forwarding {} to {}", args.listen, args.upstream);
+
+    let next_session = AtomicUsize::new(1);
+    loop {
+        let (client, addr) = listener.accept().await?;
+        let session = Arc::new(Session::new(next_session.fetch_add(1, Ordering::Relaxed)));
+        info!("[{}] {} connected", session.id, addr);
+
+        let args = args.clone();
+        tokio::spawn(async move {
+            let id = session.id;
+            match proxy(args, session, client).await {
+                Ok(()) => info!("[{}] Session closed", id),
+                Err(e) => info!("[{}] Session closed: {}", id, e),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use minecrab::{
+        net::{
+            packets::login::clientbound::{Compress_47, Success_735, Success_759},
+            versions::encode_packet,
+        },
+        varint::VarInt,
+    };
+
+    use super::*;
+
+    /// Like `receive_packet`, with the packet translated to the session's protocol first
+    fn receive(
+        session: &Session,
+        args: &CliArgs,
+        packet: AbstractPacket,
+        direction: PacketDirection,
+    ) {
+        let state = session.state();
+        let protocol = session.decode_protocol(state).unwrap();
+        let p = packet.to_packet(protocol as i32).unwrap();
+        receive_packet(session, args, p, direction);
+    }
+
+    /// Sends `packet` through the wire format of the session's current state, then applies it
+    fn receive_packet(
+        session: &Session,
+        args: &CliArgs,
+        packet: Packet,
+        direction: PacketDirection,
+    ) {
+        let state = session.state();
+        let protocol = session.decode_protocol(state).unwrap();
+        let rp = encode_packet(protocol, &packet, state, direction).unwrap();
+        let p = decode_packet(protocol, &rp, state, direction).unwrap();
+        session.update(args, &p).unwrap();
+    }
+
+    fn login(protocol: ProtocolVersion, success: Packet) {
+        let args = CliArgs::parse_from(["minecrab-proxy"]);
+        let session = Session::new(1);
+
+        receive(
+            &session,
+            &args,
+            AbstractPacket::SetProtocol {
+                protocol_version: VarInt(protocol as i32),
+                server_host: "localhost".to_string(),
+                server_port: 25565,
+                next_state: ConnectionState::Login,
+            },
+            PacketDirection::Server,
+        );
+        assert_eq!(session.state(), ConnectionState::Login);
+        assert_eq!(session.protocol.get(), Some(&protocol));
+
+        let compress = Compress_47 {
+            threshold: VarInt(256),
+        };
+        receive_packet(
+            &session,
+            &args,
+            Packet::Compress_47(compress),
+            PacketDirection::Client,
+        );
+        assert_eq!(session.compression_threshold.load(Ordering::Acquire), 256);
+
+        receive_packet(&session, &args, success, PacketDirection::Client);
+        assert_eq!(session.state(), ConnectionState::Play);
+    }
+
+    #[test]
+    fn login_1_16_5() {
+        let success = Success_735 {
+            username: "Steve".to_string(),
+            ..Default::default()
+        };
+        login(ProtocolVersion::Proto1_16_5, Packet::Success_735(success));
+    }
+
+    #[test]
+    fn login_1_19_2() {
+        let success = Success_759 {
+            username: "Steve".to_string(),
+            ..Default::default()
+        };
+        login(ProtocolVersion::Proto1_19_2, Packet::Success_759(success));
+    }
+}
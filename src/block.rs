@@ -0,0 +1,391 @@
+use std::path::Path;
+
+use anyhow::{ensure, Context};
+use cgmath::Point3;
+use collision::Aabb3;
+use serde::Deserialize;
+
+use crate::{
+    ids::{IdMap, StateName},
+    world::BlockState,
+};
+
+/// Block data for 1.7 up to 1.12.2, block ids only ever got added in those versions
+const BLOCKS_1_12_2: &str = include_str!("../data/blocks/1.12.2.json");
+
+/// Legacy blocks whose properties are used for the blocks added in 1.13 and later, by the end of their name
+///
+/// The first match wins, so longer suffixes go before the shorter ones they end with. Blocks that match nothing are
+/// full cubes, which most of them are.
+const TEMPLATES: &[(&str, &str)] = &[
+    ("air", "air"),
+    ("bubble_column", "water"),
+    ("cauldron", "cauldron"),
+    ("_wall_banner", "wall_banner"),
+    ("_banner", "standing_banner"),
+    ("_wall_sign", "wall_sign"),
+    ("_sign", "standing_sign"),
+    ("torch", "torch"),
+    ("lantern", "torch"),
+    ("_pressure_plate", "wooden_pressure_plate"),
+    ("_button", "wooden_button"),
+    ("_trapdoor", "trapdoor"),
+    ("_door", "wooden_door"),
+    ("_fence_gate", "fence_gate"),
+    ("_fence", "fence"),
+    ("_wall", "cobblestone_wall"),
+    ("_stairs", "stone_stairs"),
+    ("_slab", "stone_slab"),
+    ("_carpet", "carpet"),
+    ("_bed", "bed"),
+    ("_leaves", "leaves"),
+    ("azalea", "leaves"),
+    ("_sapling", "sapling"),
+    ("_propagule", "sapling"),
+    ("_melon_stem", "melon_stem"),
+    ("_pumpkin_stem", "pumpkin_stem"),
+    ("dripleaf", "tallgrass"),
+    ("dripleaf_stem", "tallgrass"),
+    ("_stem", "log"),
+    ("_hyphae", "log"),
+    ("_log", "log"),
+    ("_wood", "log"),
+    ("_planks", "planks"),
+    ("coral", "tallgrass"),
+    ("_fan", "tallgrass"),
+    ("kelp", "tallgrass"),
+    ("kelp_plant", "tallgrass"),
+    ("seagrass", "tallgrass"),
+    ("sea_pickle", "tallgrass"),
+    ("muddy_mangrove_roots", "dirt"),
+    ("mangrove_roots", "leaves"),
+    ("_roots", "tallgrass"),
+    ("_sprouts", "tallgrass"),
+    ("_fungus", "brown_mushroom"),
+    ("_bush", "tallgrass"),
+    ("cornflower", "red_flower"),
+    ("lily_of_the_valley", "red_flower"),
+    ("wither_rose", "red_flower"),
+    ("spore_blossom", "red_flower"),
+    ("_vines", "vine"),
+    ("_vines_plant", "vine"),
+    ("glow_lichen", "vine"),
+    ("sculk_vein", "vine"),
+    ("_bud", "tallgrass"),
+    ("amethyst_cluster", "tallgrass"),
+    ("pointed_dripstone", "cactus"),
+    ("bamboo", "cactus"),
+    ("candle", "torch"),
+    ("cake", "cake"),
+    ("_head", "skull"),
+    ("_skull", "skull"),
+    ("_glass", "glass"),
+    ("fire", "fire"),
+    ("chain", "iron_bars"),
+    ("lightning_rod", "end_rod"),
+    ("scaffolding", "ladder"),
+    ("_ice", "packed_ice"),
+    ("froglight", "sea_lantern"),
+    ("shroomlight", "glowstone"),
+    ("light", "air"),
+    ("conduit", "beacon"),
+    ("shulker_box", "purple_shulker_box"),
+    ("furnace", "furnace"),
+    ("smoker", "furnace"),
+    ("honey_block", "slime"),
+    ("dirt_path", "grass_path"),
+    ("lectern", "enchanting_table"),
+    ("grindstone", "anvil"),
+    ("stonecutter", "daylight_detector"),
+    ("sculk_sensor", "daylight_detector"),
+    ("sculk_shrieker", "daylight_detector"),
+    ("frogspawn", "waterlily"),
+    ("turtle_egg", "cake"),
+];
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionShape {
+    None,
+    Full,
+    /// Min and max corners, relative to the block position. The height can go past 1 for blocks like fences.
+    Box([f32; 6]),
+}
+
+impl CollisionShape {
+    /// Bounding box of the shape for a block at the given position
+    pub fn aabb(&self, x: i32, y: i32, z: i32) -> Option<Aabb3<f32>> {
+        let [x0, y0, z0, x1, y1, z1] = match *self {
+            Self::None => return None,
+            Self::Full => [0., 0., 0., 1., 1., 1.],
+            Self::Box(b) => b,
+        };
+
+        let (x, y, z) = (x as f32, y as f32, z as f32);
+        Some(Aabb3::new(
+            Point3::new(x + x0, y + y0, z + z0),
+            Point3::new(x + x1, y + y1, z + z1),
+        ))
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderLayer {
+    /// Not meshed at all
+    Invisible,
+    Solid,
+    /// Fully transparent holes in the texture, like glass
+    Cutout,
+    /// Cutout blocks that show the faces between them, like leaves
+    CutoutMipped,
+    Translucent,
+}
+
+/// Texture atlas indices for each face
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BlockTextures {
+    pub top: u8,
+    pub bottom: u8,
+    pub side: u8,
+}
+
+impl BlockTextures {
+    pub fn all(index: u8) -> Self {
+        Self {
+            top: index,
+            bottom: index,
+            side: index,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    /// Namespaced name, such as `minecraft:stone`
+    pub name: String,
+    pub collision: CollisionShape,
+    /// Full opaque cubes hide the faces of their neighbours and darken their corners
+    pub opaque: bool,
+    pub light_emission: u8,
+    /// How much light is lost when passing through the block, on top of the 1 level lost per block travelled
+    pub light_filter: u8,
+    pub render_layer: RenderLayer,
+    /// How long the block takes to break, negative for unbreakable blocks
+    pub hardness: f32,
+    pub textures: BlockTextures,
+}
+
+impl Block {
+    /// Used for block states that aren't in the data file, these are treated like stone
+    fn unknown() -> Self {
+        Self {
+            name: "minecraft:unknown".to_string(),
+            collision: CollisionShape::Full,
+            opaque: true,
+            light_emission: 0,
+            light_filter: 15,
+            render_layer: RenderLayer::Solid,
+            hardness: 1.,
+            textures: BlockTextures::all(1),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawBlockFile {
+    blocks: Vec<RawBlock>,
+}
+
+/// A single texture index can be used for all faces
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawTextures {
+    All(u8),
+    Faces(BlockTextures),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawMeta {
+    Single(u8),
+    List(Vec<u8>),
+}
+
+/// A block as stored in the data file, everything but the id, name and hardness is optional
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawBlock {
+    id: u16,
+    /// The entry only applies to these metadata values, all of them if it's missing
+    meta: Option<RawMeta>,
+    name: String,
+    #[serde(default = "default_collision")]
+    collision: CollisionShape,
+    #[serde(default = "default_true")]
+    opaque: bool,
+    #[serde(default)]
+    light_emission: u8,
+    #[serde(default = "default_light_filter")]
+    light_filter: u8,
+    #[serde(default = "default_render_layer")]
+    render_layer: RenderLayer,
+    hardness: f32,
+    /// Defaults to the atlas index matching the block id
+    textures: Option<RawTextures>,
+}
+
+fn default_collision() -> CollisionShape {
+    CollisionShape::Full
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_light_filter() -> u8 {
+    15
+}
+
+fn default_render_layer() -> RenderLayer {
+    RenderLayer::Solid
+}
+
+/// Properties of every block state, used by physics, meshing and lighting
+#[derive(Debug, Clone)]
+pub struct BlockRegistry {
+    blocks: Vec<Block>,
+    /// Index into `blocks` for each block state, 0 being the unknown block
+    states: Vec<u16>,
+}
+
+impl Default for BlockRegistry {
+    fn default() -> Self {
+        Self::from_json(BLOCKS_1_12_2).expect("Built-in block data is invalid")
+    }
+}
+
+impl BlockRegistry {
+    /// The built-in block data along with the blocks `ids` added after 1.12.2
+    pub fn for_ids(ids: &IdMap) -> Self {
+        let mut registry = Self::default();
+        registry.add_new_states(ids);
+        registry
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::from_json(&data).with_context(|| format!("Failed to load {}", path.display()))
+    }
+
+    /// Parses block data, later entries override earlier ones so entries for specific metadata go after the one for the whole block
+    pub fn from_json(data: &str) -> anyhow::Result<Self> {
+        let file: RawBlockFile = serde_json::from_str(data)?;
+
+        let mut blocks = vec![Block::unknown()];
+        let mut states = vec![0u16; 1 << 16];
+        for raw in file.blocks {
+            ensure!(
+                raw.id < 1 << 12,
+                "Block id {} of {} is too large",
+                raw.id,
+                raw.name
+            );
+            ensure!(
+                raw.light_emission <= 15 && raw.light_filter <= 15,
+                "Light levels of {} are out of range",
+                raw.name
+            );
+
+            let metas = match raw.meta {
+                None => (0..16).collect(),
+                Some(RawMeta::Single(meta)) => vec![meta],
+                Some(RawMeta::List(metas)) => metas,
+            };
+            ensure!(
+                metas.iter().all(|&m| m < 16),
+                "Invalid metadata for {}",
+                raw.name
+            );
+
+            let textures = match raw.textures {
+                Some(RawTextures::All(index)) => BlockTextures::all(index),
+                Some(RawTextures::Faces(textures)) => textures,
+                // The atlas only goes up to 255
+                None => BlockTextures::all(raw.id as u8),
+            };
+
+            let index = blocks.len() as u16;
+            blocks.push(Block {
+                name: raw.name,
+                collision: raw.collision,
+                opaque: raw.opaque,
+                light_emission: raw.light_emission,
+                light_filter: raw.light_filter,
+                render_layer: raw.render_layer,
+                hardness: raw.hardness,
+                textures,
+            });
+
+            for meta in metas {
+                states[BlockState::new(raw.id, meta).0 as usize] = index;
+            }
+        }
+
+        Ok(Self { blocks, states })
+    }
+
+    /// Adds the states of blocks added in 1.13 and later, see `TEMPLATES` for where their properties come from
+    pub fn add_new_states(&mut self, ids: &IdMap) {
+        for (state, name) in ids.new_block_states() {
+            let block = self.derive(name);
+            self.states[state.0 as usize] = self.blocks.len() as u16;
+            self.blocks.push(block);
+        }
+    }
+
+    /// Properties of a block the data file doesn't know about, based on a legacy block with a similar name
+    fn derive(&self, name: &StateName) -> Block {
+        let path = name.name.strip_prefix("minecraft:").unwrap_or(&name.name);
+        let template = if path.starts_with("potted_") {
+            "flower_pot"
+        } else {
+            TEMPLATES
+                .iter()
+                .find(|(suffix, _)| path.ends_with(suffix))
+                .map_or("stone", |(_, template)| template)
+        };
+
+        let template = format!("minecraft:{template}");
+        let mut block = self
+            .blocks
+            .iter()
+            .find(|b| b.name == template)
+            .cloned()
+            .expect("Template of a new block is missing from the block data");
+        block.name = name.name.clone();
+
+        // Slabs are bottom halves unless the state says otherwise
+        match name.property("type") {
+            Some("top") if path.ends_with("_slab") => {
+                block.collision = CollisionShape::Box([0., 0.5, 0., 1., 1., 1.])
+            }
+            Some("double") if path.ends_with("_slab") => {
+                block.collision = CollisionShape::Full;
+                block.opaque = true;
+            }
+            _ => {}
+        }
+        if name.property("lit") == Some("false") {
+            block.light_emission = 0;
+        }
+
+        block
+    }
+
+    pub fn get(&self, state: BlockState) -> &Block {
+        &self.blocks[self.states[state.0 as usize] as usize]
+    }
+}
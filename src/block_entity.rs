@@ -0,0 +1,258 @@
+use nbt::Value;
+
+/// The types of block entities we have typed views for
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BlockEntityKind {
+    Sign,
+    Skull,
+    Banner,
+    MobSpawner,
+    Beacon,
+    Chest,
+    FlowerPot,
+    Other,
+}
+
+impl BlockEntityKind {
+    /// Maps both the pre-1.11 (`MobSpawner`) and namespaced (`minecraft:mob_spawner`) ids
+    pub fn from_id(id: &str) -> Self {
+        match id.strip_prefix("minecraft:").unwrap_or(id) {
+            "Sign" | "sign" => Self::Sign,
+            "Skull" | "skull" => Self::Skull,
+            "Banner" | "banner" => Self::Banner,
+            "MobSpawner" | "mob_spawner" => Self::MobSpawner,
+            "Beacon" | "beacon" => Self::Beacon,
+            "Chest" | "chest" => Self::Chest,
+            "FlowerPot" | "flower_pot" => Self::FlowerPot,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// A block entity as sent by the server, the NBT is kept as-is
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockEntity {
+    pub nbt: nbt::Blob,
+}
+
+impl BlockEntity {
+    pub fn new(nbt: nbt::Blob) -> Self {
+        Self { nbt }
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.nbt.get("id").and_then(as_str)
+    }
+
+    pub fn kind(&self) -> BlockEntityKind {
+        self.id()
+            .map(BlockEntityKind::from_id)
+            .unwrap_or(BlockEntityKind::Other)
+    }
+
+    pub fn view(&self) -> BlockEntityView<'_> {
+        let nbt = &self.nbt;
+        match self.kind() {
+            BlockEntityKind::Sign => BlockEntityView::Sign(Sign(nbt)),
+            BlockEntityKind::Skull => BlockEntityView::Skull(Skull(nbt)),
+            BlockEntityKind::Banner => BlockEntityView::Banner(Banner(nbt)),
+            BlockEntityKind::MobSpawner => BlockEntityView::MobSpawner(MobSpawner(nbt)),
+            BlockEntityKind::Beacon => BlockEntityView::Beacon(Beacon(nbt)),
+            BlockEntityKind::Chest => BlockEntityView::Chest(Chest(nbt)),
+            BlockEntityKind::FlowerPot => BlockEntityView::FlowerPot(FlowerPot(nbt)),
+            BlockEntityKind::Other => BlockEntityView::Other,
+        }
+    }
+}
+
+pub enum BlockEntityView<'a> {
+    Sign(Sign<'a>),
+    Skull(Skull<'a>),
+    Banner(Banner<'a>),
+    MobSpawner(MobSpawner<'a>),
+    Beacon(Beacon<'a>),
+    Chest(Chest<'a>),
+    FlowerPot(FlowerPot<'a>),
+    Other,
+}
+
+/// An item id, numeric before 1.8 and namespaced after
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ItemId<'a> {
+    Numeric(i32),
+    Named(&'a str),
+}
+
+impl<'a> ItemId<'a> {
+    fn from_value(v: &'a Value) -> Option<Self> {
+        as_int(v)
+            .map(Self::Numeric)
+            .or_else(|| as_str(v).map(Self::Named))
+    }
+}
+
+pub struct Sign<'a>(&'a nbt::Blob);
+
+impl<'a> Sign<'a> {
+    /// The raw text of each line, this is a JSON chat component from 1.8 onwards
+    pub fn lines(&self) -> [&'a str; 4] {
+        ["Text1", "Text2", "Text3", "Text4"].map(|k| self.0.get(k).and_then(as_str).unwrap_or(""))
+    }
+}
+
+pub struct Skull<'a>(&'a nbt::Blob);
+
+impl<'a> Skull<'a> {
+    /// 0: skeleton, 1: wither skeleton, 2: zombie, 3: player, 4: creeper, 5: dragon
+    pub fn skull_type(&self) -> i32 {
+        self.0.get("SkullType").and_then(as_int).unwrap_or(0)
+    }
+
+    /// Rotation in 1/16ths of a turn, only used when placed on the floor
+    pub fn rotation(&self) -> i32 {
+        self.0.get("Rot").and_then(as_int).unwrap_or(0)
+    }
+
+    /// Name of the player whose head this is
+    pub fn owner(&self) -> Option<&'a str> {
+        match self.0.get("Owner") {
+            Some(Value::Compound(owner)) => owner.get("Name").and_then(as_str),
+            // 1.7
+            _ => self.0.get("ExtraType").and_then(as_str),
+        }
+    }
+}
+
+pub struct Banner<'a>(&'a nbt::Blob);
+
+impl<'a> Banner<'a> {
+    pub fn base_color(&self) -> i32 {
+        self.0.get("Base").and_then(as_int).unwrap_or(0)
+    }
+
+    /// Pattern code and color of each layer, from bottom to top
+    pub fn patterns(&self) -> Vec<(&'a str, i32)> {
+        compounds(self.0.get("Patterns"))
+            .filter_map(|p| {
+                Some((
+                    p.get("Pattern").and_then(as_str)?,
+                    p.get("Color").and_then(as_int)?,
+                ))
+            })
+            .collect()
+    }
+}
+
+pub struct MobSpawner<'a>(&'a nbt::Blob);
+
+impl<'a> MobSpawner<'a> {
+    /// Entity shown spinning inside the spawner
+    pub fn entity_id(&self) -> Option<&'a str> {
+        match self.0.get("SpawnData") {
+            // 1.9+
+            Some(Value::Compound(data)) => data.get("id").and_then(as_str),
+            _ => self.0.get("EntityId").and_then(as_str),
+        }
+    }
+
+    /// Ticks until the next spawn attempt
+    pub fn delay(&self) -> i32 {
+        self.0.get("Delay").and_then(as_int).unwrap_or(0)
+    }
+}
+
+pub struct Beacon<'a>(&'a nbt::Blob);
+
+impl Beacon<'_> {
+    /// Amount of pyramid levels, 0 if the beacon is inactive
+    pub fn levels(&self) -> i32 {
+        self.0.get("Levels").and_then(as_int).unwrap_or(0)
+    }
+
+    /// Potion id of the primary effect, 0 if none
+    pub fn primary_effect(&self) -> i32 {
+        self.0.get("Primary").and_then(as_int).unwrap_or(0)
+    }
+
+    /// Potion id of the secondary effect, 0 if none
+    pub fn secondary_effect(&self) -> i32 {
+        self.0.get("Secondary").and_then(as_int).unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChestItem<'a> {
+    pub slot: i32,
+    pub id: ItemId<'a>,
+    pub count: i32,
+    pub damage: i32,
+}
+
+pub struct Chest<'a>(&'a nbt::Blob);
+
+impl<'a> Chest<'a> {
+    pub fn custom_name(&self) -> Option<&'a str> {
+        self.0.get("CustomName").and_then(as_str)
+    }
+
+    /// Servers usually don't send the contents of chests, these are only filled in by some servers and world files
+    pub fn items(&self) -> Vec<ChestItem<'a>> {
+        compounds(self.0.get("Items"))
+            .filter_map(|i| {
+                Some(ChestItem {
+                    slot: i.get("Slot").and_then(as_int)?,
+                    id: ItemId::from_value(i.get("id")?)?,
+                    count: i.get("Count").and_then(as_int).unwrap_or(1),
+                    damage: i.get("Damage").and_then(as_int).unwrap_or(0),
+                })
+            })
+            .collect()
+    }
+}
+
+pub struct FlowerPot<'a>(&'a nbt::Blob);
+
+impl<'a> FlowerPot<'a> {
+    /// The plant in the pot, `None` if it's empty
+    pub fn item(&self) -> Option<ItemId<'a>> {
+        match ItemId::from_value(self.0.get("Item")?)? {
+            ItemId::Numeric(0) | ItemId::Named("") | ItemId::Named("minecraft:air") => None,
+            item => Some(item),
+        }
+    }
+
+    /// Damage value of the plant, such as the type of sapling
+    pub fn data(&self) -> i32 {
+        self.0.get("Data").and_then(as_int).unwrap_or(0)
+    }
+}
+
+fn as_str(v: &Value) -> Option<&str> {
+    match v {
+        Value::String(s) => Some(s),
+        _ => None,
+    }
+}
+
+/// Reads any integer tag that fits in an i32, the exact type differs between versions
+fn as_int(v: &Value) -> Option<i32> {
+    match *v {
+        Value::Byte(v) => Some(v as i32),
+        Value::Short(v) => Some(v as i32),
+        Value::Int(v) => Some(v),
+        _ => None,
+    }
+}
+
+/// Iterates over the compounds in a list tag
+fn compounds(v: Option<&Value>) -> impl Iterator<Item = &nbt::Map<String, Value>> {
+    let list = match v {
+        Some(Value::List(list)) => list.as_slice(),
+        _ => &[],
+    };
+
+    list.iter().filter_map(|v| match v {
+        Value::Compound(c) => Some(c),
+        _ => None,
+    })
+}
@@ -0,0 +1,526 @@
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+
+use cgmath::{Point3, Vector3};
+use tokio::net::TcpStream;
+
+use crate::{
+    anvil::WorldDownloader,
+    block::BlockRegistry,
+    ecs::{update_interpolation, update_velocity, EntityRegistry, TICK_DELTA},
+    net::{
+        auth::Authenticator,
+        capture::CaptureWriter,
+        connection::{ClientConnection, DisconnectReason},
+        wrapper::{AbstractPacket, PositionFlags},
+        ProtocolVersion,
+    },
+    physics,
+    world::{BlockAction, BlockState, ChunkManager, LevelInfo},
+};
+
+// Vanilla player movement constants, applied once per tick
+const GRAVITY: f64 = 0.08;
+const AIR_DRAG: f64 = 0.98;
+const GROUND_FRICTION: f64 = 0.546;
+const AIR_FRICTION: f64 = 0.91;
+
+pub struct PlayerState {
+    /// Position of the player's feet
+    pub position: Point3<f64>,
+    pub yaw: f32,
+    pub pitch: f32,
+    /// In blocks per tick
+    pub velocity: Vector3<f64>,
+    pub on_ground: bool,
+}
+
+pub enum ClientEvent {
+    /// The server sent our initial position
+    Spawned,
+    /// The server moved the player, `PlayerState` has already been updated
+    Teleported,
+    /// Packets that aren't handled by the client itself
+    Packet(AbstractPacket),
+    /// The connection was closed, no more events will follow
+    Disconnected(DisconnectReason),
+}
+
+/// Connection, world and entity state for a single player, without any rendering or audio
+pub struct Client {
+    pub connection: ClientConnection,
+    pub chunks: ChunkManager,
+    pub world: hecs::World,
+    /// Maps server entity ids to entities in `world`
+    pub entities: EntityRegistry,
+    pub player: PlayerState,
+    pub level: LevelInfo,
+    /// Saves the chunks we receive to disk if set
+    pub downloader: Option<WorldDownloader>,
+
+    /// Apply gravity and collisions to the player every tick, disable this if the frontend moves the player itself
+    pub simulate_player: bool,
+
+    /// Set once the server has sent our initial position
+    spawned: bool,
+    disconnect_reason: Option<DisconnectReason>,
+    events: VecDeque<ClientEvent>,
+}
+
+impl Client {
+    /// Connects to a server and logs in, returning once the connection has entered the play state
+    ///
+    /// If `capture` is set, the whole session (including the login) is recorded to it.
+    pub async fn connect(
+        address: &str,
+        port: u16,
+        username: &str,
+        protocol: ProtocolVersion,
+        authenticator: Arc<dyn Authenticator>,
+        capture: Option<CaptureWriter>,
+    ) -> anyhow::Result<Self> {
+        let stream = TcpStream::connect(format!("{}:{}", address, port)).await?;
+        let mut connection =
+            ClientConnection::from_stream_with_auth(stream, protocol, authenticator);
+        if let Some(capture) = capture {
+            connection.start_capture(capture);
+        }
+
+        connection
+            .login(address.to_string(), port, username.to_string())
+            .await?;
+
+        Ok(Self::new(connection))
+    }
+
+    /// `connection` should already be in the play state
+    pub fn new(connection: ClientConnection) -> Self {
+        let mut chunks = ChunkManager::new();
+        chunks.blocks = Arc::new(BlockRegistry::for_ids(&connection.ids));
+        chunks.ids = connection.ids.clone();
+
+        Self {
+            connection,
+            chunks,
+            world: hecs::World::new(),
+            entities: EntityRegistry::new(),
+            player: PlayerState {
+                position: Point3::new(0., 0., 0.),
+                yaw: 0.,
+                pitch: 0.,
+                velocity: Vector3::new(0., 0., 0.),
+                on_ground: false,
+            },
+            level: LevelInfo::default(),
+            downloader: None,
+            simulate_player: true,
+            spawned: false,
+            disconnect_reason: None,
+            events: VecDeque::new(),
+        }
+    }
+
+    pub fn spawned(&self) -> bool {
+        self.spawned
+    }
+
+    /// Why the connection was closed, if it has been
+    pub fn disconnect_reason(&self) -> Option<&DisconnectReason> {
+        self.disconnect_reason.as_ref()
+    }
+
+    /// Ticks the client at a fixed 20 TPS until it's disconnected or an error occurs
+    pub async fn run(mut self) -> anyhow::Result<DisconnectReason> {
+        let mut interval = tokio::time::interval(Duration::from_secs_f32(TICK_DELTA));
+        loop {
+            interval.tick().await;
+            self.tick()?;
+
+            if let Some(reason) = self.disconnect_reason.take() {
+                return Ok(reason);
+            }
+
+            // Nobody is listening
+            self.events.clear();
+        }
+    }
+
+    /// Ticks the client at a fixed 20 TPS until the server has sent our initial position
+    pub async fn wait_for_spawn(&mut self) -> anyhow::Result<()> {
+        let mut interval = tokio::time::interval(Duration::from_secs_f32(TICK_DELTA));
+        while !self.spawned {
+            interval.tick().await;
+            self.tick()?;
+
+            if let Some(reason) = &self.disconnect_reason {
+                anyhow::bail!("Disconnected before spawning: {}", reason);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handles incoming packets and advances the simulation by a single tick, does nothing once disconnected
+    pub fn tick(&mut self) -> anyhow::Result<()> {
+        if self.disconnect_reason.is_some() {
+            return Ok(());
+        }
+
+        loop {
+            match self.connection.read() {
+                Ok(Some(p)) => self.handle_packet(p)?,
+                Ok(None) => break,
+                Err(reason) => {
+                    self.save_world();
+                    self.disconnect_reason = Some(reason.clone());
+                    self.events.push_back(ClientEvent::Disconnected(reason));
+                    return Ok(());
+                }
+            }
+        }
+
+        // Chunks are saved as they arrive, rather than when they're unloaded
+        self.save_chunks();
+
+        if !self.spawned {
+            return Ok(());
+        }
+
+        update_velocity(&mut self.world);
+        update_interpolation(&mut self.world, TICK_DELTA);
+
+        if self.simulate_player {
+            self.update_physics();
+        }
+
+        self.connection
+            .write(AbstractPacket::PositionLookServerBound {
+                pos: self.player.position,
+                yaw: self.player.yaw,
+                pitch: self.player.pitch,
+                on_ground: self.player.on_ground,
+            })
+            .ok();
+
+        Ok(())
+    }
+
+    /// Saves the columns that changed since they were last saved, if we're downloading the world
+    pub fn save_chunks(&mut self) {
+        if let Some(downloader) = &mut self.downloader {
+            if let Err(e) = downloader.save_chunks(&mut self.chunks, self.level.dimension) {
+                error!("Failed to save chunks: {e:#}");
+            }
+        }
+    }
+
+    /// Saves all unsaved columns and level.dat, if we're downloading the world
+    pub fn save_world(&mut self) {
+        self.save_chunks();
+        if let Some(downloader) = &self.downloader {
+            if let Err(e) = downloader.save_level(&self.level) {
+                error!("Failed to save level.dat: {e:#}");
+            }
+        }
+    }
+
+    /// Unloads every column `keep` returns false for, saving them first if we're downloading the world
+    pub fn evict_chunks(&mut self, mut keep: impl FnMut(&(i32, i32)) -> bool) {
+        if let Some(downloader) = &mut self.downloader {
+            let evicted: Vec<(i32, i32)> = self
+                .chunks
+                .chunks
+                .keys()
+                .copied()
+                .filter(|c| !keep(c))
+                .collect();
+            if evicted.is_empty() {
+                return;
+            }
+
+            if let Err(e) =
+                downloader.save_evicted(&mut self.chunks, self.level.dimension, &evicted)
+            {
+                error!("Failed to save evicted chunks: {e:#}");
+            }
+            for c in evicted {
+                self.chunks.chunks.remove(&c);
+            }
+        } else {
+            self.chunks.chunks.retain(|c, _| keep(c));
+        }
+    }
+
+    /// Returns the next event that occurred during a tick, if any
+    pub fn poll_event(&mut self) -> Option<ClientEvent> {
+        self.events.pop_front()
+    }
+
+    fn handle_packet(&mut self, p: AbstractPacket) -> anyhow::Result<()> {
+        match p {
+            AbstractPacket::Chunks(chunk_data) => {
+                // The packet may unload columns with changes we haven't saved yet
+                self.save_chunks();
+
+                match self.chunks.apply(chunk_data) {
+                    Ok(result) => {
+                        if !result.short.is_empty() {
+                            warn!("Chunk data ended early for {:?}", result.short);
+                        }
+
+                        if result.trailing_bytes != 0 {
+                            warn!(
+                                "Trailing data in chunk packet! ({} bytes left)",
+                                result.trailing_bytes
+                            );
+                        }
+                    }
+                    Err(e) => error!("Failed to load chunk data: {e:#}"),
+                }
+            }
+            AbstractPacket::Explosion {
+                pos,
+                affected_block_offsets,
+                player_motion,
+                ..
+            } => {
+                self.chunks
+                    .set_blocks(affected_block_offsets.iter().map(|r| {
+                        (
+                            pos.x as i32 + r.0 as i32,
+                            pos.y as i32 + r.1 as i32,
+                            pos.z as i32 + r.2 as i32,
+                            BlockState::AIR,
+                        )
+                    }));
+
+                self.player.velocity += player_motion;
+            }
+            AbstractPacket::JoinGame {
+                gamemode,
+                dimension,
+                difficulty,
+                level_kind,
+                min_y,
+                dimension_types,
+                ..
+            } => {
+                self.level.gamemode = gamemode;
+                self.level.dimension = dimension;
+                self.level.difficulty = difficulty;
+                self.level.level_kind = level_kind;
+                self.level.min_y = min_y;
+                self.level.dimension_types = dimension_types.into_iter().collect();
+                self.chunks.set_min_y(min_y);
+
+                // Proxies resend JoinGame when moving us to another server, entities from the old one won't be despawned
+                self.entities.clear(&mut self.world);
+            }
+            AbstractPacket::SpawnPosition { location } => {
+                self.level.spawn = (location.x, location.y, location.z);
+                self.save_world();
+            }
+            AbstractPacket::UpdateTime {
+                world_age,
+                time_of_day,
+            } => {
+                self.level.world_age = world_age;
+                self.level.time_of_day = time_of_day;
+            }
+            AbstractPacket::Respawn {
+                dimension,
+                difficulty,
+                gamemode,
+                level_kind,
+                min_y,
+                dimension_type,
+            } => {
+                self.save_world();
+                self.level.dimension = dimension;
+                self.level.difficulty = difficulty;
+                self.level.gamemode = gamemode;
+                self.level.level_kind = level_kind;
+                self.level.min_y = min_y
+                    .or_else(|| self.level.dimension_types.get(&dimension_type?).copied())
+                    .unwrap_or(0);
+
+                self.chunks.reset(self.level.min_y);
+                self.entities.clear(&mut self.world);
+
+                // Shrink to reclaim memory
+                self.chunks.chunks.shrink_to_fit();
+            }
+            AbstractPacket::BlockChange { state, location } => {
+                self.chunks
+                    .set_block(location.x, location.y, location.z, state);
+            }
+            AbstractPacket::MultiBlockChange {
+                chunk_x,
+                chunk_z,
+                records,
+            } => {
+                self.chunks
+                    .apply_multi_block_change(chunk_x, chunk_z, &records);
+            }
+            AbstractPacket::BlockAction {
+                location,
+                action_id,
+                action_param,
+                block_id,
+            } => {
+                self.chunks.set_block_action(
+                    location.x,
+                    location.y,
+                    location.z,
+                    BlockAction {
+                        action_id,
+                        action_param,
+                        block_id,
+                    },
+                );
+            }
+            AbstractPacket::TileEntityData { location, nbt, .. } => {
+                self.chunks
+                    .set_block_entity(location.x, location.y, location.z, nbt);
+            }
+            AbstractPacket::UpdateSign { location, lines } => {
+                self.chunks
+                    .update_sign(location.x, location.y, location.z, lines);
+            }
+            AbstractPacket::BlockBreakAnimation {
+                entity_id,
+                location,
+                destroy_stage,
+            } => {
+                self.chunks.set_break_progress(
+                    entity_id,
+                    location.x,
+                    location.y,
+                    location.z,
+                    destroy_stage,
+                );
+            }
+            AbstractPacket::PositionLookClientBound {
+                pos,
+                yaw,
+                pitch,
+                flags,
+                ..
+            } => {
+                let flags = flags.unwrap_or_else(PositionFlags::empty);
+                let player = &mut self.player;
+
+                macro_rules! apply {
+                    ($field:expr, $value:expr, $flag:expr) => {
+                        if flags.contains($flag) {
+                            $field += $value
+                        } else {
+                            $field = $value
+                        }
+                    };
+                }
+
+                apply!(player.position.x, pos.x, PositionFlags::X);
+                apply!(player.position.y, pos.y, PositionFlags::Y);
+                apply!(player.position.z, pos.z, PositionFlags::Z);
+                apply!(player.yaw, yaw, PositionFlags::Y_ROT);
+                apply!(player.pitch, pitch, PositionFlags::X_ROT);
+                player.velocity = Vector3::new(0., 0., 0.);
+
+                self.connection
+                    .write(AbstractPacket::PositionLookServerBound {
+                        pos: player.position,
+                        yaw: player.yaw,
+                        pitch: player.pitch,
+                        on_ground: false,
+                    })?;
+
+                if !self.spawned {
+                    info!(
+                        "Spawned at {:.2} / {:.2} / {:.2}",
+                        player.position.x, player.position.y, player.position.z
+                    );
+
+                    self.connection
+                        .write(AbstractPacket::ClientCommand { action_id: 0 })?;
+                    self.spawned = true;
+                    self.events.push_back(ClientEvent::Spawned);
+                } else {
+                    self.events.push_back(ClientEvent::Teleported);
+                }
+            }
+            p => {
+                if let Some(p) = self.entities.handle_packet(&mut self.world, p) {
+                    self.events.push_back(ClientEvent::Packet(p));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn update_physics(&mut self) {
+        let player = &mut self.player;
+
+        // Don't fall into the void while the chunk we're standing in hasn't been loaded yet
+        let chunk = (
+            (player.position.x.floor() as i32) >> 4,
+            (player.position.z.floor() as i32) >> 4,
+        );
+        if self.chunks.get(&chunk).is_none() {
+            return;
+        }
+
+        player.velocity.y = (player.velocity.y - GRAVITY) * AIR_DRAG;
+
+        let velocity = Vector3::new(
+            player.velocity.x as f32,
+            player.velocity.y as f32,
+            player.velocity.z as f32,
+        );
+        let start = Point3::new(
+            player.position.x as f32 - 0.3,
+            player.position.y as f32,
+            player.position.z as f32 - 0.3,
+        );
+
+        let mut next_pos = physics::calculate_next_player_pos(
+            &self.chunks,
+            start,
+            Vector3::new(velocity.x, 0., 0.),
+        );
+
+        next_pos = physics::calculate_next_player_pos(
+            &self.chunks,
+            next_pos,
+            Vector3::new(0., 0., velocity.z),
+        );
+
+        let before_y = next_pos.y;
+        next_pos = physics::calculate_next_player_pos(
+            &self.chunks,
+            next_pos,
+            Vector3::new(0., velocity.y, 0.),
+        );
+
+        // We hit something if we didn't move the full distance
+        let blocked_y = (next_pos.y - before_y - velocity.y).abs() > 0.001;
+        player.on_ground = blocked_y && velocity.y < 0.;
+        if blocked_y {
+            player.velocity.y = 0.;
+        }
+
+        let friction = if player.on_ground {
+            GROUND_FRICTION
+        } else {
+            AIR_FRICTION
+        };
+        player.velocity.x *= friction;
+        player.velocity.z *= friction;
+
+        player.position = Point3::new(
+            next_pos.x as f64 + 0.3,
+            next_pos.y as f64,
+            next_pos.z as f64 + 0.3,
+        );
+    }
+}
@@ -1,73 +1,538 @@
-use std::fmt::Debug;
+use std::{collections::HashMap, fmt::Debug};
 
 use cgmath::{Point3, Vector3, Zero};
 use hecs::{Entity, World};
+use uuid::Uuid;
 
-// In units (aka blocks) per second
-pub const VELOCITY_UNIT: f32 = (1. / 8000.) * 20.;
-// pub const VELOCITY_UNIT: f32 = (1. / 8000.);
+use crate::{
+    ids::ItemStack,
+    net::{
+        types::EntityMeta,
+        wrapper::{AbstractPacket, Attribute, EquipmentSlot},
+    },
+};
 
 pub const TICK_DELTA: f32 = 1. / 20.;
 
+/// Number of ticks it takes to move an entity to the position the server sent, same as vanilla
+pub const INTERPOLATION_TICKS: f32 = 3.;
+
+/// Applied to extrapolated velocities every tick
+const DRAG: f64 = 0.98;
+
+/// Id the server uses for this entity
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ServerId(pub i32);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntityKind {
+    /// Non-living entities like minecarts, arrows and dropped items
+    Object(i8),
+    Mob(i32),
+    Player,
+    Painting {
+        title: String,
+        direction: u8,
+    },
+    ExperienceOrb {
+        count: i16,
+    },
+    /// Lightning bolts
+    Global(i8),
+}
+
 /// Used for interpolation
 pub struct InterpolatedPosition {
     pub position: Point3<f32>,
     pub delta: f32, // The amount of time until we reach the target
 }
 
-pub struct Position(pub Point3<f32>);
-pub struct Velocity(pub Vector3<f32>);
+/// Position of the entity, extrapolated from the last one the server sent using its velocity
+pub struct Position(pub Point3<f64>);
+
+/// Last position sent by the server, relative moves are applied to this so extrapolation errors don't add up
+pub struct ServerPosition(pub Point3<f64>);
+
+/// In blocks per tick
+pub struct Velocity(pub Vector3<f64>);
+
+/// In degrees
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rotation {
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+/// In degrees
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HeadYaw(pub f32);
+
+/// Not sent by 1.7 servers, assumed to be true there
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct OnGround(pub bool);
+
+/// Every metadata field the server has sent, updates are merged into it
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Metadata(pub EntityMeta);
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Equipment(pub HashMap<EquipmentSlot, ItemStack>);
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Attributes(pub HashMap<String, Attribute>);
+
+impl Attributes {
+    /// Returns the value of the attribute with all of its modifiers applied
+    pub fn get(&self, key: &str) -> Option<f64> {
+        let attribute = self.0.get(key)?;
+
+        let mut value = attribute.value;
+        for m in attribute.modifiers.iter().filter(|m| m.operation == 0) {
+            value += m.amount;
+        }
+
+        let mut result = value;
+        for m in &attribute.modifiers {
+            match m.operation {
+                1 => result += value * m.amount,
+                2 => result *= 1. + m.amount,
+                _ => {}
+            }
+        }
+
+        Some(result)
+    }
+}
+
+/// Server ids of the entities riding this one
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Passengers(pub Vec<i32>);
+
+/// Server id of the entity holding this one's leash
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LeashHolder(pub i32);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerInfo {
+    pub uuid: Uuid,
+    /// Only sent by 1.7 servers
+    pub name: Option<String>,
+}
+
+/// Maps the entity ids the server uses to the entities in a `World`
+#[derive(Default)]
+pub struct EntityRegistry {
+    entities: HashMap<i32, Entity>,
+}
+
+impl EntityRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, id: i32) -> Option<Entity> {
+        self.entities.get(&id).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Spawns an entity with the components every entity has, replacing any entity that already uses `id`
+    pub fn spawn(
+        &mut self,
+        world: &mut World,
+        id: i32,
+        kind: EntityKind,
+        pos: Point3<f64>,
+        rotation: Rotation,
+    ) -> Entity {
+        self.despawn(world, id);
+
+        let entity = world.spawn((
+            ServerId(id),
+            kind,
+            Position(pos),
+            ServerPosition(pos),
+            Velocity(Vector3::zero()),
+            InterpolatedPosition {
+                position: pos.cast().unwrap(),
+                delta: 0.,
+            },
+            rotation,
+            OnGround(true),
+            Metadata::default(),
+        ));
+        self.entities.insert(id, entity);
+
+        entity
+    }
+
+    pub fn despawn(&mut self, world: &mut World, id: i32) {
+        if let Some(entity) = self.entities.remove(&id) {
+            world.despawn(entity).ok();
+        }
+    }
+
+    /// Despawns every entity, used when the world is unloaded
+    pub fn clear(&mut self, world: &mut World) {
+        for (_, entity) in self.entities.drain() {
+            world.despawn(entity).ok();
+        }
+    }
+
+    /// Applies an entity packet to the world, returns the packet back if it isn't one
+    ///
+    /// Packets for entities that haven't been spawned are ignored, like vanilla does.
+    pub fn handle_packet(
+        &mut self,
+        world: &mut World,
+        packet: AbstractPacket,
+    ) -> Option<AbstractPacket> {
+        match packet {
+            AbstractPacket::SpawnObject {
+                entity_id,
+                kind,
+                pos,
+                yaw,
+                pitch,
+                velocity,
+                ..
+            } => {
+                let entity = self.spawn(
+                    world,
+                    entity_id,
+                    EntityKind::Object(kind),
+                    pos,
+                    Rotation { yaw, pitch },
+                );
+                world.insert_one(entity, Velocity(velocity)).ok();
+            }
+            AbstractPacket::SpawnMob {
+                entity_id,
+                kind,
+                pos,
+                yaw,
+                pitch,
+                head_pitch,
+                velocity,
+                metadata,
+                ..
+            } => {
+                let entity = self.spawn(
+                    world,
+                    entity_id,
+                    EntityKind::Mob(kind),
+                    pos,
+                    Rotation { yaw, pitch },
+                );
+                // Vanilla uses the head pitch as the head yaw
+                world
+                    .insert(
+                        entity,
+                        (Velocity(velocity), HeadYaw(head_pitch), Metadata(metadata)),
+                    )
+                    .ok();
+            }
+            AbstractPacket::SpawnPlayer {
+                entity_id,
+                uuid,
+                name,
+                pos,
+                yaw,
+                pitch,
+                current_item,
+                metadata,
+            } => {
+                let entity = self.spawn(
+                    world,
+                    entity_id,
+                    EntityKind::Player,
+                    pos,
+                    Rotation { yaw, pitch },
+                );
+                world
+                    .insert(
+                        entity,
+                        (PlayerInfo { uuid, name }, HeadYaw(yaw), Metadata(metadata)),
+                    )
+                    .ok();
+                if let Some(item) = current_item {
+                    let item = ItemStack {
+                        item,
+                        count: 1,
+                        damage: 0,
+                        nbt: None,
+                    };
+                    let equipment = HashMap::from([(EquipmentSlot::MainHand, item)]);
+                    world.insert_one(entity, Equipment(equipment)).ok();
+                }
+            }
+            AbstractPacket::SpawnPainting {
+                entity_id,
+                title,
+                location,
+                direction,
+                ..
+            } => {
+                let pos = Point3::new(location.x as f64, location.y as f64, location.z as f64);
+                self.spawn(
+                    world,
+                    entity_id,
+                    EntityKind::Painting { title, direction },
+                    pos,
+                    Rotation { yaw: 0., pitch: 0. },
+                );
+            }
+            AbstractPacket::SpawnExperienceOrb {
+                entity_id,
+                pos,
+                count,
+            } => {
+                self.spawn(
+                    world,
+                    entity_id,
+                    EntityKind::ExperienceOrb { count },
+                    pos,
+                    Rotation { yaw: 0., pitch: 0. },
+                );
+            }
+            AbstractPacket::SpawnGlobalEntity {
+                entity_id,
+                kind,
+                pos,
+            } => {
+                self.spawn(
+                    world,
+                    entity_id,
+                    EntityKind::Global(kind),
+                    pos,
+                    Rotation { yaw: 0., pitch: 0. },
+                );
+            }
+
+            AbstractPacket::EntityMove {
+                entity_id,
+                delta,
+                on_ground,
+            } => self.move_entity(world, entity_id, |p| p + delta, None, on_ground),
+            AbstractPacket::EntityLook {
+                entity_id,
+                yaw,
+                pitch,
+                on_ground,
+            } => {
+                if let Some(entity) = self.get(entity_id) {
+                    if let Ok(mut rotation) = world.get::<&mut Rotation>(entity) {
+                        *rotation = Rotation { yaw, pitch };
+                    }
+                    if let Some(on_ground) = on_ground {
+                        world.insert_one(entity, OnGround(on_ground)).ok();
+                    }
+                }
+            }
+            AbstractPacket::EntityMoveLook {
+                entity_id,
+                delta,
+                yaw,
+                pitch,
+                on_ground,
+            } => self.move_entity(
+                world,
+                entity_id,
+                |p| p + delta,
+                Some(Rotation { yaw, pitch }),
+                on_ground,
+            ),
+            AbstractPacket::EntityTeleport {
+                entity_id,
+                pos,
+                yaw,
+                pitch,
+                on_ground,
+            } => self.move_entity(
+                world,
+                entity_id,
+                |_| pos,
+                Some(Rotation { yaw, pitch }),
+                on_ground,
+            ),
+            AbstractPacket::EntityVelocity {
+                entity_id,
+                velocity,
+            } => {
+                if let Some(entity) = self.get(entity_id) {
+                    world.insert_one(entity, Velocity(velocity)).ok();
+                }
+            }
+            AbstractPacket::EntityHeadRotation {
+                entity_id,
+                head_yaw,
+            } => {
+                if let Some(entity) = self.get(entity_id) {
+                    world.insert_one(entity, HeadYaw(head_yaw)).ok();
+                }
+            }
+
+            AbstractPacket::EntityMetadata {
+                entity_id,
+                metadata,
+            } => {
+                if let Some(entity) = self.get(entity_id) {
+                    if let Ok(mut m) = world.get::<&mut Metadata>(entity) {
+                        for (index, field) in metadata.meta.iter() {
+                            m.0.meta.insert(*index, field.clone());
+                        }
+                    }
+                }
+            }
+            AbstractPacket::EntityEquipment { entity_id, items } => {
+                if let Some(entity) = self.get(entity_id) {
+                    let mut equipment = world.remove_one::<Equipment>(entity).unwrap_or_default();
+                    for (slot, item) in items {
+                        match item {
+                            Some(item) => equipment.0.insert(slot, item),
+                            None => equipment.0.remove(&slot),
+                        };
+                    }
+                    world.insert_one(entity, equipment).ok();
+                }
+            }
+            AbstractPacket::EntityAttributes {
+                entity_id,
+                attributes,
+            } => {
+                if let Some(entity) = self.get(entity_id) {
+                    let mut a = world.remove_one::<Attributes>(entity).unwrap_or_default();
+                    for attribute in attributes {
+                        a.0.insert(attribute.key.clone(), attribute);
+                    }
+                    world.insert_one(entity, a).ok();
+                }
+            }
+            AbstractPacket::EntityDestroy { entity_ids } => {
+                for id in entity_ids {
+                    self.despawn(world, id);
+                }
+            }
+
+            AbstractPacket::AttachEntity {
+                entity_id,
+                vehicle_id,
+                leash: true,
+            } => {
+                if let Some(entity) = self.get(entity_id) {
+                    if vehicle_id == -1 {
+                        world.remove_one::<LeashHolder>(entity).ok();
+                    } else {
+                        world.insert_one(entity, LeashHolder(vehicle_id)).ok();
+                    }
+                }
+            }
+            // Riding before 1.9, an entity can only ride a single vehicle
+            AbstractPacket::AttachEntity {
+                entity_id,
+                vehicle_id,
+                leash: false,
+            } => {
+                for (_, passengers) in world.query_mut::<&mut Passengers>() {
+                    passengers.0.retain(|id| *id != entity_id);
+                }
+
+                if let Some(vehicle) = self.get(vehicle_id) {
+                    let mut passengers =
+                        world.remove_one::<Passengers>(vehicle).unwrap_or_default();
+                    passengers.0.push(entity_id);
+                    world.insert_one(vehicle, passengers).ok();
+                }
+            }
+            AbstractPacket::SetPassengers {
+                entity_id,
+                passengers,
+            } => {
+                if let Some(entity) = self.get(entity_id) {
+                    world.insert_one(entity, Passengers(passengers)).ok();
+                }
+            }
+
+            p => return Some(p),
+        }
+
+        None
+    }
+
+    fn move_entity(
+        &mut self,
+        world: &mut World,
+        id: i32,
+        f: impl FnOnce(Point3<f64>) -> Point3<f64>,
+        rotation: Option<Rotation>,
+        on_ground: Option<bool>,
+    ) {
+        let entity = match self.get(id) {
+            Some(e) => e,
+            None => return,
+        };
+
+        if let Ok((pos, server_pos, interp)) = world.query_one_mut::<(
+            &mut Position,
+            &mut ServerPosition,
+            &mut InterpolatedPosition,
+        )>(entity)
+        {
+            server_pos.0 = f(server_pos.0);
+            pos.0 = server_pos.0;
+            interp.delta = TICK_DELTA * INTERPOLATION_TICKS;
+        }
+
+        if let Some(rotation) = rotation {
+            world.insert_one(entity, rotation).ok();
+        }
+        if let Some(on_ground) = on_ground {
+            world.insert_one(entity, OnGround(on_ground)).ok();
+        }
+    }
+}
+
+/// Moves entities by their velocity, should be called once per tick
+pub fn update_velocity(world: &mut World) {
+    for (_e, (pos, vel, on_ground)) in
+        world.query_mut::<(&mut Position, &mut Velocity, Option<&OnGround>)>()
+    {
+        // There's no collision with blocks, so don't let entities standing on the ground sink into it
+        let mut delta = vel.0;
+        if on_ground.is_none_or(|g| g.0) {
+            delta.y = 0.;
+        }
 
-pub fn update_velocity(_world: &mut World, _delta: f32) {
-    // for (e, (pos, vel)) in world.query::<(&mut Position, &Velocity)>().iter() {
-    //     // FIXME: We're not moving in Y, because the lack of block collision makes mobs phase through the ground
-    //     let temp_vel = Vector3::new(vel.0.x, 0., vel.0.z);
-    //     pos.0 += temp_vel * delta;
-    // }
+        pos.0 += delta;
+        vel.0 *= DRAG;
+    }
 }
 
+/// Moves the rendered position of entities towards their actual position, `delta` is the time since the last update in seconds
 pub fn update_interpolation(world: &mut World, delta: f32) {
     for (_e, (pos, interp)) in world
-        .query::<(&mut Position, &mut InterpolatedPosition)>()
+        .query::<(&Position, &mut InterpolatedPosition)>()
         .iter()
     {
-        // interp.position = pos.0;
-        if interp.delta <= 0. {
-            continue;
-        }
-
-        let offset_mul = delta / interp.delta;
-        if offset_mul <= 0. {
+        let target: Point3<f32> = pos.0.cast().unwrap();
+        if interp.delta <= delta {
+            interp.position = target;
+            interp.delta = 0.;
             continue;
         }
 
-        let offset = (pos.0 - interp.position) * offset_mul;
+        let offset = (target - interp.position) * (delta / interp.delta);
         interp.position += offset;
         interp.delta -= delta;
     }
 }
 
-pub fn get_or_insert(world: &mut World, eid: i32) -> Entity {
-    // println!("{:?}", NonZeroU32::new(eid as u32));
-    let ent = Entity::from_bits(eid as u64 | (1 << 32)).unwrap();
-    if world.get::<&Position>(ent).is_ok() {
-        ent
-    } else {
-        world.spawn_at(
-            ent,
-            (
-                Position(Point3::new(0., 0., 0.)),
-                Velocity(Vector3::zero()),
-                InterpolatedPosition {
-                    position: Point3::new(0., 0., 0.),
-                    delta: TICK_DELTA,
-                },
-            ),
-        );
-        ent
-    }
-}
-
 impl Debug for Position {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!(
@@ -0,0 +1,462 @@
+//! Translation between the block and item ids each protocol sends and the ones used by the rest of the client
+//!
+//! Block states are stored as [`BlockState`]s in the 1.12 layout (`id << 4 | meta`), and every one of them has a
+//! flattened name in the 1.13 format (`minecraft:oak_stairs[facing=east,half=bottom]`) that the world, renderer and
+//! bots can use no matter which version the server speaks. Before 1.13 the wire ids already are in that layout.
+//!
+//! 1.13 replaced the ids with a global palette, which is loaded from a table in `data/ids` listing the name of every
+//! wire id in order. Each name is mapped to the legacy state with the same name, or failing that the one with the
+//! same block and the most properties in common. Blocks that didn't exist before 1.13 get new states starting at
+//! block id 256, except for the ones in `ALIASES` that behave exactly like an older block.
+//!
+//! Items are namespaced names along with their count, damage and NBT. Before 1.13 the damage value also picked
+//! variants of some items (wool colours, dyes, ...), which are turned into their own names. Tables that don't list
+//! the items of their protocol leave them as `unknown:<id>`.
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use anyhow::{bail, ensure};
+use fnv::FnvHashMap;
+use serde::Deserialize;
+
+use crate::{
+    net::{types::Slot, ProtocolVersion},
+    world::BlockState,
+};
+
+/// Names of the block states and items of 1.7 up to 1.12.2
+const IDS_LEGACY: &str = include_str!("../data/ids/legacy.json");
+/// Block state names of the global palette protocols, in wire id order
+const IDS_1_13_2: &str = include_str!("../data/ids/1.13.2.json");
+const IDS_1_16_5: &str = include_str!("../data/ids/1.16.5.json");
+const IDS_1_19_2: &str = include_str!("../data/ids/1.19.2.json");
+
+/// First block id used for states that don't exist before 1.13
+const FIRST_NEW_BLOCK_ID: u16 = 256;
+
+/// Blocks added in 1.13 that are mapped to the state of an older one
+const ALIASES: &[(&str, &str)] = &[
+    ("minecraft:cave_air", "minecraft:air"),
+    ("minecraft:void_air", "minecraft:air"),
+];
+
+/// Namespace for items without a known name, followed by their wire id
+const UNKNOWN_ITEM_NAMESPACE: &str = "unknown";
+
+/// A namespaced block name along with the values of its properties, such as `minecraft:water[level=0]`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StateName {
+    pub name: String,
+    pub properties: BTreeMap<String, String>,
+}
+
+impl StateName {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: namespaced(name),
+            properties: BTreeMap::new(),
+        }
+    }
+
+    pub fn with(mut self, property: &str, value: &str) -> Self {
+        self.properties
+            .insert(property.to_string(), value.to_string());
+        self
+    }
+
+    pub fn property(&self, property: &str) -> Option<&str> {
+        self.properties.get(property).map(String::as_str)
+    }
+}
+
+impl FromStr for StateName {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, properties) = match s.split_once('[') {
+            Some((name, rest)) => match rest.strip_suffix(']') {
+                Some(properties) => (name, properties),
+                None => bail!("Missing ] in block state {s}"),
+            },
+            None => (s, ""),
+        };
+        ensure!(!name.is_empty(), "Block state {s} has no name");
+
+        let mut state = Self::new(name);
+        for property in properties.split(',').filter(|p| !p.is_empty()) {
+            let Some((key, value)) = property.split_once('=') else {
+                bail!("Property {property} of {s} has no value");
+            };
+            state = state.with(key, value);
+        }
+
+        Ok(state)
+    }
+}
+
+impl fmt::Display for StateName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if self.properties.is_empty() {
+            return Ok(());
+        }
+
+        f.write_str("[")?;
+        for (i, (key, value)) in self.properties.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{key}={value}")?;
+        }
+        f.write_str("]")
+    }
+}
+
+/// Adds the `minecraft:` namespace to names that don't have one
+fn namespaced(name: &str) -> String {
+    if name.contains(':') {
+        name.to_string()
+    } else {
+        format!("minecraft:{name}")
+    }
+}
+
+/// The contents of a slot, independent of the protocol
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemStack {
+    /// Namespaced name, such as `minecraft:iron_pickaxe`
+    pub item: String,
+    pub count: u8,
+    /// Durability used by tools and armour
+    pub damage: i16,
+    pub nbt: Option<nbt::Blob>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawLegacyFile {
+    blocks: Vec<RawLegacyBlock>,
+    items: Vec<RawLegacyItem>,
+}
+
+/// The name of each metadata value, metadata that is missing or `null` has the same name as metadata 0
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawLegacyBlock {
+    id: u16,
+    states: Vec<Option<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawLegacyItem {
+    id: i16,
+    /// The entry only applies to this damage value, otherwise the damage is the item's durability
+    damage: Option<i16>,
+    name: String,
+}
+
+/// The names of every wire id of a global palette protocol, in order
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawTable {
+    blocks: Vec<String>,
+    #[serde(default)]
+    items: Vec<String>,
+}
+
+/// Ids used by protocols with a global palette
+#[derive(Debug, Clone)]
+struct GlobalPalette {
+    blocks: Vec<BlockState>,
+    block_ids: FnvHashMap<BlockState, i32>,
+    items: Vec<String>,
+    item_ids: FnvHashMap<String, i32>,
+}
+
+/// Maps the wire ids of a protocol to block states and item names
+#[derive(Debug, Clone)]
+pub struct IdMap {
+    names: FnvHashMap<BlockState, StateName>,
+    /// The first state with each name, the legacy table lists still water and lava before the flowing kinds
+    states: FnvHashMap<StateName, BlockState>,
+    /// Keyed by item id and damage, or `None` for items that use the damage as their durability
+    legacy_items: FnvHashMap<(i16, Option<i16>), String>,
+    legacy_item_ids: FnvHashMap<String, (i16, Option<i16>)>,
+    /// Set for 1.13+
+    palette: Option<GlobalPalette>,
+}
+
+impl Default for IdMap {
+    fn default() -> Self {
+        Self::legacy(IDS_LEGACY).expect("Built-in id data is invalid")
+    }
+}
+
+impl IdMap {
+    /// Loads the built-in ids for a protocol version
+    ///
+    /// Every version has a table, so this only fails if the built-in data is invalid.
+    pub fn for_protocol(protocol: ProtocolVersion) -> anyhow::Result<Self> {
+        use ProtocolVersion::*;
+
+        match protocol {
+            Proto1_7 | Proto1_7_6 | Proto1_8 | Proto1_9 | Proto1_9_2 | Proto1_9_4 | Proto1_10
+            | Proto1_11 | Proto1_12 | Proto1_12_1 | Proto1_12_2 => Self::legacy(IDS_LEGACY),
+            Proto1_13_2 => Self::with_table(IDS_1_13_2),
+            Proto1_16_5 => Self::with_table(IDS_1_16_5),
+            Proto1_19_2 => Self::with_table(IDS_1_19_2),
+        }
+    }
+
+    /// Parses the names of the legacy block states and items
+    fn legacy(data: &str) -> anyhow::Result<Self> {
+        let file: RawLegacyFile = serde_json::from_str(data)?;
+
+        let mut map = Self {
+            names: FnvHashMap::default(),
+            states: FnvHashMap::default(),
+            legacy_items: FnvHashMap::default(),
+            legacy_item_ids: FnvHashMap::default(),
+            palette: None,
+        };
+
+        for raw in file.blocks {
+            ensure!(
+                raw.id < FIRST_NEW_BLOCK_ID,
+                "Block id {} is too large",
+                raw.id
+            );
+            ensure!(
+                (1..=16).contains(&raw.states.len()),
+                "Block {} needs between 1 and 16 states",
+                raw.id
+            );
+            let Some(Some(default)) = raw.states.first() else {
+                bail!("Metadata 0 of block {} has no name", raw.id);
+            };
+            let default: StateName = default.parse()?;
+
+            for meta in 0..16 {
+                let name = match raw.states.get(meta as usize) {
+                    Some(Some(name)) => name.parse()?,
+                    _ => default.clone(),
+                };
+                map.insert(BlockState::new(raw.id, meta), name);
+            }
+        }
+
+        for raw in file.items {
+            let name = namespaced(&raw.name);
+            map.legacy_item_ids
+                .entry(name.clone())
+                .or_insert((raw.id, raw.damage));
+            map.legacy_items.insert((raw.id, raw.damage), name);
+        }
+
+        Ok(map)
+    }
+
+    /// Parses the table of a global palette protocol, see the module docs for how its blocks are matched to states
+    pub fn with_table(data: &str) -> anyhow::Result<Self> {
+        let table: RawTable = serde_json::from_str(data)?;
+        let mut map = Self::default();
+
+        // Legacy states by block name, to find the closest one to the ones that don't match exactly
+        let mut by_name: FnvHashMap<String, Vec<BlockState>> = FnvHashMap::default();
+        for (&state, name) in &map.names {
+            by_name.entry(name.name.clone()).or_default().push(state);
+        }
+        for states in by_name.values_mut() {
+            states.sort_by_key(|s| s.0);
+        }
+
+        let mut next = BlockState::new(FIRST_NEW_BLOCK_ID, 0);
+        let mut palette = GlobalPalette {
+            blocks: Vec::with_capacity(table.blocks.len()),
+            block_ids: FnvHashMap::default(),
+            items: Vec::with_capacity(table.items.len()),
+            item_ids: FnvHashMap::default(),
+        };
+        // Ids whose name only matched the closest state, those don't get to be the state's id unless nothing matches
+        let mut inexact = vec![];
+        for (id, name) in table.blocks.iter().enumerate() {
+            let name: StateName = name.parse()?;
+            if let Some((_, alias)) = ALIASES.iter().find(|(n, _)| *n == name.name) {
+                let state = map.states[&StateName::new(alias)];
+                map.states.entry(name).or_insert(state);
+                inexact.push((state, id as i32));
+                palette.blocks.push(state);
+                continue;
+            }
+
+            let state = match map.states.get(&name) {
+                Some(&state) => state,
+                None => match by_name.get(&name.name) {
+                    Some(candidates) => {
+                        let state = closest_state(&map.names, candidates, &name);
+                        inexact.push((state, id as i32));
+                        palette.blocks.push(state);
+                        continue;
+                    }
+                    None => {
+                        let state = next;
+                        ensure!(state.0 < u16::MAX, "Too many block states");
+                        next = BlockState(next.0 + 1);
+                        map.insert(state, name);
+                        state
+                    }
+                },
+            };
+
+            palette.blocks.push(state);
+            palette.block_ids.entry(state).or_insert(id as i32);
+        }
+        for (state, id) in inexact {
+            palette.block_ids.entry(state).or_insert(id);
+        }
+
+        for (id, name) in table.items.iter().enumerate() {
+            let name = namespaced(name);
+            palette.item_ids.entry(name.clone()).or_insert(id as i32);
+            palette.items.push(name);
+        }
+
+        map.palette = Some(palette);
+        Ok(map)
+    }
+
+    fn insert(&mut self, state: BlockState, name: StateName) {
+        self.states.entry(name.clone()).or_insert(state);
+        self.names.insert(state, name);
+    }
+
+    /// Converts a block state sent by the server, the legacy protocols send it as `id << 4 | meta`
+    ///
+    /// Unknown ids turn into air.
+    pub fn block_from_wire(&self, id: i32) -> BlockState {
+        match &self.palette {
+            Some(palette) => usize::try_from(id)
+                .ok()
+                .and_then(|id| palette.blocks.get(id).copied())
+                .unwrap_or(BlockState::AIR),
+            None => u16::try_from(id).map(BlockState).unwrap_or(BlockState::AIR),
+        }
+    }
+
+    /// The id the server would use for a block state, if it has one
+    pub fn block_to_wire(&self, state: BlockState) -> Option<i32> {
+        match &self.palette {
+            Some(palette) => palette.block_ids.get(&state).copied(),
+            None => (state.id() < FIRST_NEW_BLOCK_ID).then_some(state.0 as i32),
+        }
+    }
+
+    pub fn block_name(&self, state: BlockState) -> Option<&StateName> {
+        self.names.get(&state)
+    }
+
+    /// The states of blocks that didn't exist before 1.13, in no particular order
+    pub fn new_block_states(&self) -> impl Iterator<Item = (BlockState, &StateName)> {
+        self.names
+            .iter()
+            .filter(|(state, _)| state.id() >= FIRST_NEW_BLOCK_ID)
+            .map(|(&state, name)| (state, name))
+    }
+
+    /// Finds the state with a name, falling back to the closest state of the same block if there's no exact match
+    pub fn block_state(&self, name: &StateName) -> Option<BlockState> {
+        if let Some(&state) = self.states.get(name) {
+            return Some(state);
+        }
+
+        let mut candidates: Vec<_> = self
+            .names
+            .iter()
+            .filter(|(_, n)| n.name == name.name)
+            .map(|(&state, _)| state)
+            .collect();
+        candidates.sort_by_key(|s| s.0);
+        (!candidates.is_empty()).then(|| closest_state(&self.names, &candidates, name))
+    }
+
+    /// Converts a slot sent by the server, returns `None` for empty slots
+    pub fn item_from_wire(&self, slot: &Slot) -> Option<ItemStack> {
+        // -1 is an empty slot, and 0 (air) is used for empty hands
+        if slot.item_id <= 0 {
+            return None;
+        }
+
+        let damage = slot.item_damage.unwrap_or(0);
+        let (item, damage) = match &self.palette {
+            Some(palette) => (palette.items.get(slot.item_id as usize).cloned(), damage),
+            None => match self.legacy_items.get(&(slot.item_id, Some(damage))) {
+                Some(name) => (Some(name.clone()), 0),
+                None => (
+                    self.legacy_items.get(&(slot.item_id, None)).cloned(),
+                    damage,
+                ),
+            },
+        };
+
+        Some(ItemStack {
+            item: item.unwrap_or_else(|| format!("{UNKNOWN_ITEM_NAMESPACE}:{}", slot.item_id)),
+            count: slot.item_count.unwrap_or(1),
+            damage,
+            nbt: slot.data.clone(),
+        })
+    }
+
+    /// Converts an item into a slot the server understands, if the protocol has the item
+    pub fn item_to_wire(&self, stack: &ItemStack) -> Option<Slot> {
+        let unknown_id = stack
+            .item
+            .strip_prefix(UNKNOWN_ITEM_NAMESPACE)
+            .and_then(|id| id.strip_prefix(':'))
+            .and_then(|id| id.parse().ok());
+
+        let (item_id, item_damage) = match (&self.palette, unknown_id) {
+            (_, Some(id)) => (id, Some(stack.damage)),
+            // The damage is part of the NBT from 1.13 on
+            (Some(palette), None) => (*palette.item_ids.get(&stack.item)? as i16, None),
+            (None, None) => match *self.legacy_item_ids.get(&stack.item)? {
+                (id, Some(damage)) => (id, Some(damage)),
+                (id, None) => (id, Some(stack.damage)),
+            },
+        };
+
+        Some(Slot {
+            item_id,
+            item_count: Some(stack.count),
+            item_damage,
+            data: stack.nbt.clone(),
+        })
+    }
+}
+
+/// The candidate with the most properties matching `name`, the first one wins ties
+fn closest_state(
+    names: &FnvHashMap<BlockState, StateName>,
+    candidates: &[BlockState],
+    name: &StateName,
+) -> BlockState {
+    let matching = |state: &BlockState| {
+        names[state]
+            .properties
+            .iter()
+            .filter(|(key, value)| name.properties.get(*key) == Some(*value))
+            .count()
+    };
+
+    let mut best = candidates[0];
+    let mut best_matching = matching(&best);
+    for state in &candidates[1..] {
+        let m = matching(state);
+        if m > best_matching {
+            best = *state;
+            best_matching = m;
+        }
+    }
+
+    best
+}
@@ -0,0 +1,17 @@
+#[macro_use]
+extern crate log;
+
+pub mod anvil;
+pub mod audio;
+pub mod block;
+pub mod block_entity;
+pub mod client;
+pub mod ecs;
+pub mod fixed_point;
+pub mod ids;
+pub mod light;
+pub mod net;
+pub mod physics;
+pub mod render;
+pub mod varint;
+pub mod world;
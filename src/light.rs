@@ -0,0 +1,332 @@
+//! Client-side block and sky light propagation
+//!
+//! Light is only sent along with chunks, so after a block changes we relight the area around it ourselves.
+//! Both kinds of light use the same breadth-first search: first the light that came from the old blocks is removed,
+//! then the remaining light (and any new light source) spreads back into the gap.
+
+use std::collections::VecDeque;
+
+use fnv::FnvHashSet;
+
+use crate::world::{affected_sections, ChunkManager};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LightKind {
+    Block,
+    Sky,
+}
+
+/// Up, down and the 4 horizontal directions
+const NEIGHBOURS: [(i32, i32, i32); 6] = [
+    (0, 1, 0),
+    (0, -1, 0),
+    (-1, 0, 0),
+    (1, 0, 0),
+    (0, 0, -1),
+    (0, 0, 1),
+];
+
+/// Relights the world around blocks that have changed, adding every section whose light changed (and the neighbours that can see it) to `dirty`
+pub fn relight(
+    chunks: &mut ChunkManager,
+    changed: &[(i32, i32, i32)],
+    dirty: &mut FnvHashSet<(i32, i32, i32)>,
+) {
+    relight_kind(chunks, LightKind::Block, changed, dirty);
+    relight_kind(chunks, LightKind::Sky, changed, dirty);
+}
+
+fn relight_kind(
+    chunks: &mut ChunkManager,
+    kind: LightKind,
+    changed: &[(i32, i32, i32)],
+    dirty: &mut FnvHashSet<(i32, i32, i32)>,
+) {
+    // Blocks whose light has been cleared, along with the level they had
+    let mut decrease = VecDeque::new();
+    // Blocks that can spread their light to their neighbours
+    let mut increase = VecDeque::new();
+
+    for &(x, y, z) in changed {
+        let Some(old) = get_light(chunks, kind, x, y, z) else {
+            continue;
+        };
+
+        set_light(chunks, kind, x, y, z, 0, dirty);
+        if old > 0 {
+            decrease.push_back((x, y, z, old));
+        }
+
+        let source = source_light(chunks, kind, x, y, z);
+        if source > 0 && set_light(chunks, kind, x, y, z, source, dirty) {
+            increase.push_back((x, y, z));
+        }
+
+        // The block might let light through now
+        for (dx, dy, dz) in NEIGHBOURS {
+            increase.push_back((x + dx, y + dy, z + dz));
+        }
+    }
+
+    while let Some((x, y, z, level)) = decrease.pop_front() {
+        for (dx, dy, dz) in NEIGHBOURS {
+            let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+            let Some(neighbour) = get_light(chunks, kind, nx, ny, nz) else {
+                continue;
+            };
+            if neighbour == 0 {
+                continue;
+            }
+
+            // Full sky light travels down without getting weaker, so it has to be removed the same way
+            let direct_sky = kind == LightKind::Sky && dy == -1 && level == 15 && neighbour == 15;
+            if neighbour < level || direct_sky {
+                set_light(chunks, kind, nx, ny, nz, 0, dirty);
+                decrease.push_back((nx, ny, nz, neighbour));
+
+                let source = source_light(chunks, kind, nx, ny, nz);
+                if source > 0 && set_light(chunks, kind, nx, ny, nz, source, dirty) {
+                    increase.push_back((nx, ny, nz));
+                }
+            } else {
+                // Lit by something else, which has to fill the gap back in
+                increase.push_back((nx, ny, nz));
+            }
+        }
+    }
+
+    while let Some((x, y, z)) = increase.pop_front() {
+        let Some(level) = get_light(chunks, kind, x, y, z) else {
+            continue;
+        };
+        if level <= 1 {
+            continue;
+        }
+
+        for (dx, dy, dz) in NEIGHBOURS {
+            let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+            let Some(neighbour) = get_light(chunks, kind, nx, ny, nz) else {
+                continue;
+            };
+
+            let filter = chunks.blocks.get(chunks.get_block(nx, ny, nz)).light_filter;
+            let new = if kind == LightKind::Sky && dy == -1 && level == 15 && filter == 0 {
+                15
+            } else {
+                level.saturating_sub(filter.max(1))
+            };
+
+            if new > neighbour && set_light(chunks, kind, nx, ny, nz, new, dirty) {
+                increase.push_back((nx, ny, nz));
+            }
+        }
+    }
+}
+
+/// Light a block gives off by itself
+fn source_light(chunks: &ChunkManager, kind: LightKind, x: i32, y: i32, z: i32) -> u8 {
+    match kind {
+        LightKind::Block => chunks.blocks.get(chunks.get_block(x, y, z)).light_emission,
+        // The sky is above the world, which is handled by `get_light`
+        LightKind::Sky => 0,
+    }
+}
+
+/// Returns `None` for blocks in columns that aren't loaded and below the world, light doesn't spread into those
+fn get_light(chunks: &ChunkManager, kind: LightKind, x: i32, y: i32, z: i32) -> Option<u8> {
+    let column = chunks.get(&(x >> 4, z >> 4))?;
+    if y >> 4 < column.min_section {
+        return None;
+    }
+
+    let (block, sky) = chunks.get_block_light(x, y, z);
+    Some(match kind {
+        LightKind::Block => block,
+        LightKind::Sky => sky,
+    })
+}
+
+/// Returns false if the light can't be stored there
+fn set_light(
+    chunks: &mut ChunkManager,
+    kind: LightKind,
+    x: i32,
+    y: i32,
+    z: i32,
+    level: u8,
+    dirty: &mut FnvHashSet<(i32, i32, i32)>,
+) -> bool {
+    let Some(column) = chunks.get_mut(&(x >> 4, z >> 4)) else {
+        return false;
+    };
+
+    let section_y = y >> 4;
+    if !column.section_range().contains(&section_y) {
+        return false;
+    }
+
+    let previous = match column.get_section(section_y) {
+        Some(section) => {
+            let (block, sky) = section.get_block_light(x, y, z);
+            match kind {
+                LightKind::Block => block,
+                LightKind::Sky => sky,
+            }
+        }
+        None => match kind {
+            LightKind::Block => 0,
+            LightKind::Sky => column.implied_skylight(section_y),
+        },
+    };
+    if previous == level {
+        return true;
+    }
+
+    let section = column.get_section_mut_or_insert_lit(section_y);
+    match kind {
+        LightKind::Block => section.set_block_light(x, y, z, level),
+        LightKind::Sky => section.set_sky_light(x, y, z, level),
+    }
+    affected_sections(dirty, x, y, z);
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{BlockState, ChunkColumn};
+
+    const STONE: BlockState = BlockState::new(1, 0);
+    const TORCH: BlockState = BlockState::new(50, 5);
+    const WATER: BlockState = BlockState::new(9, 0);
+
+    /// A 3x3 area of columns with a stone floor at y=63
+    fn flat_world(skylight: bool) -> ChunkManager {
+        let mut chunks = ChunkManager::new();
+        for cx in -1..=1 {
+            for cz in -1..=1 {
+                let mut column = ChunkColumn::empty();
+                column.skylight = skylight;
+                for sy in 0..4 {
+                    let s = column.get_section_mut_or_insert(sy);
+                    s.blocks = [STONE; 4096];
+                }
+                chunks.chunks.insert((cx, cz), column);
+            }
+        }
+
+        chunks
+    }
+
+    fn block_light(chunks: &ChunkManager, x: i32, y: i32, z: i32) -> u8 {
+        chunks.get_block_light(x, y, z).0
+    }
+
+    fn sky_light(chunks: &ChunkManager, x: i32, y: i32, z: i32) -> u8 {
+        chunks.get_block_light(x, y, z).1
+    }
+
+    #[test]
+    fn torch_falloff() {
+        let mut chunks = flat_world(false);
+        chunks.set_block(8, 64, 8, TORCH);
+
+        assert_eq!(block_light(&chunks, 8, 64, 8), 14);
+        assert_eq!(block_light(&chunks, 9, 64, 8), 13);
+        assert_eq!(block_light(&chunks, 8, 66, 8), 12);
+        // Manhattan distance
+        assert_eq!(block_light(&chunks, 11, 65, 6), 8);
+        assert_eq!(block_light(&chunks, 8, 64, 22), 0);
+        // The floor doesn't let light through
+        assert_eq!(block_light(&chunks, 8, 63, 8), 0);
+
+        chunks.set_block(8, 64, 8, BlockState::AIR);
+        assert_eq!(block_light(&chunks, 8, 64, 8), 0);
+        assert_eq!(block_light(&chunks, 11, 65, 6), 0);
+    }
+
+    #[test]
+    fn torch_behind_wall() {
+        let mut chunks = flat_world(false);
+        chunks.set_blocks((64..68).flat_map(|y| (-16..32).map(move |z| (9, y, z, STONE))));
+        chunks.set_block(8, 64, 8, TORCH);
+
+        // The light has to go over the wall
+        assert_eq!(block_light(&chunks, 10, 68, 8), 14 - 6);
+        assert_eq!(block_light(&chunks, 10, 64, 8), 14 - 10);
+        assert_eq!(block_light(&chunks, 9, 64, 8), 0);
+
+        // Opening up the wall lets it straight through
+        chunks.set_block(9, 64, 8, BlockState::AIR);
+        assert_eq!(block_light(&chunks, 9, 64, 8), 13);
+        assert_eq!(block_light(&chunks, 10, 64, 8), 12);
+    }
+
+    #[test]
+    fn light_crosses_columns() {
+        let mut chunks = flat_world(false);
+        for column in chunks.chunks.values_mut() {
+            for s in column.sections.iter_mut().flatten() {
+                s.dirty = false;
+            }
+        }
+
+        chunks.set_block(15, 64, 8, TORCH);
+        assert_eq!(block_light(&chunks, 16, 64, 8), 13);
+        assert_eq!(block_light(&chunks, 20, 64, 8), 9);
+        // The neighbouring column gets a new section for the light, and the one below it has to update its top faces
+        let neighbour = chunks.get(&(1, 0)).unwrap();
+        assert!(neighbour.get_section(4).unwrap().dirty);
+        assert!(neighbour.get_section(3).unwrap().dirty);
+        assert!(!chunks.get(&(-1, 0)).unwrap().get_section(3).unwrap().dirty);
+    }
+
+    #[test]
+    fn sky_occlusion() {
+        let mut chunks = flat_world(true);
+        assert_eq!(sky_light(&chunks, 8, 64, 8), 15);
+        assert_eq!(sky_light(&chunks, 8, 300, 8), 15);
+
+        // A single block only blocks the direct sky, light still comes in from the sides
+        chunks.set_block(8, 70, 8, STONE);
+        assert_eq!(sky_light(&chunks, 8, 70, 8), 0);
+        assert_eq!(sky_light(&chunks, 8, 69, 8), 14);
+        assert_eq!(sky_light(&chunks, 8, 64, 8), 14);
+        assert_eq!(sky_light(&chunks, 8, 71, 8), 15);
+
+        // A roof keeps it out further
+        chunks.set_blocks((4..13).flat_map(|x| (4..13).map(move |z| (x, 70, z, STONE))));
+        assert_eq!(sky_light(&chunks, 8, 69, 8), 15 - 5);
+        assert_eq!(sky_light(&chunks, 8, 64, 8), 15 - 5);
+        assert_eq!(sky_light(&chunks, 4, 64, 8), 14);
+
+        // And it all comes back once it's gone
+        chunks.set_blocks((4..13).flat_map(|x| (4..13).map(move |z| (x, 70, z, BlockState::AIR))));
+        assert_eq!(sky_light(&chunks, 8, 70, 8), 15);
+        assert_eq!(sky_light(&chunks, 8, 64, 8), 15);
+    }
+
+    #[test]
+    fn sky_through_water() {
+        let mut chunks = flat_world(true);
+        // A lake that doesn't leave any other way in
+        chunks
+            .set_blocks((-16..32).flat_map(|x| {
+                (-16..32).flat_map(move |z| (64..67).map(move |y| (x, y, z, WATER)))
+            }));
+
+        assert_eq!(sky_light(&chunks, 8, 66, 8), 12);
+        assert_eq!(sky_light(&chunks, 8, 65, 8), 9);
+        assert_eq!(sky_light(&chunks, 8, 64, 8), 6);
+        assert_eq!(sky_light(&chunks, 8, 67, 8), 15);
+    }
+
+    #[test]
+    fn no_sky_without_skylight() {
+        let mut chunks = flat_world(false);
+        chunks.set_block(8, 63, 8, BlockState::AIR);
+        assert_eq!(sky_light(&chunks, 8, 64, 8), 0);
+        assert_eq!(sky_light(&chunks, 8, 63, 8), 0);
+    }
+}
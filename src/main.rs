@@ -7,27 +7,28 @@ mod build_info {
 }
 
 use cgmath::{Euler, MetricSpace, Point3, Quaternion, Vector2, Vector3};
-use flate2::read::ZlibDecoder;
 use rand::Rng;
-use tokio::net::TcpStream;
 
-use std::{
-    io::{Cursor, Read},
-    sync::Arc,
-    time::Instant,
-};
+use std::{path::PathBuf, sync::Arc, time::Instant};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use imgui::FontGlyphRanges;
 use wgpu::util::DeviceExt;
-use world::ChunkManager;
 
-use crate::{
+use minecrab::{
+    anvil::WorldDownloader,
     audio::AudioManager,
-    ecs::{update_interpolation, update_velocity, InterpolatedPosition, Position, Velocity},
+    client::{Client, ClientEvent},
+    ecs::{EntityKind, InterpolatedPosition, Position, ServerId, TICK_DELTA},
     net::{
-        connection::ClientConnection, wrapper::AbstractPacket, ConnectionState, ProtocolVersion,
+        auth::{Authenticator, OfflineAuthenticator, SessionServerAuthenticator},
+        capture::{CaptureReader, CaptureWriter},
+        connection::ClientConnection,
+        status,
+        wrapper::AbstractPacket,
+        ProtocolVersion,
     },
+    physics,
     render::{
         chunk::ChunkRenderer,
         chunk_debug::DebugLineRenderer,
@@ -38,7 +39,6 @@ use crate::{
     },
 };
 
-use crate::net::wrapper::ChunkData;
 use winit::{
     dpi::PhysicalSize,
     event::*,
@@ -46,15 +46,6 @@ use winit::{
     window::WindowBuilder,
 };
 
-mod audio;
-mod ecs;
-mod fixed_point;
-mod net;
-mod physics;
-mod render;
-mod varint;
-mod world;
-
 const ICON_MIN_FA: u32 = 0xe005;
 const ICON_MAX_FA: u32 = 0xf8ff;
 
@@ -71,6 +62,81 @@ struct CliArgs {
 
     #[arg(short, long, default_value = "Nautilus")]
     username: String,
+
+    /// Access token used to join online-mode servers, connects in offline mode if not set
+    #[arg(long, requires = "uuid")]
+    access_token: Option<String>,
+
+    /// UUID of the profile belonging to the access token, without dashes
+    #[arg(long)]
+    uuid: Option<String>,
+
+    /// Session server to authenticate with
+    #[arg(long, default_value = minecrab::net::auth::MOJANG_SESSION_SERVER)]
+    session_server: String,
+
+    /// Protocol version to use, detected using a status query if not set
+    #[arg(long)]
+    protocol: Option<i32>,
+
+    /// Run without a window, GPU or audio device
+    #[arg(long)]
+    headless: bool,
+
+    /// Record every packet sent and received to this file
+    #[arg(long)]
+    capture: Option<PathBuf>,
+
+    /// Play back a capture instead of connecting to a server
+    #[arg(long, conflicts_with = "capture")]
+    replay: Option<PathBuf>,
+
+    /// Save the chunks we receive as an Anvil world in this directory
+    #[arg(long)]
+    download: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Query the server's status (version, players, MOTD) and exit
+    Status {
+        /// Save the server's favicon to this path
+        #[arg(long)]
+        favicon: Option<PathBuf>,
+    },
+}
+
+async fn print_status(
+    address: &str,
+    port: u16,
+    favicon_path: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let status = status::query_status(address, port).await?;
+
+    println!(
+        "Version: {} (protocol {})",
+        status.version.name, status.version.protocol
+    );
+    println!("Players: {}/{}", status.players.online, status.players.max);
+    for p in &status.players.sample {
+        println!("  - {} ({})", p.name, p.id);
+    }
+    println!("MOTD: {}", status.description);
+    println!("Latency: {} ms", status.latency.as_millis());
+
+    match (status.favicon, favicon_path) {
+        (Some(favicon), Some(path)) => {
+            std::fs::write(&path, favicon)?;
+            println!("Favicon saved to {}", path.display());
+        }
+        (None, Some(_)) => println!("Server has no favicon"),
+        _ => {}
+    }
+
+    Ok(())
 }
 
 #[tokio::main]
@@ -79,56 +145,74 @@ async fn main() -> anyhow::Result<()> {
 
     let args = CliArgs::parse();
 
-    let _client = tracy_client::Client::start();
-
-    let mut chunks = ChunkManager::new();
+    if let Some(Command::Status { favicon }) = args.command {
+        return print_status(&args.address, args.port, favicon).await;
+    }
 
-    let stream = TcpStream::connect(format!("{}:{}", args.address, args.port)).await?;
-    let mut connection = ClientConnection::from_stream(stream, ProtocolVersion::Proto1_8);
+    let _client = tracy_client::Client::start();
 
-    connection.write(AbstractPacket::SetProtocol {
-        // protocol_version: varint::VarInt(5),
-        protocol_version: varint::VarInt(47),
-        server_host: args.address,
-        server_port: args.port,
-        next_state: ConnectionState::Login,
-    })?;
+    let mut client = match args.replay {
+        Some(path) => {
+            let capture = CaptureReader::open(&path)?;
+            info!(
+                "Replaying {} (protocol {:?})",
+                path.display(),
+                capture.protocol
+            );
+            Client::new(ClientConnection::from_capture(capture, true))
+        }
+        None => {
+            let authenticator: Arc<dyn Authenticator> = match (args.access_token, args.uuid) {
+                (Some(access_token), Some(profile_uuid)) => Arc::new(SessionServerAuthenticator {
+                    session_server: args.session_server,
+                    access_token,
+                    profile_uuid,
+                }),
+                _ => Arc::new(OfflineAuthenticator),
+            };
+
+            let protocol = match args.protocol {
+                Some(protocol) => ProtocolVersion::from_protocol(protocol)?,
+                None => status::detect_protocol(&args.address, args.port).await?,
+            };
+            info!("Using protocol {:?}", protocol);
+
+            let capture = match &args.capture {
+                Some(path) => Some(CaptureWriter::create(path, protocol)?),
+                None => None,
+            };
+
+            Client::connect(
+                &args.address,
+                args.port,
+                &args.username,
+                protocol,
+                authenticator,
+                capture,
+            )
+            .await?
+        }
+    };
 
-    connection.write(AbstractPacket::LoginStart {
-        username: args.username,
-    })?;
+    if let Some(dir) = args.download {
+        info!("Downloading the world to {}", dir.display());
+        client.downloader = Some(WorldDownloader::new(dir));
+    }
 
-    // Wait for login success
-    while connection.state != ConnectionState::Play {
-        connection.read();
+    if args.headless {
+        let reason = client.run().await?;
+        println!("Disconnected: {}", reason);
+        return Ok(());
     }
 
+    // The camera controller moves the player
+    client.simulate_player = false;
+    client.wait_for_spawn().await?;
+
     let mut camera = Camera::new();
     camera.aspect = 1600 as f32 / 900 as f32;
-
-    // Wait for player pos
-    'w: loop {
-        match connection.read() {
-            Some(AbstractPacket::PositionLookClientBound {
-                pos, pitch, yaw, ..
-            }) => {
-                camera.position = Point3::new(pos.x as f32, pos.y as f32 + 1.62, pos.z as f32);
-                camera.orientation = Vector2::new(pitch, yaw);
-
-                connection.write(AbstractPacket::PositionLookServerBound {
-                    pos,
-                    pitch,
-                    yaw,
-                    on_ground: true,
-                })?;
-
-                connection.write(AbstractPacket::ClientCommand { action_id: 0 })?;
-
-                break 'w;
-            }
-            _ => {}
-        }
-    }
+    camera.position = player_eye_position(&client);
+    camera.orientation = Vector2::new(client.player.pitch, client.player.yaw);
 
     #[cfg(target_os = "linux")]
     std::env::set_var("WINIT_UNIX_BACKEND", "x11");
@@ -346,8 +430,6 @@ async fn main() -> anyhow::Result<()> {
         },
     );
 
-    let mut world = hecs::World::new();
-
     let (chunkmesher_send, mut chunkmesher_recv) =
         chunk_mesher_thread(device.clone(), queue.clone());
 
@@ -368,7 +450,7 @@ async fn main() -> anyhow::Result<()> {
 
     let mut audio_manager = AudioManager::new();
     let mut cursor_grabbed = false;
-    let mut frame_count = 0;
+    let mut tick_accumulator = 0.;
     let mut last_frame = Instant::now();
     let mut chunks_rendered = 0;
     let mut total_chunks = 0;
@@ -379,7 +461,7 @@ async fn main() -> anyhow::Result<()> {
         match event {
             Event::DeviceEvent { ref event, .. } => match event {
                 DeviceEvent::MouseMotion { delta } => {
-                    if cursor_grabbed {
+                    if cursor_grabbed && client.disconnect_reason().is_none() {
                         camera_controller.process_mouse(&mut camera, *delta);
                     }
                 }
@@ -389,7 +471,7 @@ async fn main() -> anyhow::Result<()> {
                 ref event,
                 window_id,
             } if window_id == window.id() => {
-                if !imgui_ctx.io().want_capture_keyboard {
+                if !imgui_ctx.io().want_capture_keyboard && client.disconnect_reason().is_none() {
                     camera_controller.process_events(event);
                 }
 
@@ -422,7 +504,7 @@ async fn main() -> anyhow::Result<()> {
                                 }
                                 VirtualKeyCode::F6 => {
                                     if input.state == ElementState::Pressed {
-                                        chunks.chunks.iter_mut().for_each(|c| {
+                                        client.chunks.chunks.iter_mut().for_each(|c| {
                                             c.1.sections.iter_mut().for_each(|cs| {
                                                 if let Some(cs) = cs {
                                                     cs.dirty = true;
@@ -456,9 +538,6 @@ async fn main() -> anyhow::Result<()> {
                 let frame_delta = last_frame.elapsed().as_secs_f32();
                 imgui_ctx.io_mut().update_delta_time(last_frame.elapsed());
 
-                update_velocity(&mut world, frame_delta);
-                update_interpolation(&mut world, frame_delta);
-
                 audio_manager.maintain();
                 audio_manager.set_listener_transform(
                     camera.position,
@@ -469,299 +548,78 @@ async fn main() -> anyhow::Result<()> {
                     }),
                 );
 
-                // * Receive chunks
-                // ! Yes i know doing this just before rendering a frame isn't great but it doesnt need to be yet.
-                let mut packet_quota = 256;
-                loop {
-                    if let Some(p) = connection.read() {
-                        match p {
-                            AbstractPacket::Chunks(chunks_packet) => match chunks_packet {
-                                ChunkData::Bulk_5(p) => {
-                                    let mut data_offset = 0;
-                                    let mut c = Cursor::new(&p.data);
-                                    let mut z = ZlibDecoder::new(&mut c);
-
-                                    let mut data = Vec::new();
-                                    if z.read_to_end(&mut data).is_err() {
-                                        warn!("Chunk data failed to decompress");
-                                        continue;
-                                    }
-
-                                    for (_i, cm) in p.meta.iter().enumerate() {
-                                        let bytes_read = chunks
-                                            .load_chunk_5(
-                                                (cm.chunk_x, cm.chunk_z),
-                                                cm.primary_bitmap,
-                                                cm.add_bitmap,
-                                                p.sky_light_sent,
-                                                true,
-                                                &data[data_offset..],
-                                            )
-                                            .unwrap();
-                                        data_offset += bytes_read as usize;
-                                    }
-
-                                    if data_offset < data.len() {
-                                        warn!("Trailing data in chunk batch!");
-                                    }
-                                }
-                                ChunkData::Bulk_47(p) => {
-                                    let mut data_offset = 0;
-
-                                    for (_i, cm) in p.meta.data.iter().enumerate() {
-                                        let bytes_read = chunks
-                                            .load_chunk_47(
-                                                (cm.chunk_x, cm.chunk_z),
-                                                cm.bitmap,
-                                                p.sky_light_sent,
-                                                true,
-                                                &p.data[data_offset..],
-                                            )
-                                            .unwrap();
-                                        data_offset += bytes_read as usize;
-                                    }
+                // Tick at a fixed rate, independent of the frame rate, until the connection is closed
+                tick_accumulator += frame_delta;
+                while tick_accumulator >= TICK_DELTA && client.disconnect_reason().is_none() {
+                    tick_accumulator -= TICK_DELTA;
+
+                    client.player.position = Point3::new(
+                        camera.position.x as f64,
+                        camera.position.y as f64 - 1.62,
+                        camera.position.z as f64,
+                    );
+                    client.player.yaw = camera.orientation.y;
+                    client.player.pitch = camera.orientation.x;
+
+                    if let Err(e) = client.tick() {
+                        error!("{e}");
+                        *control_flow = ControlFlow::Exit;
+                    }
+                }
 
-                                    if data_offset < p.data.len() {
-                                        warn!(
-                                            "Trailing data in chunk batch! ({} bytes left)",
-                                            p.data.len() - data_offset
-                                        );
-                                    }
-                                }
-                                ChunkData::Single_5(p) => {
-                                    let mut c = Cursor::new(&p.compressed_chunk_data.data);
-                                    let mut z = ZlibDecoder::new(&mut c);
-
-                                    let mut data = Vec::new();
-                                    if z.read_to_end(&mut data).is_err() {
-                                        warn!("Chunk data failed to decompress");
-                                        continue;
-                                    }
+                while let Some(event) = client.poll_event() {
+                    match event {
+                        ClientEvent::Disconnected(reason) => {
+                            error!("Disconnected: {}", reason);
 
-                                    chunks
-                                        .load_chunk_5(
-                                            (p.x, p.z),
-                                            p.bit_map,
-                                            p.add_bit_map,
-                                            false,
-                                            p.ground_up,
-                                            &data,
-                                        )
+                            // Free the cursor so the disconnect window can be used
+                            cursor_grabbed = false;
+                            window.set_cursor_grab(false).ok();
+                            window.set_cursor_visible(true);
+                        }
+                        ClientEvent::Spawned | ClientEvent::Teleported => {
+                            camera.position = player_eye_position(&client);
+                        }
+                        ClientEvent::Packet(AbstractPacket::NamedSoundEffect {
+                            pos,
+                            pitch,
+                            volume,
+                            sound_name,
+                            ..
+                        }) => {
+                            let path_glob = format!(
+                                "assets/minecraft/sounds/{}*.ogg",
+                                sound_name.replace('.', "/")
+                            );
+                            if let Ok(paths) = glob::glob(&path_glob) {
+                                let paths: Vec<std::path::PathBuf> =
+                                    paths.filter(|p| p.is_ok()).map(|p| p.unwrap()).collect();
+                                if paths.len() > 0 {
+                                    let r = rand::thread_rng().gen::<usize>();
+                                    let path = paths[r % paths.len()]
+                                        .clone()
+                                        .into_os_string()
+                                        .into_string()
                                         .unwrap();
-                                }
-                                ChunkData::Single_47(p) => {
-                                    chunks
-                                        .load_chunk_47(
-                                            (p.x, p.z),
-                                            p.bit_map,
-                                            false,
-                                            p.ground_up,
-                                            &p.chunk_data.data,
+                                    audio_manager
+                                        .play(
+                                            &path,
+                                            Point3::new(pos.x as f32, pos.y as f32, pos.z as f32),
+                                            volume,
+                                            pitch,
                                         )
-                                        .unwrap();
-                                }
-                                _ => {
-                                    error!(
-                                        "Unhandled chunk packet {:?}",
-                                        <&'static str>::from(chunks_packet)
-                                    )
+                                        .ok();
                                 }
-                            },
-                            AbstractPacket::Explosion {
-                                pos,
-                                affected_block_offsets,
-                                ..
-                            } => {
-                                for r in affected_block_offsets {
-                                    chunks.set_block(
-                                        pos.x as i32 + r.0 as i32,
-                                        pos.y as i32 + r.1 as i32,
-                                        pos.z as i32 + r.2 as i32,
-                                        0,
-                                    )
-                                }
-                            }
-                            AbstractPacket::Respawn { .. } => {
-                                chunks.chunks.clear();
-
-                                // Shrink to reclaim memory
-                                chunks.chunks.shrink_to_fit();
-                            }
-                            AbstractPacket::BlockChange { kind, location } => {
-                                println!(
-                                    "Block change {} {} {} {}",
-                                    location.x, location.y, location.z, kind
-                                );
-                                chunks.set_block(
-                                    location.x,
-                                    location.y,
-                                    location.z,
-                                    (kind >> 4) as u8,
-                                );
-                            }
-                            // net::packets::Packet::MultiBlockChange_47(p) => {
-                            //     for r in p.records.data {
-                            //         let offset_x = (r.pos_horizontal & 0xf0) >> 4;
-                            //         let offset_z = r.pos_horizontal & 0x0f;
-                            //         // println!(
-                            //         //     "Multiblock change record {} {} {} {}",
-                            //         //     p.chunk_x * 16 + offset_x as i32,
-                            //         //     r.y as i32,
-                            //         //     p.chunk_z * 16 + offset_z as i32,
-                            //         //     r.block_id.0,
-                            //         // );
-                            //         chunks.set_block(
-                            //             p.chunk_x * 16 + offset_x as i32,
-                            //             r.y as i32,
-                            //             p.chunk_z * 16 + offset_z as i32,
-                            //             (r.block_id.0 >> 4) as u8,
-                            //         );
-                            //     }
-                            // }
-                            // net::packets::Packet::EntityMoveLook_47(p) => {
-                            //     let ent = ecs::get_or_insert(&mut world, p.entity_id.0);
-                            //     if let Ok((pos, interp)) = world.query_one_mut::<(
-                            //         &mut Position,
-                            //         &mut InterpolatedPosition,
-                            //     )>(
-                            //         ent
-                            //     ) {
-                            //         pos.0 += Vector3::new(
-                            //             p.d_x.0 as f32,
-                            //             p.d_y.0 as f32,
-                            //             p.d_z.0 as f32,
-                            //         );
-                            //
-                            //         interp.delta = ecs::TICK_DELTA
-                            //     }
-                            // }
-                            // net::packets::Packet::RelEntityMove_47(p) => {
-                            //     let ent = ecs::get_or_insert(&mut world, p.entity_id.0);
-                            //     if let Ok((pos, interp)) = world.query_one_mut::<(
-                            //         &mut Position,
-                            //         &mut InterpolatedPosition,
-                            //     )>(
-                            //         ent
-                            //     ) {
-                            //         pos.0 += Vector3::new(
-                            //             p.d_x.0 as f32,
-                            //             p.d_y.0 as f32,
-                            //             p.d_z.0 as f32,
-                            //         );
-                            //
-                            //         interp.delta = ecs::TICK_DELTA
-                            //     }
-                            // }
-                            // net::packets::Packet::EntityVelocity_47(p) => {
-                            //     let ent = ecs::get_or_insert(&mut world, p.entity_id.0);
-                            //     if let Ok((_pos, v)) =
-                            //         world.query_one_mut::<(&mut Position, &mut Velocity)>(ent)
-                            //     {
-                            //         v.0 = Vector3::new(
-                            //             p.velocity_x as f32,
-                            //             p.velocity_y as f32,
-                            //             p.velocity_z as f32,
-                            //         ) * ecs::VELOCITY_UNIT;
-                            //     }
-                            // }
-                            // net::packets::Packet::EntityTeleport_47(p) => {
-                            //     let ent = ecs::get_or_insert(&mut world, p.entity_id.0);
-                            //     if let Ok((pos, interp)) = world.query_one_mut::<(
-                            //         &mut Position,
-                            //         &mut InterpolatedPosition,
-                            //     )>(
-                            //         ent
-                            //     ) {
-                            //         pos.0 = Point3::new(p.x.0 as f32, p.y.0 as f32, p.z.0 as f32);
-                            //
-                            //         interp.delta = ecs::TICK_DELTA / 5.;
-                            //     }
-                            // }
-                            // net::packets::Packet::SpawnEntityLiving_5(p) => {
-                            //     let ent = ecs::get_or_insert(&mut world, p.entity_id.0);
-                            //     if let Ok((pos, v)) =
-                            //         world.query_one_mut::<(&mut Position, &mut Velocity)>(ent)
-                            //     {
-                            //         pos.0 = Point3::new(p.x.0 as f32, p.y.0 as f32, p.z.0 as f32);
-                            //         v.0 = Vector3::new(
-                            //             p.velocity_x as f32,
-                            //             p.velocity_y as f32,
-                            //             p.velocity_z as f32,
-                            //         ) * ecs::VELOCITY_UNIT;
-                            //     }
-                            // }
-                            // net::packets::Packet::SpawnEntity_5(p) => {
-                            //     let ent = ecs::get_or_insert(&mut world, p.entity_id.0);
-                            //     if let Ok(pos) = world.query_one_mut::<&mut Position>(ent) {
-                            //         pos.0 = Point3::new(p.x.0 as f32, p.y.0 as f32, p.z.0 as f32);
-                            //     }
-                            // }
-                            // net::packets::Packet::NamedEntitySpawn_47(p) => {
-                            //     let ent = ecs::get_or_insert(&mut world, p.entity_id.0);
-                            //     if let Ok(pos) = world.query_one_mut::<&mut Position>(ent) {
-                            //         pos.0 = Point3::new(p.x.0 as f32, p.y.0 as f32, p.z.0 as f32);
-                            //     }
-                            // }
-                            // net::packets::Packet::EntityDestroy_47(p) => {
-                            //     for e in p.entity_ids.data {
-                            //         let eid = ecs::get_or_insert(&mut world, e.0);
-                            //         world.despawn(eid).ok();
-                            //     }
-                            // }
-                            AbstractPacket::NamedSoundEffect {
-                                pos,
-                                pitch,
-                                volume,
-                                sound_name,
-                                ..
-                            } => {
-                                let path_glob = format!(
-                                    "assets/minecraft/sounds/{}*.ogg",
-                                    sound_name.replace('.', "/")
-                                );
-                                if let Ok(paths) = glob::glob(&path_glob) {
-                                    let paths: Vec<std::path::PathBuf> =
-                                        paths.filter(|p| p.is_ok()).map(|p| p.unwrap()).collect();
-                                    if paths.len() > 0 {
-                                        let r = rand::thread_rng().gen::<usize>();
-                                        let path = paths[r % paths.len()]
-                                            .clone()
-                                            .into_os_string()
-                                            .into_string()
-                                            .unwrap();
-                                        audio_manager
-                                            .play(
-                                                &path,
-                                                Point3::new(
-                                                    pos.x as f32,
-                                                    pos.y as f32,
-                                                    pos.z as f32,
-                                                ),
-                                                volume,
-                                                pitch,
-                                            )
-                                            .ok();
-                                    }
-                                };
-                            }
-                            AbstractPacket::PositionLookClientBound { pos, .. } => {
-                                camera.position =
-                                    Point3::new(pos.x as f32, pos.y as f32 + 1.62, pos.z as f32);
-                            }
-                            _ => {}
+                            };
                         }
-                    } else {
-                        break;
-                    }
-
-                    packet_quota -= 1;
-                    if packet_quota == 0 {
-                        warn!("Packet quota reached!");
-                        break;
+                        ClientEvent::Packet(AbstractPacket::ChatMessage { message, .. }) => {
+                            info!("[chat] {}", message);
+                        }
+                        _ => {}
                     }
                 }
 
-                chunks.chunks.retain(|c, _| {
+                client.evict_chunks(|c| {
                     let chunkpos_real = Vector2::new(c.0 as f32, c.1 as f32) * 16.;
 
                     if chunkpos_real.distance(camera.position.to_homogeneous().xz())
@@ -773,7 +631,8 @@ async fn main() -> anyhow::Result<()> {
                     }
                 });
 
-                let dirty_chunk_count = chunks
+                let dirty_chunk_count = client
+                    .chunks
                     .chunks
                     .iter()
                     .map(|c| {
@@ -792,14 +651,12 @@ async fn main() -> anyhow::Result<()> {
                 let mut dirty_chunks = vec![];
                 if dirty_chunk_count != 0 {
                     let mut chunk_meshing_quota = 8;
-                    for (coord, chunk) in chunks.chunks.iter_mut() {
-                        for cy in 0..16 {
-                            if let Some(cd) = chunk.get_section_mut(cy) {
-                                if cd.dirty {
-                                    dirty_chunks.push((coord.0, cy, coord.1));
+                    for (coord, chunk) in client.chunks.chunks.iter_mut() {
+                        for (cy, cd) in chunk.iter_sections() {
+                            if cd.dirty {
+                                dirty_chunks.push((coord.0, cy, coord.1));
 
-                                    chunk_meshing_quota -= 1;
-                                }
+                                chunk_meshing_quota -= 1;
                             }
                         }
 
@@ -812,15 +669,16 @@ async fn main() -> anyhow::Result<()> {
                 // dirty_chunks.dedup();
 
                 for c in &mut dirty_chunks {
-                    let data = ChunkSectionContext::new(&chunks, Point3::new(c.0, c.1 as i32, c.2));
+                    let data = ChunkSectionContext::new(&client.chunks, Point3::new(c.0, c.1, c.2));
 
-                    if let Some(cd) = chunks
+                    if let Some(cd) = client
+                        .chunks
                         .get_mut(&(c.0, c.2))
                         .and_then(|cc| cc.get_section_mut(c.1))
                     {
                         if chunkmesher_send
                             .try_send(ChunkMeshingRequest {
-                                chunk_pos: Point3::new(c.0, c.1 as i32, c.2),
+                                chunk_pos: Point3::new(c.0, c.1, c.2),
                                 data,
                                 buffers: None,
                             })
@@ -833,52 +691,39 @@ async fn main() -> anyhow::Result<()> {
 
                 // Get finished chunks from the chunk mesher thread
                 while let Ok(rd) = chunkmesher_recv.try_recv() {
-                    if let Some(cd) = chunks
+                    if let Some(cd) = client
+                        .chunks
                         .get_mut(&(rd.position.x, rd.position.z))
-                        .and_then(|cc| cc.get_section_mut(rd.position.y as u8))
+                        .and_then(|cc| cc.get_section_mut(rd.position.y))
                     {
                         cd.renderdata = Some(rd);
                     }
                 }
 
-                // Send player position every tick instead of every frame
-                // FIXME: If you dont have vsync enabled (or a 60hz monitor) this is going to hurt
-                if frame_count % 3 == 0 {
-                    connection
-                        .write(AbstractPacket::PositionLookServerBound {
-                            pos: Point3::new(
-                                camera.position.x as f64,
-                                camera.position.y as f64 - 1.62,
-                                camera.position.z as f64,
-                            ),
-                            yaw: camera.orientation.y,
-                            pitch: camera.orientation.x,
-                            on_ground: false,
-                        })
-                        .ok();
-                }
-
                 last_frame = Instant::now();
-                camera_controller.update_camera(&mut camera, frame_delta);
-                let mut next_pos = physics::calculate_next_player_pos(
-                    &chunks,
-                    camera.position - Vector3::new(0.3, 1.62, 0.3),
-                    Vector3::new(camera_controller.velocity.x, 0., 0.),
-                );
-
-                next_pos = physics::calculate_next_player_pos(
-                    &chunks,
-                    next_pos,
-                    Vector3::new(0., 0., camera_controller.velocity.z),
-                );
-
-                next_pos = physics::calculate_next_player_pos(
-                    &chunks,
-                    next_pos,
-                    Vector3::new(0., camera_controller.velocity.y, 0.),
-                );
-
-                camera.position = next_pos + Vector3::new(0.3, 1.62, 0.3);
+                let connected = client.disconnect_reason().is_none();
+                if connected {
+                    camera_controller.update_camera(&mut camera, frame_delta);
+                    let mut next_pos = physics::calculate_next_player_pos(
+                        &client.chunks,
+                        camera.position - Vector3::new(0.3, 1.62, 0.3),
+                        Vector3::new(camera_controller.velocity.x, 0., 0.),
+                    );
+
+                    next_pos = physics::calculate_next_player_pos(
+                        &client.chunks,
+                        next_pos,
+                        Vector3::new(0., 0., camera_controller.velocity.z),
+                    );
+
+                    next_pos = physics::calculate_next_player_pos(
+                        &client.chunks,
+                        next_pos,
+                        Vector3::new(0., camera_controller.velocity.y, 0.),
+                    );
+
+                    camera.position = next_pos + Vector3::new(0.3, 1.62, 0.3);
+                }
                 camera_uniform.update_view_proj(&mut camera);
                 queue.write_buffer(&camera_buffer, 0, bytemuck::cast_slice(&[camera_uniform]));
 
@@ -938,15 +783,22 @@ async fn main() -> anyhow::Result<()> {
                     imgui::Slider::new("FOV", 30., 110.).build(&ui, &mut camera.fovy);
                 });
 
+                // There's nobody to send messages to once disconnected
                 imgui::Window::new("Chat").build(&ui, || {
+                    if !connected {
+                        ui.text_disabled("Disconnected");
+                        return;
+                    }
+
                     let enter_hit = ui
                         .input_text("Message", &mut chatmsg_buf)
                         .enter_returns_true(true)
                         .build();
 
                     if enter_hit || ui.button("Send") {
-                        if let Err(e) =
-                            connection.write(AbstractPacket::ChatServerbound(chatmsg_buf.clone()))
+                        if let Err(e) = client
+                            .connection
+                            .write(AbstractPacket::ChatServerbound(chatmsg_buf.clone()))
                         {
                             error!("{e}");
                         }
@@ -954,7 +806,7 @@ async fn main() -> anyhow::Result<()> {
                     }
 
                     if ui.button("TNT (40 ticks)") {
-                        if let Err(e) = connection.write(AbstractPacket::ChatServerbound(
+                        if let Err(e) = client.connection.write(AbstractPacket::ChatServerbound(
                             "/summon PrimedTnt ~ ~ ~ {Ticks: 40}".to_owned(),
                         )) {
                             error!("{e}");
@@ -963,14 +815,32 @@ async fn main() -> anyhow::Result<()> {
                 });
 
                 imgui::Window::new("Entities").build(&ui, || {
-                    for (e, pos) in world.query::<&Position>().iter() {
-                        ui.text(format!("{:?} - {:?}", e, pos));
+                    for (_, (id, kind, pos)) in client
+                        .world
+                        .query::<(&ServerId, &EntityKind, &Position)>()
+                        .iter()
+                    {
+                        ui.text(format!("{} {:?} - {:?}", id.0, kind, pos));
                     }
                 });
 
-                // if thread_net_recv.is_finished() {
-                //     *control_flow = ControlFlow::Exit;
-                // }
+                if let Some(reason) = client.disconnect_reason() {
+                    let size = window.inner_size();
+                    imgui::Window::new("Disconnected")
+                        .position(
+                            [size.width as f32 / 2., size.height as f32 / 2.],
+                            imgui::Condition::Always,
+                        )
+                        .position_pivot([0.5, 0.5])
+                        .always_auto_resize(true)
+                        .collapsible(false)
+                        .build(&ui, || {
+                            ui.text(reason.to_string());
+                            if ui.button("Quit") {
+                                *control_flow = ControlFlow::Exit;
+                            }
+                        });
+                }
 
                 let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
                     label: Some("Render Encoder"),
@@ -1010,7 +880,7 @@ async fn main() -> anyhow::Result<()> {
 
                     chunks_rendered = 0;
                     total_chunks = 0;
-                    for (_, c) in chunks.chunks.iter() {
+                    for (_, c) in client.chunks.chunks.iter() {
                         for section in &c.sections {
                             if let Some(s) = section {
                                 if let Some(cr) = &s.renderdata {
@@ -1062,7 +932,7 @@ async fn main() -> anyhow::Result<()> {
                     render_pass.set_pipeline(&debugcube_pipeline);
                     render_pass.set_bind_group(0, &camera_bind_group, &[]);
                     render_pass.set_bind_group(1, &texture_bind_group_debugcube, &[]);
-                    for (_, position) in world.query::<&InterpolatedPosition>().iter() {
+                    for (_, position) in client.world.query::<&InterpolatedPosition>().iter() {
                         debugcube.render(
                             &mut render_pass,
                             position.position + Vector3::new(0., 0.5, 0.),
@@ -1102,15 +972,20 @@ async fn main() -> anyhow::Result<()> {
 
                 queue.submit(std::iter::once(encoder.finish()));
                 output.present();
-                frame_count += 1;
                 profiling::finish_frame!();
             }
             Event::MainEventsCleared => {
                 window.request_redraw();
             }
+            Event::LoopDestroyed => client.save_world(),
             _ => {}
         }
 
         platform.handle_event(imgui_ctx.io_mut(), &window, &event);
     })
 }
+
+fn player_eye_position(client: &Client) -> Point3<f32> {
+    let pos = client.player.position;
+    Point3::new(pos.x as f32, pos.y as f32 + 1.62, pos.z as f32)
+}
@@ -0,0 +1,57 @@
+use anyhow::ensure;
+use serde::Serialize;
+
+pub const MOJANG_SESSION_SERVER: &str = "https://sessionserver.mojang.com";
+
+/// Authenticates the client with a session server before the encryption response is sent
+///
+/// This is called from a blocking context, so implementations are free to do blocking I/O
+pub trait Authenticator: Send + Sync {
+    fn join(&self, server_hash: &str) -> anyhow::Result<()>;
+}
+
+/// Offline-mode, skips the session server entirely
+pub struct OfflineAuthenticator;
+
+impl Authenticator for OfflineAuthenticator {
+    fn join(&self, _server_hash: &str) -> anyhow::Result<()> {
+        debug!("Offline mode, not contacting the session server");
+        Ok(())
+    }
+}
+
+/// Online-mode, see https://wiki.vg/Protocol_Encryption#Client
+pub struct SessionServerAuthenticator {
+    /// Base URL of the session server, without trailing slash
+    pub session_server: String,
+    pub access_token: String,
+    /// Profile UUID without dashes
+    pub profile_uuid: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JoinRequest<'a> {
+    access_token: &'a str,
+    selected_profile: &'a str,
+    server_id: &'a str,
+}
+
+impl Authenticator for SessionServerAuthenticator {
+    fn join(&self, server_hash: &str) -> anyhow::Result<()> {
+        let response = ureq::post(&format!("{}/session/minecraft/join", self.session_server))
+            .send_json(JoinRequest {
+                access_token: &self.access_token,
+                selected_profile: &self.profile_uuid,
+                server_id: server_hash,
+            })?;
+
+        ensure!(
+            response.status() == 204,
+            "Session server responded with status {}",
+            response.status()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use anyhow::ensure;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use num_traits::{FromPrimitive, ToPrimitive};
+
+use crate::varint::{ReadProtoExt, WriteProtoExt};
+
+use super::{
+    codec::RawPacket, packets::Packet, versions::decode_packet, ConnectionState, PacketDirection,
+    ProtocolVersion,
+};
+
+// A capture starts with the magic, the format version and the protocol version of the session.
+// It's followed by a record for every packet: the microseconds since the capture started (u64), the connection state (u8),
+// the direction (u8, 0 for clientbound), the packet id (varint) and the length-prefixed packet data.
+// Packets are stored decompressed and decrypted, so they can be fed straight into `decode_packet`.
+const MAGIC: &[u8; 8] = b"MCRABCAP";
+const FORMAT_VERSION: u16 = 1;
+
+#[derive(Debug)]
+pub struct CapturedPacket {
+    /// Time since the capture was started
+    pub timestamp: Duration,
+    pub state: ConnectionState,
+    pub direction: PacketDirection,
+    pub packet: RawPacket,
+}
+
+impl CapturedPacket {
+    pub fn decode(&self, protocol: ProtocolVersion) -> super::error::Result<Packet> {
+        decode_packet(protocol, &self.packet, self.state, self.direction)
+    }
+}
+
+pub struct CaptureWriter {
+    writer: Box<dyn Write + Send>,
+    start: Instant,
+}
+
+impl CaptureWriter {
+    pub fn create<P: AsRef<Path>>(path: P, protocol: ProtocolVersion) -> anyhow::Result<Self> {
+        Self::new(File::create(path)?, protocol)
+    }
+
+    /// Writes the capture header to `writer`
+    pub fn new<W: Write + Send + 'static>(
+        mut writer: W,
+        protocol: ProtocolVersion,
+    ) -> anyhow::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_u16::<BigEndian>(FORMAT_VERSION)?;
+        writer.write_i32::<BigEndian>(protocol.to_i32().unwrap())?;
+        writer.flush()?;
+
+        Ok(Self {
+            writer: Box::new(writer),
+            start: Instant::now(),
+        })
+    }
+
+    pub fn record(
+        &mut self,
+        state: ConnectionState,
+        direction: PacketDirection,
+        packet: &RawPacket,
+    ) -> anyhow::Result<()> {
+        let mut buf = vec![];
+        buf.write_u64::<BigEndian>(self.start.elapsed().as_micros() as u64)?;
+        buf.write_u8(state.to_u8().unwrap())?;
+        buf.write_u8(match direction {
+            PacketDirection::Client => 0,
+            PacketDirection::Server => 1,
+        })?;
+        buf.write_varint(packet.id)?;
+        buf.write_varint(packet.data.len() as i32)?;
+        buf.extend_from_slice(&packet.data);
+
+        // Flushed after every packet, so the capture is still usable if the client crashes
+        self.writer.write_all(&buf)?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+pub struct CaptureReader<R: Read> {
+    /// Protocol version of the recorded session
+    pub protocol: ProtocolVersion,
+    reader: R,
+}
+
+impl CaptureReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Reads the capture header from `reader`
+    pub fn new(mut reader: R) -> anyhow::Result<Self> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        ensure!(&magic == MAGIC, "Not a packet capture");
+
+        let format_version = reader.read_u16::<BigEndian>()?;
+        ensure!(
+            format_version == FORMAT_VERSION,
+            "Unsupported capture format version {}",
+            format_version
+        );
+
+        let protocol = ProtocolVersion::from_protocol(reader.read_i32::<BigEndian>()?)?;
+
+        Ok(Self { protocol, reader })
+    }
+
+    /// Returns `None` at the end of the capture
+    pub fn next_packet(&mut self) -> anyhow::Result<Option<CapturedPacket>> {
+        let timestamp = match self.reader.read_u64::<BigEndian>() {
+            Ok(t) => Duration::from_micros(t),
+            // A capture that was cut off mid-record is treated as if it ended there
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let state = self.reader.read_u8()?;
+        let state = ConnectionState::from_u8(state)
+            .ok_or_else(|| anyhow::anyhow!("Invalid connection state {} in capture", state))?;
+        let direction = match self.reader.read_u8()? {
+            0 => PacketDirection::Client,
+            1 => PacketDirection::Server,
+            d => anyhow::bail!("Invalid packet direction {} in capture", d),
+        };
+
+        let id = self.reader.read_varint()?;
+        let len = self.reader.read_varint()?;
+        ensure!(len >= 0, "Invalid packet length {} in capture", len);
+        let mut data = vec![0u8; len as usize];
+        self.reader.read_exact(&mut data)?;
+
+        Ok(Some(CapturedPacket {
+            timestamp,
+            state,
+            direction,
+            packet: RawPacket { id, data },
+        }))
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = anyhow::Result<CapturedPacket>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_packet().transpose()
+    }
+}
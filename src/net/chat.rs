@@ -0,0 +1,142 @@
+use std::fmt;
+
+use serde::Deserialize;
+
+/// JSON text component, see https://wiki.vg/Chat
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(from = "RawChatComponent")]
+pub struct ChatComponent {
+    pub text: String,
+    /// Translation key, `with` holds the arguments
+    pub translate: Option<String>,
+    pub with: Vec<ChatComponent>,
+    pub color: Option<String>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underlined: bool,
+    pub strikethrough: bool,
+    pub obfuscated: bool,
+    pub extra: Vec<ChatComponent>,
+}
+
+/// Components can also be plain strings, or arrays where the first element is the parent of the rest
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawChatComponent {
+    Text(String),
+    Array(Vec<ChatComponent>),
+    Object {
+        #[serde(default)]
+        text: String,
+        translate: Option<String>,
+        #[serde(default)]
+        with: Vec<ChatComponent>,
+        color: Option<String>,
+        #[serde(default)]
+        bold: bool,
+        #[serde(default)]
+        italic: bool,
+        #[serde(default)]
+        underlined: bool,
+        #[serde(default)]
+        strikethrough: bool,
+        #[serde(default)]
+        obfuscated: bool,
+        #[serde(default)]
+        extra: Vec<ChatComponent>,
+    },
+}
+
+impl From<RawChatComponent> for ChatComponent {
+    fn from(raw: RawChatComponent) -> Self {
+        match raw {
+            RawChatComponent::Text(text) => Self::from(text),
+            RawChatComponent::Array(mut components) => {
+                if components.is_empty() {
+                    return Self::default();
+                }
+
+                let mut parent = components.remove(0);
+                parent.extra.extend(components);
+                parent
+            }
+            RawChatComponent::Object {
+                text,
+                translate,
+                with,
+                color,
+                bold,
+                italic,
+                underlined,
+                strikethrough,
+                obfuscated,
+                extra,
+            } => Self {
+                text,
+                translate,
+                with,
+                color,
+                bold,
+                italic,
+                underlined,
+                strikethrough,
+                obfuscated,
+                extra,
+            },
+        }
+    }
+}
+
+impl From<String> for ChatComponent {
+    fn from(text: String) -> Self {
+        Self {
+            text,
+            ..Default::default()
+        }
+    }
+}
+
+impl ChatComponent {
+    /// Parses a JSON component, falling back to treating `s` as plain text if it isn't valid JSON
+    pub fn from_json(s: &str) -> Self {
+        serde_json::from_str(s).unwrap_or_else(|_| Self::from(s.to_string()))
+    }
+
+    /// The text of this component and its children, without any formatting
+    pub fn to_plain_text(&self) -> String {
+        let mut out = String::new();
+        self.write_plain_text(&mut out);
+        out
+    }
+
+    fn write_plain_text(&self, out: &mut String) {
+        // Legacy formatting codes are a section sign followed by a single character
+        let mut chars = self.text.chars();
+        while let Some(c) = chars.next() {
+            if c == '§' {
+                chars.next();
+            } else {
+                out.push(c);
+            }
+        }
+
+        // We don't have the translation files, so just show the key and its arguments
+        if let Some(key) = &self.translate {
+            out.push_str(key);
+            if !self.with.is_empty() {
+                let args: Vec<String> = self.with.iter().map(|c| c.to_plain_text()).collect();
+                out.push_str(&format!(" [{}]", args.join(", ")));
+            }
+        }
+
+        for c in &self.extra {
+            c.write_plain_text(out);
+        }
+    }
+}
+
+impl fmt::Display for ChatComponent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_plain_text())
+    }
+}
@@ -1,12 +1,42 @@
+use std::io::{self, Cursor, Read, Write};
+
 use anyhow::ensure;
-use tokio::{
-    io::AsyncReadExt,
-    net::tcp::{OwnedReadHalf, OwnedWriteHalf},
-};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::varint::{read_varint, ReadProtoExt, WriteProtoExt};
+
+/// Largest frame vanilla accepts, its length has to fit in a 3 byte VarInt
+pub const MAX_FRAME_LEN: i32 = 1 << 21;
+/// Largest packet vanilla accepts once decompressed
+pub const MAX_DATA_LEN: i32 = 1 << 23;
 
-use crate::varint::{read_varint, varint_len, WriteProtoExt};
+/// Reads a single frame (everything after the packet length) without decoding it
+///
+/// Returns `None` if the connection was closed before the next frame started.
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> anyhow::Result<Option<Vec<u8>>> {
+    let len = match read_varint(reader).await {
+        Ok(len) => len,
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    ensure!(
+        len > 0 && len <= MAX_FRAME_LEN,
+        "Invalid packet length {}",
+        len
+    );
+
+    let mut frame = vec![0u8; len as usize];
+    reader.read_exact(&mut frame).await?;
+
+    Ok(Some(frame))
+}
 
-pub struct MinecraftCodec;
+#[derive(Default)]
+pub struct MinecraftCodec {
+    /// Packets with an uncompressed size of at least this many bytes are compressed, `None` if compression is disabled
+    pub compression_threshold: Option<i32>,
+}
 
 #[derive(Debug)]
 pub struct RawPacket {
@@ -15,28 +45,112 @@ pub struct RawPacket {
 }
 
 impl MinecraftCodec {
-    pub async fn read(reader: &mut OwnedReadHalf) -> anyhow::Result<RawPacket> {
-        let len = read_varint(reader).await?;
-        let id = read_varint(reader).await?;
+    /// Applies the threshold sent by the server in SetCompression, negative values disable compression
+    pub fn set_compression(&mut self, threshold: i32) {
+        self.compression_threshold = if threshold >= 0 {
+            Some(threshold)
+        } else {
+            None
+        };
+    }
+
+    pub async fn read<R: AsyncRead + Unpin>(&self, reader: &mut R) -> anyhow::Result<RawPacket> {
+        match read_frame(reader).await? {
+            Some(frame) => self.decode_frame(&frame),
+            None => Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+        }
+    }
+
+    pub async fn write<W: AsyncWrite + Unpin>(
+        &self,
+        writer: &mut W,
+        packet: &RawPacket,
+    ) -> anyhow::Result<()> {
+        let frame = self.encode_frame(packet)?;
+
+        let mut buf = vec![];
+        buf.write_varint(frame.len() as i32)?;
+        buf.extend_from_slice(&frame);
+
+        writer.write_all(&buf).await?;
+        writer.flush().await?;
+
+        Ok(())
+    }
+
+    /// Decodes a single frame (everything after the packet length)
+    pub fn decode_frame(&self, frame: &[u8]) -> anyhow::Result<RawPacket> {
+        let mut c = Cursor::new(frame);
 
-        let mut data = vec![0u8; len as usize - varint_len(id)];
-        reader.read_exact(&mut data).await?;
+        let body = if let Some(threshold) = self.compression_threshold {
+            let data_len = c.read_varint()?;
 
-        ensure!((data.len() + varint_len(id)) == len as usize);
+            if data_len == 0 {
+                frame[c.position() as usize..].to_vec()
+            } else {
+                // Like vanilla, packets below the threshold must be sent uncompressed
+                ensure!(
+                    data_len >= threshold,
+                    "Compressed packet is {} bytes, below the threshold of {}",
+                    data_len,
+                    threshold
+                );
+                ensure!(
+                    data_len <= MAX_DATA_LEN,
+                    "Compressed packet is {} bytes, more than the maximum of {}",
+                    data_len,
+                    MAX_DATA_LEN
+                );
+
+                // Read one byte past the declared length, so data that inflates to more than that is caught
+                let mut body = vec![];
+                ZlibDecoder::new(&mut c)
+                    .take(data_len as u64 + 1)
+                    .read_to_end(&mut body)?;
+                ensure!(
+                    body.len() == data_len as usize,
+                    "Decompressed packet is {} bytes, expected {}",
+                    body.len(),
+                    data_len
+                );
+
+                body
+            }
+        } else {
+            frame.to_vec()
+        };
+
+        let mut c = Cursor::new(&body);
+        let id = c.read_varint()?;
+        let data = body[c.position() as usize..].to_vec();
 
         Ok(RawPacket { id, data })
     }
 
-    pub async fn write(writer: &mut OwnedWriteHalf, packet: &RawPacket) -> anyhow::Result<()> {
-        let len = varint_len(packet.id) + packet.data.len();
+    /// Encodes a packet into a single frame (everything after the packet length)
+    pub fn encode_frame(&self, packet: &RawPacket) -> anyhow::Result<Vec<u8>> {
+        let mut body = vec![];
+        body.write_varint(packet.id)?;
+        body.extend_from_slice(&packet.data);
 
-        let mut buf = vec![];
-        buf.write_varint(len as i32)?;
-        buf.write_varint(packet.id)?;
-        std::io::Write::write_all(&mut buf, &packet.data)?;
+        let threshold = if let Some(t) = self.compression_threshold {
+            t
+        } else {
+            return Ok(body);
+        };
 
-        writer.try_write(&buf)?;
+        let mut frame = vec![];
+        if body.len() >= threshold as usize {
+            frame.write_varint(body.len() as i32)?;
 
-        Ok(())
+            let mut z = ZlibEncoder::new(&mut frame, Compression::default());
+            z.write_all(&body)?;
+            z.finish()?;
+        } else {
+            frame.write_varint(0)?;
+            frame.extend_from_slice(&body);
+        }
+
+        Ok(frame)
     }
 }
@@ -1,44 +1,314 @@
+use std::{
+    fmt,
+    io::{self, Read},
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::ids::IdMap;
 use crate::net::wrapper::AbstractPacket;
 use crate::net::PacketDirection;
+use crate::varint::VarInt;
 use num_traits::ToPrimitive;
-use tokio::{net::TcpStream, sync::mpsc, task::JoinHandle};
+use tokio::{
+    net::TcpStream,
+    sync::mpsc::{self, error::TryRecvError},
+    task::JoinHandle,
+    time::Instant,
+};
 
 use super::{
+    auth::{Authenticator, OfflineAuthenticator},
+    capture::{CaptureReader, CaptureWriter},
+    chat::ChatComponent,
     codec::{MinecraftCodec, RawPacket},
+    encryption::{EncryptedReader, EncryptedWriter, EncryptionResponse},
     ConnectionState, ProtocolVersion,
 };
 
+/// The connection is considered dead if the server hasn't sent a keep-alive for this long
+const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub enum DisconnectReason {
+    /// Kicked by the server while playing
+    Kicked(Box<ChatComponent>),
+    /// The server refused to let us log in
+    LoginRejected(Box<ChatComponent>),
+    SocketClosed,
+    /// The server sent something we couldn't make sense of
+    ProtocolError(String),
+    /// The server stopped sending keep-alives
+    TimedOut,
+}
+
+impl fmt::Display for DisconnectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisconnectReason::Kicked(reason) => write!(f, "Kicked: {}", reason),
+            DisconnectReason::LoginRejected(reason) => write!(f, "Login rejected: {}", reason),
+            DisconnectReason::SocketClosed => write!(f, "Connection closed"),
+            DisconnectReason::ProtocolError(e) => write!(f, "Protocol error: {}", e),
+            DisconnectReason::TimedOut => write!(
+                f,
+                "Timed out, no keep-alive received for {} seconds",
+                KEEP_ALIVE_TIMEOUT.as_secs()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DisconnectReason {}
+
+impl DisconnectReason {
+    /// Reason for a Disconnect packet received in `state`
+    fn from_disconnect_packet(reason: &str, state: ConnectionState) -> Self {
+        let reason = Box::new(ChatComponent::from_json(reason));
+        if state == ConnectionState::Play {
+            DisconnectReason::Kicked(reason)
+        } else {
+            DisconnectReason::LoginRejected(reason)
+        }
+    }
+
+    /// Errors that mean the socket is gone, anything else is the server's fault
+    fn from_read_error(e: anyhow::Error) -> Self {
+        match e.downcast_ref::<io::Error>().map(|e| e.kind()) {
+            Some(
+                io::ErrorKind::UnexpectedEof
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::BrokenPipe,
+            ) => DisconnectReason::SocketClosed,
+            _ => DisconnectReason::ProtocolError(e.to_string()),
+        }
+    }
+}
+
+/// Messages handled by the sending thread
+#[derive(Debug)]
+enum Outgoing {
+    /// A packet encoded in the given state
+    Packet(RawPacket, ConnectionState),
+    /// Everything written after this is encrypted with the given shared secret
+    EnableEncryption([u8; 16]),
+}
+
+/// State shared between the connection and its network tasks
+struct SharedState {
+    state: Mutex<ConnectionState>,
+    /// Negative if compression is disabled
+    compression_threshold: AtomicI32,
+    capture: Mutex<Option<CaptureWriter>>,
+}
+
+impl SharedState {
+    fn state(&self) -> ConnectionState {
+        *self.state.lock().unwrap()
+    }
+
+    fn set_state(&self, state: ConnectionState) {
+        debug!("State switching to {:?}", state);
+        *self.state.lock().unwrap() = state;
+    }
+
+    fn record(&self, state: ConnectionState, direction: PacketDirection, rp: &RawPacket) {
+        let mut capture = self.capture.lock().unwrap();
+        if let Some(writer) = capture.as_mut() {
+            if let Err(e) = writer.record(state, direction, rp) {
+                error!("Failed to record packet, stopping capture: {e}");
+                *capture = None;
+            }
+        }
+    }
+}
+
 pub struct ClientConnection {
     pub protocol: ProtocolVersion,
-    pub state: ConnectionState,
+    /// Block and item ids used by the protocol
+    pub ids: Arc<IdMap>,
 
-    packet_tx: mpsc::Sender<RawPacket>,
-    packet_rx: mpsc::Receiver<RawPacket>,
+    shared: Arc<SharedState>,
+    packet_tx: mpsc::Sender<Outgoing>,
+    packet_rx: mpsc::Receiver<Result<AbstractPacket, DisconnectReason>>,
+    /// Set once the receiving thread has stopped
+    disconnect_reason: Option<DisconnectReason>,
     thread_send: JoinHandle<()>,
     thread_recv: JoinHandle<()>,
 }
 
 impl ClientConnection {
+    /// Creates an offline-mode connection
     pub fn from_stream(tcp: TcpStream, protocol: ProtocolVersion) -> Self {
-        let (mut read_half, mut write_half) = tcp.into_split();
+        Self::from_stream_with_auth(tcp, protocol, Arc::new(OfflineAuthenticator))
+    }
+
+    /// Creates a connection that uses `authenticator` when the server requests encryption
+    pub fn from_stream_with_auth(
+        tcp: TcpStream,
+        protocol: ProtocolVersion,
+        authenticator: Arc<dyn Authenticator>,
+    ) -> Self {
+        let ids = Self::load_ids(protocol);
+        let (read_half, write_half) = tcp.into_split();
+        let mut read_half = EncryptedReader::new(read_half);
+        let mut write_half = EncryptedWriter::new(write_half);
+
+        let shared = Arc::new(SharedState {
+            state: Mutex::new(ConnectionState::Handshaking),
+            compression_threshold: AtomicI32::new(-1),
+            capture: Mutex::new(None),
+        });
 
-        let (write_tx, mut write_rx) = tokio::sync::mpsc::channel::<RawPacket>(128);
+        let send_shared = shared.clone();
+        let (write_tx, mut write_rx) = tokio::sync::mpsc::channel::<Outgoing>(128);
         let thread_send = tokio::spawn(async move {
+            let mut codec = MinecraftCodec::default();
             loop {
-                if let Some(rp) = write_rx.recv().await {
-                    MinecraftCodec::write(&mut write_half, &rp).await.unwrap();
-                } else {
+                match write_rx.recv().await {
+                    Some(Outgoing::Packet(rp, state)) => {
+                        codec.set_compression(
+                            send_shared.compression_threshold.load(Ordering::Acquire),
+                        );
+                        if let Err(e) = codec.write(&mut write_half, &rp).await {
+                            // The receiving thread will find out why
+                            debug!("Failed to send packet, stopping send thread: {e}");
+                            break;
+                        }
+
+                        send_shared.record(state, PacketDirection::Server, &rp);
+                    }
+                    Some(Outgoing::EnableEncryption(shared_secret)) => {
+                        debug!("Enabling encryption for outgoing packets");
+                        write_half.enable_encryption(&shared_secret);
+                    }
                     // Channel is closed, exit thread
-                    break;
+                    None => break,
                 }
             }
         });
 
-        let (read_tx, read_rx) = tokio::sync::mpsc::channel::<RawPacket>(512);
+        // Packets are decoded on the receiving thread, as some of them (eg. SetCompression) change how the packets following them are framed
+        let recv_shared = shared.clone();
+        let recv_write_tx = write_tx.clone();
+        let recv_ids = ids.clone();
+        let (read_tx, read_rx) =
+            tokio::sync::mpsc::channel::<Result<AbstractPacket, DisconnectReason>>(512);
         let thread_recv = tokio::spawn(async move {
+            let mut codec = MinecraftCodec::default();
+            let mut last_keep_alive = Instant::now();
             loop {
-                if let Ok(rp) = MinecraftCodec::read(&mut read_half).await {
-                    read_tx.send(rp).await.ok();
+                // Keep-alives are only sent in the play state, but logging in shouldn't take this long either
+                let deadline = last_keep_alive + KEEP_ALIVE_TIMEOUT;
+                let rp = match tokio::time::timeout_at(deadline, codec.read(&mut read_half)).await {
+                    Ok(Ok(rp)) => rp,
+                    Ok(Err(e)) => {
+                        read_tx
+                            .send(Err(DisconnectReason::from_read_error(e)))
+                            .await
+                            .ok();
+                        break;
+                    }
+                    Err(_) => {
+                        read_tx.send(Err(DisconnectReason::TimedOut)).await.ok();
+                        break;
+                    }
+                };
+
+                let state = recv_shared.state();
+                recv_shared.record(state, PacketDirection::Client, &rp);
+
+                let packet = match Self::translate_packet(protocol, &recv_ids, &rp, state) {
+                    Ok(Some(ap)) => ap,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        read_tx.send(Err(e)).await.ok();
+                        break;
+                    }
+                };
+
+                match &packet {
+                    AbstractPacket::SetCompression { threshold } => {
+                        debug!("Compression threshold set to {}", threshold.0);
+                        codec.set_compression(threshold.0);
+                        recv_shared
+                            .compression_threshold
+                            .store(threshold.0, Ordering::Release);
+                    }
+                    AbstractPacket::LoginSuccess { .. } => {
+                        recv_shared.set_state(ConnectionState::Play);
+                    }
+                    // Answered here so the reply doesn't depend on how often the connection is read from
+                    AbstractPacket::KeepAlive { keep_alive_id } => {
+                        last_keep_alive = Instant::now();
+                        if let Err(e) =
+                            Self::answer_keep_alive(protocol, &recv_write_tx, *keep_alive_id).await
+                        {
+                            error!("Failed to answer keep-alive: {e}");
+                        }
+
+                        continue;
+                    }
+                    // We don't know any login plugin channels, so every request is declined
+                    AbstractPacket::LoginPluginRequest {
+                        message_id,
+                        channel,
+                        ..
+                    } => {
+                        debug!("Declining login plugin request on channel {channel}");
+                        if let Err(e) =
+                            Self::answer_login_plugin_request(protocol, &recv_write_tx, *message_id)
+                                .await
+                        {
+                            error!("Failed to answer login plugin request: {e}");
+                        }
+
+                        continue;
+                    }
+                    AbstractPacket::EncryptionBeginClientbound {
+                        server_id,
+                        public_key,
+                        verify_token,
+                    } => {
+                        // The response has to be sent before anything else is read, as everything after it is encrypted
+                        match Self::answer_encryption_request(
+                            protocol,
+                            authenticator.clone(),
+                            &recv_write_tx,
+                            server_id,
+                            public_key,
+                            verify_token,
+                        )
+                        .await
+                        {
+                            Ok(shared_secret) => {
+                                debug!("Enabling encryption for incoming packets");
+                                read_half.enable_encryption(&shared_secret);
+                            }
+                            Err(e) => {
+                                let e = DisconnectReason::ProtocolError(format!(
+                                    "Failed to set up encryption: {}",
+                                    e
+                                ));
+                                read_tx.send(Err(e)).await.ok();
+                                break;
+                            }
+                        }
+                    }
+                    AbstractPacket::Disconnect { reason } => {
+                        let reason = DisconnectReason::from_disconnect_packet(reason, state);
+                        read_tx.send(Err(reason)).await.ok();
+                        break;
+                    }
+                    _ => {}
+                }
+
+                if read_tx.send(Ok(packet)).await.is_err() {
+                    // The connection has been dropped
+                    break;
                 }
             }
         });
@@ -46,95 +316,346 @@ impl ClientConnection {
         Self {
             packet_tx: write_tx,
             packet_rx: read_rx,
+            disconnect_reason: None,
             thread_send,
             thread_recv,
 
             protocol,
-            state: ConnectionState::Handshaking,
+            ids,
+            shared,
         }
     }
 
-    fn check_for_state_change(&mut self, p: &AbstractPacket) {
-        match p {
-            AbstractPacket::SetProtocol { next_state, .. } => {
-                self.state = *next_state;
-                debug!("State switching to {:?}", self.state);
-            }
-            AbstractPacket::LoginSuccess { .. } => {
-                debug!("State switching to Play");
-                self.state = ConnectionState::Play;
-            }
+    /// Creates a connection that plays back the clientbound packets in a capture, packets written to it are discarded
+    ///
+    /// If `realtime` is set, packets are delayed to match their timestamps, otherwise they're replayed as fast as they're read.
+    pub fn from_capture<R: Read + Send + 'static>(
+        mut capture: CaptureReader<R>,
+        realtime: bool,
+    ) -> Self {
+        let protocol = capture.protocol;
+        let ids = Self::load_ids(protocol);
+        let shared = Arc::new(SharedState {
+            state: Mutex::new(ConnectionState::Handshaking),
+            compression_threshold: AtomicI32::new(-1),
+            capture: Mutex::new(None),
+        });
 
-            AbstractPacket::SetCompression { .. } => {
-                panic!("Compression is not supported!");
-            }
+        let (write_tx, mut write_rx) = tokio::sync::mpsc::channel::<Outgoing>(128);
+        let thread_send = tokio::spawn(async move { while write_rx.recv().await.is_some() {} });
+
+        let recv_shared = shared.clone();
+        let recv_ids = ids.clone();
+        let (read_tx, read_rx) =
+            tokio::sync::mpsc::channel::<Result<AbstractPacket, DisconnectReason>>(512);
 
-            AbstractPacket::EncryptionBeginClientbound { .. } => {
-                panic!("Encryption is not supported!");
+        // Reading the capture blocks, so it's done on a blocking thread that stops once the receiving task is gone
+        let (capture_tx, mut capture_rx) = tokio::sync::mpsc::channel(512);
+        tokio::task::spawn_blocking(move || loop {
+            match capture.next_packet() {
+                Ok(Some(cp)) if cp.direction != PacketDirection::Client => continue,
+                Ok(Some(cp)) => {
+                    if capture_tx.blocking_send(Ok(cp)).is_err() {
+                        break;
+                    }
+                }
+                // Dropping the sender tells the receiving task that the capture ended
+                Ok(None) => break,
+                Err(e) => {
+                    capture_tx.blocking_send(Err(e)).ok();
+                    break;
+                }
             }
-            _ => {}
-        }
-    }
+        });
 
-    // TODO: Error handling
-    pub fn read(&mut self) -> Option<AbstractPacket> {
-        let data = self.packet_rx.try_recv().ok()?;
-        match super::versions::decode_packet(
-            self.protocol,
-            &data,
-            self.state,
-            super::PacketDirection::Client,
-        ) {
-            Ok(p) => match AbstractPacket::from_packet(p) {
-                Some(ap) => {
-                    self.check_for_state_change(&ap);
-                    Some(ap)
+        let thread_recv = tokio::spawn(async move {
+            let start = Instant::now();
+            loop {
+                let cp = match capture_rx.recv().await {
+                    Some(Ok(cp)) => cp,
+                    // The server closing the connection is the most likely reason for a capture to end
+                    None => {
+                        read_tx.send(Err(DisconnectReason::SocketClosed)).await.ok();
+                        break;
+                    }
+                    Some(Err(e)) => {
+                        let e = DisconnectReason::ProtocolError(format!(
+                            "Failed to read capture: {}",
+                            e
+                        ));
+                        read_tx.send(Err(e)).await.ok();
+                        break;
+                    }
+                };
+
+                if realtime {
+                    tokio::time::sleep_until(start + cp.timestamp).await;
                 }
-                None => {
-                    if self.state != ConnectionState::Play {
-                        panic!("Non-play packet could not be translated to an AbstractPacket");
-                    } else {
-                        None
+
+                if recv_shared.state() != cp.state {
+                    recv_shared.set_state(cp.state);
+                }
+
+                let packet = match Self::translate_packet(protocol, &recv_ids, &cp.packet, cp.state)
+                {
+                    Ok(Some(ap)) => ap,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        read_tx.send(Err(e)).await.ok();
+                        break;
                     }
+                };
+
+                if let AbstractPacket::Disconnect { reason } = &packet {
+                    let reason = DisconnectReason::from_disconnect_packet(reason, cp.state);
+                    read_tx.send(Err(reason)).await.ok();
+                    break;
                 }
-            },
-            Err(e) => {
-                if self.state != ConnectionState::Play {
-                    panic!(
-                        "An error occurred while decoding non-play packet 0x{:x}: {}",
-                        data.id, e
-                    );
-                } else {
-                    error!("Error decoding packet 0x{:x}: {}", data.id, e);
-                    None
+
+                if read_tx.send(Ok(packet)).await.is_err() {
+                    break;
                 }
             }
+        });
+
+        Self {
+            packet_tx: write_tx,
+            packet_rx: read_rx,
+            disconnect_reason: None,
+            thread_send,
+            thread_recv,
+
+            protocol,
+            ids,
+            shared,
         }
     }
 
-    pub fn write(&mut self, ap: AbstractPacket) -> anyhow::Result<()> {
-        // FIXME: we're cloning the packet because the state change check happens after decoding, otherwise the encoder will fail because the state changed before encoding
-        match ap.clone().to_packet(self.protocol.to_i32().unwrap()) {
-            Ok(p) => {
-                let rp = super::versions::encode_packet(
-                    self.protocol,
-                    &p,
-                    self.state,
-                    PacketDirection::Server,
-                )?;
-                self.check_for_state_change(&ap);
+    /// Records every packet sent and received from now on
+    pub fn start_capture(&self, capture: CaptureWriter) {
+        *self.shared.capture.lock().unwrap() = Some(capture);
+    }
 
-                self.packet_tx.try_send(rp)?;
+    pub fn stop_capture(&self) {
+        *self.shared.capture.lock().unwrap() = None;
+    }
+
+    /// The built-in ids for the protocol
+    fn load_ids(protocol: ProtocolVersion) -> Arc<IdMap> {
+        Arc::new(IdMap::for_protocol(protocol).expect("Built-in id data is invalid"))
+    }
+
+    /// Decodes a clientbound packet, returns `None` if it should be skipped
+    fn translate_packet(
+        protocol: ProtocolVersion,
+        ids: &IdMap,
+        rp: &RawPacket,
+        state: ConnectionState,
+    ) -> Result<Option<AbstractPacket>, DisconnectReason> {
+        // Play packets can be skipped, anything else leaves us in an unknown state
+        match super::versions::decode_packet(protocol, rp, state, PacketDirection::Client) {
+            Ok(p) => match AbstractPacket::from_packet(p, ids) {
+                Some(ap) => Ok(Some(ap)),
+                None if state == ConnectionState::Play => Ok(None),
+                None => Err(DisconnectReason::ProtocolError(format!(
+                    "Non-play packet 0x{:x} could not be translated to an AbstractPacket",
+                    rp.id
+                ))),
+            },
+            Err(e) if state == ConnectionState::Play => {
+                error!("Error decoding packet 0x{:x}: {}", rp.id, e);
+                Ok(None)
             }
-            Err(e) => {
-                if self.state != ConnectionState::Play {
-                    panic!("An error occurred while encoding a non-play packet: {e}")
-                } else {
-                    return Err(e);
-                }
+            Err(e) => Err(DisconnectReason::ProtocolError(format!(
+                "An error occurred while decoding non-play packet 0x{:x}: {}",
+                rp.id, e
+            ))),
+        }
+    }
+
+    async fn answer_keep_alive(
+        protocol: ProtocolVersion,
+        write_tx: &mpsc::Sender<Outgoing>,
+        keep_alive_id: i64,
+    ) -> anyhow::Result<()> {
+        let p =
+            AbstractPacket::KeepAlive { keep_alive_id }.to_packet(protocol.to_i32().unwrap())?;
+        let rp = super::versions::encode_packet(
+            protocol,
+            &p,
+            ConnectionState::Play,
+            PacketDirection::Server,
+        )?;
+
+        write_tx
+            .send(Outgoing::Packet(rp, ConnectionState::Play))
+            .await?;
+        Ok(())
+    }
+
+    async fn answer_login_plugin_request(
+        protocol: ProtocolVersion,
+        write_tx: &mpsc::Sender<Outgoing>,
+        message_id: i32,
+    ) -> anyhow::Result<()> {
+        let p = AbstractPacket::LoginPluginResponse {
+            message_id,
+            data: None,
+        }
+        .to_packet(protocol.to_i32().unwrap())?;
+        let rp = super::versions::encode_packet(
+            protocol,
+            &p,
+            ConnectionState::Login,
+            PacketDirection::Server,
+        )?;
+
+        write_tx
+            .send(Outgoing::Packet(rp, ConnectionState::Login))
+            .await?;
+        Ok(())
+    }
+
+    /// Authenticates with the session server and sends the encryption response, returns the shared secret
+    async fn answer_encryption_request(
+        protocol: ProtocolVersion,
+        authenticator: Arc<dyn Authenticator>,
+        write_tx: &mpsc::Sender<Outgoing>,
+        server_id: &str,
+        public_key: &[u8],
+        verify_token: &[u8],
+    ) -> anyhow::Result<[u8; 16]> {
+        let response = EncryptionResponse::new(server_id, public_key, verify_token)?;
+
+        let server_hash = response.server_hash.clone();
+        tokio::task::spawn_blocking(move || authenticator.join(&server_hash)).await??;
+
+        let p = AbstractPacket::EncryptionBeginServerbound {
+            shared_secret: response.encrypted_secret,
+            verify_token: response.encrypted_token,
+        }
+        .to_packet(protocol.to_i32().unwrap())?;
+        let rp = super::versions::encode_packet(
+            protocol,
+            &p,
+            ConnectionState::Login,
+            PacketDirection::Server,
+        )?;
+
+        write_tx
+            .send(Outgoing::Packet(rp, ConnectionState::Login))
+            .await?;
+        write_tx
+            .send(Outgoing::EnableEncryption(response.shared_secret))
+            .await?;
+
+        Ok(response.shared_secret)
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.shared.state()
+    }
+
+    /// Sends the handshake and login start packets, then waits until the server lets us into the play state
+    pub async fn login(
+        &mut self,
+        server_host: String,
+        server_port: u16,
+        username: String,
+    ) -> anyhow::Result<()> {
+        self.write(AbstractPacket::SetProtocol {
+            protocol_version: VarInt(self.protocol.to_i32().unwrap()),
+            server_host,
+            server_port,
+            next_state: ConnectionState::Login,
+        })?;
+
+        self.write(AbstractPacket::LoginStart { username })?;
+
+        // The receiving thread switches to the play state before passing on LoginSuccess
+        let deadline = Instant::now() + KEEP_ALIVE_TIMEOUT;
+        while self.state() != ConnectionState::Play {
+            tokio::time::timeout_at(deadline, self.recv())
+                .await
+                .map_err(|_| {
+                    anyhow::anyhow!(
+                        "Timed out, not logged in after {} seconds",
+                        KEEP_ALIVE_TIMEOUT.as_secs()
+                    )
+                })??;
+        }
+
+        Ok(())
+    }
+
+    fn check_for_state_change(&mut self, p: &AbstractPacket) {
+        if let AbstractPacket::SetProtocol { next_state, .. } = p {
+            self.shared.set_state(*next_state);
+        }
+    }
+
+    /// Returns the next received packet, if any, or the reason the connection was closed
+    pub fn read(&mut self) -> Result<Option<AbstractPacket>, DisconnectReason> {
+        if let Some(reason) = &self.disconnect_reason {
+            return Err(reason.clone());
+        }
+
+        match self.packet_rx.try_recv() {
+            Ok(received) => self.handle_received(Some(received)).map(Some),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => self.handle_received(None).map(Some),
+        }
+    }
+
+    /// Waits for the next received packet, or the reason the connection was closed
+    pub async fn recv(&mut self) -> Result<AbstractPacket, DisconnectReason> {
+        if let Some(reason) = &self.disconnect_reason {
+            return Err(reason.clone());
+        }
+
+        let received = self.packet_rx.recv().await;
+        self.handle_received(received)
+    }
+
+    fn handle_received(
+        &mut self,
+        received: Option<Result<AbstractPacket, DisconnectReason>>,
+    ) -> Result<AbstractPacket, DisconnectReason> {
+        match received {
+            Some(Ok(ap)) => {
+                self.check_for_state_change(&ap);
+                Ok(ap)
+            }
+            Some(Err(reason)) => {
+                debug!("Disconnected: {}", reason);
+                self.disconnect_reason = Some(reason.clone());
+                Err(reason)
+            }
+            // Shouldn't happen, the receiving thread always sends a reason before stopping
+            None => {
+                self.disconnect_reason = Some(DisconnectReason::SocketClosed);
+                Err(DisconnectReason::SocketClosed)
             }
         }
+    }
+
+    pub fn write(&mut self, ap: AbstractPacket) -> anyhow::Result<()> {
+        // FIXME: we're cloning the packet because the state change check happens after decoding, otherwise the encoder will fail because the state changed before encoding
+        let p = ap.clone().to_packet(self.protocol.to_i32().unwrap())?;
+        let state = self.state();
+        let rp = super::versions::encode_packet(self.protocol, &p, state, PacketDirection::Server)?;
+        self.check_for_state_change(&ap);
+
+        self.packet_tx.try_send(Outgoing::Packet(rp, state))?;
 
         Ok(())
     }
 }
+
+impl Drop for ClientConnection {
+    fn drop(&mut self) {
+        // The receiving thread would otherwise keep the socket open until the server closes it
+        self.thread_recv.abort();
+        self.thread_send.abort();
+    }
+}
@@ -0,0 +1,185 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use aes::Aes128;
+use cfb8::cipher::{generic_array::GenericArray, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use num_bigint::BigInt;
+use rand::RngCore;
+use rsa::{pkcs8::DecodePublicKey, PaddingScheme, PublicKey, RsaPublicKey};
+use sha1::{Digest, Sha1};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+type Aes128Cfb8Enc = cfb8::Encryptor<Aes128>;
+type Aes128Cfb8Dec = cfb8::Decryptor<Aes128>;
+
+/// Everything needed to answer an encryption request
+pub struct EncryptionResponse {
+    pub shared_secret: [u8; 16],
+    /// Shared secret encrypted with the server's public key
+    pub encrypted_secret: Vec<u8>,
+    /// Verify token encrypted with the server's public key
+    pub encrypted_token: Vec<u8>,
+    /// Hash used for the session server join request
+    pub server_hash: String,
+}
+
+impl EncryptionResponse {
+    /// Generates a shared secret and encrypts it using the DER encoded public key sent by the server
+    pub fn new(server_id: &str, public_key: &[u8], verify_token: &[u8]) -> anyhow::Result<Self> {
+        let key = RsaPublicKey::from_public_key_der(public_key)?;
+
+        let mut rng = rand::thread_rng();
+        let mut shared_secret = [0u8; 16];
+        rng.fill_bytes(&mut shared_secret);
+
+        Ok(Self {
+            encrypted_secret: key.encrypt(
+                &mut rng,
+                PaddingScheme::new_pkcs1v15_encrypt(),
+                &shared_secret,
+            )?,
+            encrypted_token: key.encrypt(
+                &mut rng,
+                PaddingScheme::new_pkcs1v15_encrypt(),
+                verify_token,
+            )?,
+            server_hash: server_hash(server_id, &shared_secret, public_key),
+            shared_secret,
+        })
+    }
+}
+
+/// Minecraft's non-standard SHA-1 hex digest (two's complement, no leading zeroes)
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(server_id.as_bytes());
+    hasher.update(shared_secret);
+    hasher.update(public_key);
+
+    BigInt::from_signed_bytes_be(&hasher.finalize()).to_str_radix(16)
+}
+
+/// Reader that transparently decrypts everything after encryption has been enabled
+pub struct EncryptedReader<R> {
+    inner: R,
+    cipher: Option<Aes128Cfb8Dec>,
+}
+
+impl<R> EncryptedReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            cipher: None,
+        }
+    }
+
+    pub fn enable_encryption(&mut self, shared_secret: &[u8; 16]) {
+        self.cipher = Some(Aes128Cfb8Dec::new(
+            shared_secret.into(),
+            shared_secret.into(),
+        ));
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for EncryptedReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let start = buf.filled().len();
+        ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+
+        if let Some(cipher) = &mut self.cipher {
+            // CFB8 works on single byte blocks, so we can decrypt whatever we got
+            for b in &mut buf.filled_mut()[start..] {
+                cipher.decrypt_block_mut(GenericArray::from_mut_slice(std::slice::from_mut(b)));
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Writer that transparently encrypts everything after encryption has been enabled
+pub struct EncryptedWriter<W> {
+    inner: W,
+    cipher: Option<Aes128Cfb8Enc>,
+    /// Encrypted data that hasn't been written to the inner writer yet
+    pending: Vec<u8>,
+}
+
+impl<W> EncryptedWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            cipher: None,
+            pending: vec![],
+        }
+    }
+
+    pub fn enable_encryption(&mut self, shared_secret: &[u8; 16]) {
+        self.cipher = Some(Aes128Cfb8Enc::new(
+            shared_secret.into(),
+            shared_secret.into(),
+        ));
+    }
+}
+
+impl<W: AsyncWrite + Unpin> EncryptedWriter<W> {
+    fn poll_write_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.pending.is_empty() {
+            let n = ready!(Pin::new(&mut self.inner).poll_write(cx, &self.pending))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+
+            self.pending.drain(..n);
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for EncryptedWriter<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        if this.cipher.is_none() {
+            return Pin::new(&mut this.inner).poll_write(cx, buf);
+        }
+
+        // The cipher state advances with every byte, so data can only be encrypted once it's been accepted
+        ready!(this.poll_write_pending(cx))?;
+
+        let cipher = this.cipher.as_mut().unwrap();
+        let start = this.pending.len();
+        this.pending.extend_from_slice(buf);
+        for b in &mut this.pending[start..] {
+            cipher.encrypt_block_mut(GenericArray::from_mut_slice(std::slice::from_mut(b)));
+        }
+
+        // Any leftovers are written on the next write or flush
+        if this.poll_write_pending(cx)?.is_pending() {
+            trace!("{} encrypted bytes pending", this.pending.len());
+        }
+
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_write_pending(cx))?;
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_write_pending(cx))?;
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
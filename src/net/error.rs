@@ -0,0 +1,183 @@
+use std::{fmt, io};
+
+use super::{ConnectionState, PacketDirection};
+
+/// Errors from decoding, encoding and translating packets
+#[derive(Debug)]
+pub enum Error {
+    /// There are no packet id tables for this protocol version
+    UnsupportedProtocol { protocol: i32 },
+    /// The id isn't mapped to a packet in this state and direction
+    UnknownPacketId {
+        id: i32,
+        state: ConnectionState,
+        direction: PacketDirection,
+        protocol: i32,
+    },
+    /// The payload ended before every field was read
+    Truncated {
+        id: i32,
+        state: ConnectionState,
+        direction: PacketDirection,
+        protocol: i32,
+    },
+    /// A field in the payload couldn't be read, eg. an invalid string or NBT tag
+    Malformed {
+        id: i32,
+        state: ConnectionState,
+        direction: PacketDirection,
+        protocol: i32,
+        source: anyhow::Error,
+    },
+    /// The packet doesn't have an id in this state and direction
+    UnmappedPacket {
+        packet: &'static str,
+        state: ConnectionState,
+        direction: PacketDirection,
+        protocol: i32,
+    },
+    /// A field of the packet couldn't be written
+    Encode {
+        packet: &'static str,
+        state: ConnectionState,
+        direction: PacketDirection,
+        protocol: i32,
+        source: anyhow::Error,
+    },
+    /// The abstract packet has no version-specific packet for this protocol
+    NoMapping { packet: &'static str, protocol: i32 },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Sorts a `Serializable` read error into `Truncated` or `Malformed`
+    pub(crate) fn decode(
+        source: anyhow::Error,
+        id: i32,
+        state: ConnectionState,
+        direction: PacketDirection,
+        protocol: i32,
+    ) -> Self {
+        let truncated = source
+            .downcast_ref::<io::Error>()
+            .is_some_and(|e| e.kind() == io::ErrorKind::UnexpectedEof);
+
+        if truncated {
+            Error::Truncated {
+                id,
+                state,
+                direction,
+                protocol,
+            }
+        } else {
+            Error::Malformed {
+                id,
+                state,
+                direction,
+                protocol,
+                source,
+            }
+        }
+    }
+
+    /// Id of the packet that failed to decode, if it got that far
+    pub fn packet_id(&self) -> Option<i32> {
+        match self {
+            Error::UnknownPacketId { id, .. }
+            | Error::Truncated { id, .. }
+            | Error::Malformed { id, .. } => Some(*id),
+            _ => None,
+        }
+    }
+
+    pub fn protocol(&self) -> i32 {
+        match self {
+            Error::UnsupportedProtocol { protocol }
+            | Error::UnknownPacketId { protocol, .. }
+            | Error::Truncated { protocol, .. }
+            | Error::Malformed { protocol, .. }
+            | Error::UnmappedPacket { protocol, .. }
+            | Error::Encode { protocol, .. }
+            | Error::NoMapping { protocol, .. } => *protocol,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnsupportedProtocol { protocol } => {
+                write!(f, "No packet id mappings for protocol {}", protocol)
+            }
+            Error::UnknownPacketId {
+                id,
+                state,
+                direction,
+                protocol,
+            } => write!(
+                f,
+                "No mapping found for {:?}bound packet 0x{:x} in state {:?} (protocol {})",
+                direction, id, state, protocol
+            ),
+            Error::Truncated {
+                id,
+                state,
+                direction,
+                protocol,
+            } => write!(
+                f,
+                "{:?}bound packet 0x{:x} in state {:?} (protocol {}) is truncated",
+                direction, id, state, protocol
+            ),
+            Error::Malformed {
+                id,
+                state,
+                direction,
+                protocol,
+                source,
+            } => write!(
+                f,
+                "Failed to decode {:?}bound packet 0x{:x} in state {:?} (protocol {}): {}",
+                direction, id, state, protocol, source
+            ),
+            Error::UnmappedPacket {
+                packet,
+                state,
+                direction,
+                protocol,
+            } => write!(
+                f,
+                "No id found for {:?}bound packet {} in state {:?} (protocol {})",
+                direction, packet, state, protocol
+            ),
+            Error::Encode {
+                packet,
+                state,
+                direction,
+                protocol,
+                source,
+            } => write!(
+                f,
+                "Failed to encode {:?}bound packet {} in state {:?} (protocol {}): {}",
+                direction, packet, state, protocol, source
+            ),
+            Error::NoMapping { packet, protocol } => {
+                write!(
+                    f,
+                    "No mapping for protocol {} for packet {}",
+                    protocol, packet
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Malformed { source, .. } | Error::Encode { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
@@ -1,13 +1,21 @@
 use num_derive::{FromPrimitive, ToPrimitive};
 
+pub mod auth;
+pub mod capture;
+pub mod chat;
 pub mod codec;
 pub mod connection;
+pub mod encryption;
+pub mod error;
 pub mod packet_helpers;
 pub mod packets;
+pub mod status;
 pub mod types;
 pub mod versions;
 pub mod wrapper;
 
+pub use error::Error;
+
 #[derive(Debug, Copy, Clone, PartialEq, ToPrimitive, FromPrimitive)]
 pub enum ConnectionState {
     Handshaking = 0,
@@ -16,7 +24,7 @@ pub enum ConnectionState {
     Play = 3,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum PacketDirection {
     Client,
     Server,
@@ -38,4 +46,32 @@ pub enum ProtocolVersion {
     Proto1_12 = PROTO_1_12,
     Proto1_12_1 = PROTO_1_12_1,
     Proto1_12_2 = PROTO_1_12_2,
+    Proto1_13_2 = PROTO_1_13_2,
+    Proto1_16_5 = PROTO_1_16_5,
+    Proto1_19_2 = PROTO_1_19_2,
+}
+
+impl ProtocolVersion {
+    /// Looks up a protocol number, failing if there are no packet mappings for it
+    pub fn from_protocol(protocol: i32) -> anyhow::Result<Self> {
+        Ok(match protocol {
+            PROTO_1_7_6 => Self::Proto1_7_6,
+            PROTO_1_8 => Self::Proto1_8,
+            PROTO_1_9 => Self::Proto1_9,
+            PROTO_1_9_2 => Self::Proto1_9_2,
+            PROTO_1_9_4 => Self::Proto1_9_4,
+            PROTO_1_10 => Self::Proto1_10,
+            PROTO_1_11 => Self::Proto1_11,
+            PROTO_1_12 => Self::Proto1_12,
+            PROTO_1_12_1 => Self::Proto1_12_1,
+            PROTO_1_12_2 => Self::Proto1_12_2,
+            PROTO_1_13_2 => Self::Proto1_13_2,
+            PROTO_1_16_5 => Self::Proto1_16_5,
+            PROTO_1_19_2 => Self::Proto1_19_2,
+            _ => anyhow::bail!(
+                "Protocol version {} is not supported (supported versions are 1.7.6 to 1.12.2, 1.13.2, 1.16.5 and 1.19.2)",
+                protocol
+            ),
+        })
+    }
 }
@@ -19,6 +19,25 @@ pub trait Serializable: Sized {
     }
 }
 
+/// Byte range of the packet data a field was read from
+#[derive(Debug, Clone)]
+pub struct FieldRange {
+    pub name: &'static str,
+    pub range: std::ops::Range<usize>,
+}
+
+/// Implemented by every packet struct, used to find out which bytes each field consumed
+pub trait ReadTraced: Sized {
+    /// Reads the packet like `read_from_versioned`, appending the range of every field that was read to `fields`
+    ///
+    /// Fields that were read before an error occurred are still appended.
+    fn read_traced<R: std::io::Read + std::io::Seek>(
+        r: &mut R,
+        version: i32,
+        fields: &mut Vec<FieldRange>,
+    ) -> anyhow::Result<Self>;
+}
+
 #[macro_export]
 macro_rules! packet_structs {
     ($($state:ident {
@@ -33,7 +52,7 @@ macro_rules! packet_structs {
         })+
     })+) => {
         #[allow(dead_code)]
-        #[derive(Debug, Clone, PartialEq)]
+        #[derive(Debug, Clone, PartialEq, strum::IntoStaticStr)]
         pub enum Packet {
         $($($($name($state::$dir::$name),)*)+)+
         }
@@ -99,6 +118,32 @@ macro_rules! packet_structs {
                                     Ok(())
                                 }
                             }
+
+                            #[allow(unused_mut, unused_variables, unexpected_cfgs)]
+                            impl ReadTraced for $name {
+                                fn read_traced<R: std::io::Read + std::io::Seek>(r: &mut R, version: i32, fields: &mut Vec<FieldRange>) -> anyhow::Result<Self> {
+                                    let _ = (&r, &fields);
+                                    let mut p = $name::default();
+
+                                    $(
+                                        let start = r.stream_position()? as usize;
+                                        $(
+                                            for _ in 0..p.$count_var {
+                                                p.$field.push(Serializable::read_from_versioned(r, version)?);
+                                            }
+                                            fields.push(FieldRange { name: stringify!($field), range: start..r.stream_position()? as usize });
+
+                                            #[cfg(not)]
+                                        )?
+                                        if true $(&& ($cond(&p)))? {
+                                            p.$field = Serializable::read_from_versioned(r, version)?;
+                                            fields.push(FieldRange { name: stringify!($field), range: start..r.stream_position()? as usize });
+                                        }
+                                    )*
+
+                                    Ok(p)
+                                }
+                            }
                         )*
                     }
                 )*
@@ -120,7 +165,7 @@ macro_rules! packet_ids {
         use crate::net::packet_helpers::Serializable;
         use crate::net::{ConnectionState, PacketDirection};
 
-        pub fn decode_packet(p: &crate::net::codec::RawPacket, state: crate::net::ConnectionState, dir: crate::net::PacketDirection) -> anyhow::Result<crate::net::packets::Packet> {
+        pub fn decode_packet(p: &crate::net::codec::RawPacket, state: crate::net::ConnectionState, dir: crate::net::PacketDirection) -> crate::net::error::Result<crate::net::packets::Packet> {
             let mut reader = std::io::Cursor::new(&p.data);
             let r = match state {
                     $( // State
@@ -130,9 +175,12 @@ macro_rules! packet_ids {
                                 PacketDirection::$tdir => {
                                     match p.id {
                                         $(
-                                            $id => crate::net::packets::Packet::$name(Serializable::read_from_versioned(&mut reader, $version)?),
+                                            $id => crate::net::packets::Packet::$name(
+                                                Serializable::read_from_versioned(&mut reader, $version)
+                                                    .map_err(|e| crate::net::Error::decode(e, p.id, state, dir, $version))?
+                                            ),
                                         )*
-                                        _ => anyhow::bail!("No mapping found for {:?}bound packet 0x{:x} in state {:?}", $tdir, p.id, $tstate)
+                                        _ => return Err(crate::net::Error::UnknownPacketId { id: p.id, state, direction: dir, protocol: $version })
                                     }
                                 }
                             )*
@@ -154,7 +202,33 @@ macro_rules! packet_ids {
             Ok(r)
         }
 
-        pub fn encode_packet(p: &crate::net::packets::Packet, state: crate::net::ConnectionState, dir: crate::net::PacketDirection) -> anyhow::Result<crate::net::codec::RawPacket> {
+        /// Decodes a packet like `decode_packet`, appending the byte range of every field that was read to `fields`
+        pub fn decode_packet_traced(p: &crate::net::codec::RawPacket, state: crate::net::ConnectionState, dir: crate::net::PacketDirection, fields: &mut Vec<crate::net::packet_helpers::FieldRange>) -> crate::net::error::Result<crate::net::packets::Packet> {
+            let mut reader = std::io::Cursor::new(&p.data);
+            Ok(match state {
+                    $( // State
+                    ConnectionState::$tstate => {
+                        match dir {
+                            $( // Direction
+                                PacketDirection::$tdir => {
+                                    match p.id {
+                                        $(
+                                            $id => crate::net::packets::Packet::$name(
+                                                crate::net::packet_helpers::ReadTraced::read_traced(&mut reader, $version, fields)
+                                                    .map_err(|e| crate::net::Error::decode(e, p.id, state, dir, $version))?
+                                            ),
+                                        )*
+                                        _ => return Err(crate::net::Error::UnknownPacketId { id: p.id, state, direction: dir, protocol: $version })
+                                    }
+                                }
+                            )*
+                        }
+                    }
+                )*
+                })
+        }
+
+        pub fn encode_packet(p: &crate::net::packets::Packet, state: crate::net::ConnectionState, dir: crate::net::PacketDirection) -> crate::net::error::Result<crate::net::codec::RawPacket> {
             let mut rp = crate::net::codec::RawPacket {
                 id: 0,
                 data: vec![],
@@ -163,6 +237,7 @@ macro_rules! packet_ids {
             let mut writer = std::io::Cursor::new(&mut rp.data);
             // println!("Writing {:?} {:?} {:?}", dir, state, p);
 
+            let packet: &'static str = p.into();
             match state {
                     $( // State
                     ConnectionState::$tstate => {
@@ -173,10 +248,12 @@ macro_rules! packet_ids {
                                         $(
                                             crate::net::packets::Packet::$name(p) => {
                                                 rp.id = $id;
-                                                p.write_to_versioned(&mut writer, $version)?;
+                                                p.write_to_versioned(&mut writer, $version).map_err(|source| crate::net::Error::Encode {
+                                                    packet, state, direction: dir, protocol: $version, source
+                                                })?;
                                             },
                                         )*
-                                        _ => anyhow::bail!("No mapping found for {:?}bound packet {:?} in state {:?}", $tdir, p, $tstate)
+                                        _ => return Err(crate::net::Error::UnmappedPacket { packet, state, direction: dir, protocol: $version })
                                     }
                                 }
                             )*
@@ -271,6 +348,19 @@ impl<T: Serializable> Serializable for Option<T> {
 
         Ok(())
     }
+
+    // Passed on for types like Position that only implement the versioned variants
+    fn read_from_versioned<R: std::io::Read>(r: &mut R, version: i32) -> anyhow::Result<Self> {
+        Ok(Some(Serializable::read_from_versioned(r, version)?))
+    }
+
+    fn write_to_versioned<W: std::io::Write>(&self, w: &mut W, version: i32) -> anyhow::Result<()> {
+        if let Some(s) = self {
+            s.write_to_versioned(w, version)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl Serializable for Uuid {
@@ -35,10 +35,24 @@ packet_structs! {
                 public_key: PrefixedVec<u8, VarInt>,
                 verify_token: PrefixedVec<u8, VarInt>,
             }
+            packet LoginPluginRequest_393 {
+                message_id: VarInt,
+                channel: String,
+                data: Vec<u8>,
+            }
             packet Success_5 {
                 uuid: String,
                 username: String,
             }
+            packet Success_735 {
+                uuid: uuid::Uuid,
+                username: String,
+            }
+            packet Success_759 {
+                uuid: uuid::Uuid,
+                username: String,
+                properties: PrefixedVec<GameProfileProperty, VarInt>,
+            }
         }
         serverbound {
             packet EncryptionBeginServerbound_5 {
@@ -49,9 +63,31 @@ packet_structs! {
                 shared_secret: PrefixedVec<u8, VarInt>,
                 verify_token: PrefixedVec<u8, VarInt>,
             }
+            // Clients with a chat signing key sign the verify token instead of encrypting it
+            packet EncryptionBeginServerbound_759 {
+                shared_secret: PrefixedVec<u8, VarInt>,
+                has_verify_token: bool,
+                verify_token: Option<PrefixedVec<u8, VarInt>> > when(|p: &EncryptionBeginServerbound_759| p.has_verify_token),
+                salt: Option<i64> > when(|p: &EncryptionBeginServerbound_759| !p.has_verify_token),
+                message_signature: Option<PrefixedVec<u8, VarInt>> > when(|p: &EncryptionBeginServerbound_759| !p.has_verify_token),
+            }
+            packet LoginPluginResponse_393 {
+                message_id: VarInt,
+                successful: bool,
+                data: Vec<u8>,
+            }
             packet LoginStart_5 {
                 username: String,
             }
+            packet LoginStart_760 {
+                username: String,
+                has_sig_data: bool,
+                timestamp: Option<i64> > when(|p: &LoginStart_760| p.has_sig_data),
+                public_key: Option<PrefixedVec<u8, VarInt>> > when(|p: &LoginStart_760| p.has_sig_data),
+                signature: Option<PrefixedVec<u8, VarInt>> > when(|p: &LoginStart_760| p.has_sig_data),
+                has_player_uuid: bool,
+                player_uuid: Option<uuid::Uuid> > when(|p: &LoginStart_760| p.has_player_uuid),
+            }
         }
     }
     status {
@@ -156,6 +192,11 @@ packet_structs! {
                 message: String,
                 position: i8,
             }
+            packet Chat_735 {
+                message: String,
+                position: i8,
+                sender: uuid::Uuid,
+            }
             packet CloseWindow_5 {
                 window_id: u8,
             }
@@ -240,6 +281,10 @@ packet_structs! {
                 slot: VarInt,
                 item: Slot,
             }
+            packet EntityEquipment_735 {
+                entity_id: VarInt,
+                equipment: EquipmentList,
+            }
             packet EntityHeadRotation_5 {
                 entity_id: i32,
                 head_yaw: i8,
@@ -323,10 +368,13 @@ packet_structs! {
                 pitch: i8,
                 on_ground: bool,
             }
-            // TODO: Unfinished
             packet EntityUpdateAttributes_107 {
                 entity_id: VarInt,
-                // properties: ['array', {'countType': 'i32', 'type': ['container', [{'name': 'key', 'type': 'string'}, {'name': 'value', 'type': 'f64'}, {'name': 'modifiers', 'type': ['array', {'countType': 'varint', 'type': ['container', [{'name': 'uuid', 'type': 'UUID'}, {'name': 'amount', 'type': 'f64'}, {'name': 'operation', 'type': 'i8'}]]}]}]]}],
+                properties: PrefixedVec<EntityProperty_47, i32>,
+            }
+            packet EntityUpdateAttributes_755 {
+                entity_id: VarInt,
+                properties: PrefixedVec<EntityProperty_47, VarInt>,
             }
             packet EntityVelocity_5 {
                 entity_id: i32,
@@ -411,6 +459,44 @@ packet_structs! {
                 level_kind: String,
                 reduced_debug_info: bool,
             }
+            packet Login_751 {
+                entity_id: i32,
+                is_hardcore: bool,
+                game_mode: u8,
+                previous_game_mode: i8,
+                world_names: PrefixedVec<String, VarInt>,
+                dimension_codec: nbt::Blob,
+                dimension: nbt::Blob,
+                world_name: String,
+                hashed_seed: i64,
+                max_players: VarInt,
+                view_distance: VarInt,
+                reduced_debug_info: bool,
+                enable_respawn_screen: bool,
+                is_debug: bool,
+                is_flat: bool,
+            }
+            packet Login_759 {
+                entity_id: i32,
+                is_hardcore: bool,
+                game_mode: u8,
+                previous_game_mode: i8,
+                world_names: PrefixedVec<String, VarInt>,
+                registry_codec: nbt::Blob,
+                dimension_type: String,
+                world_name: String,
+                hashed_seed: i64,
+                max_players: VarInt,
+                view_distance: VarInt,
+                simulation_distance: VarInt,
+                reduced_debug_info: bool,
+                enable_respawn_screen: bool,
+                is_debug: bool,
+                is_flat: bool,
+                has_death_location: bool,
+                death_dimension: Option<String> > when(|p: &Login_759| p.has_death_location),
+                death_location: Option<Position> > when(|p: &Login_759| p.has_death_location),
+            }
             packet MapChunkBulk_5 {
                 column_count: i16,
                 data_length: i32,
@@ -453,6 +539,34 @@ packet_structs! {
                 chunk_data: PrefixedVec<u8, VarInt>,
                 block_entities: PrefixedVec<nbt::Blob, VarInt>,
             }
+            // Same fields as MapChunk_110, but the data uses the flattened section format
+            packet MapChunk_393 {
+                x: i32,
+                z: i32,
+                ground_up: bool,
+                bit_map: VarInt,
+                chunk_data: PrefixedVec<u8, VarInt>,
+                block_entities: PrefixedVec<nbt::Blob, VarInt>,
+            }
+            packet MapChunk_751 {
+                x: i32,
+                z: i32,
+                ground_up: bool,
+                bit_map: VarInt,
+                heightmaps: nbt::Blob,
+                biomes: Option<PrefixedVec<VarInt, VarInt>> > when(|p: &MapChunk_751| p.ground_up),
+                chunk_data: PrefixedVec<u8, VarInt>,
+                block_entities: PrefixedVec<nbt::Blob, VarInt>,
+            }
+            // Always a full column covering the whole height of the world, with its light
+            packet MapChunk_757 {
+                x: i32,
+                z: i32,
+                heightmaps: nbt::Blob,
+                chunk_data: PrefixedVec<u8, VarInt>,
+                block_entities: PrefixedVec<ChunkBlockEntity, VarInt>,
+                light: ChunkLight,
+            }
             packet Map_5 {
                 item_damage: VarInt,
                 data: PrefixedVec<u8, i16>,
@@ -492,6 +606,12 @@ packet_structs! {
                 chunk_z: i32,
                 records: PrefixedVec<BlockChangeRecord_47, VarInt>,
             }
+            // The section is packed as x (22 bits), z (22 bits) and y (20 bits), records as `state << 12 | x << 8 | z << 4 | y`
+            packet MultiBlockChange_751 {
+                section: i64,
+                suppress_light_updates: bool,
+                records: PrefixedVec<VarLong, VarInt>,
+            }
             packet NamedEntitySpawn_5 {
                 entity_id: VarInt,
                 player_u_u_i_d: String,
@@ -526,6 +646,15 @@ packet_structs! {
                 pitch: i8,
                 metadata: EntityMeta,
             }
+            packet NamedEntitySpawn_573 {
+                entity_id: VarInt,
+                player_u_u_i_d: uuid::Uuid,
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: i8,
+                pitch: i8,
+            }
             packet NamedSoundEffect_5 {
                 sound_name: String,
                 x: i32,
@@ -552,6 +681,16 @@ packet_structs! {
                 volume: f32,
                 pitch: f32,
             }
+            packet NamedSoundEffect_759 {
+                sound_name: String,
+                sound_category: VarInt,
+                x: i32,
+                y: i32,
+                z: i32,
+                volume: f32,
+                pitch: f32,
+                seed: i64,
+            }
             packet OpenSignEntity_5 {
                 location: PositionIII,
             }
@@ -573,6 +712,27 @@ packet_structs! {
                 slot_count: u8,
                 entity_id: Option<i32> > when(|p: &OpenWindow_47| p.inventory_type == "EntityHorse"),
             }
+            // Signed chat, the message is shown with the chat type's decoration
+            packet PlayerChat_760 {
+                has_previous_signature: bool,
+                previous_signature: Option<PrefixedVec<u8, VarInt>> > when(|p: &PlayerChat_760| p.has_previous_signature),
+                sender: uuid::Uuid,
+                header_signature: PrefixedVec<u8, VarInt>,
+                plain_message: String,
+                has_formatted_message: bool,
+                formatted_message: Option<String> > when(|p: &PlayerChat_760| p.has_formatted_message),
+                timestamp: i64,
+                salt: i64,
+                previous_messages: PrefixedVec<PreviousMessage, VarInt>,
+                has_unsigned_content: bool,
+                unsigned_content: Option<String> > when(|p: &PlayerChat_760| p.has_unsigned_content),
+                filter_type: VarInt,
+                filter_type_bits: Option<PrefixedVec<i64, VarInt>> > when(|p: &PlayerChat_760| p.filter_type.0 == 2),
+                chat_type: VarInt,
+                network_name: String,
+                has_network_target_name: bool,
+                network_target_name: Option<String> > when(|p: &PlayerChat_760| p.has_network_target_name),
+            }
             packet PlayerInfo_5 {
                 player_name: String,
                 online: bool,
@@ -612,6 +772,16 @@ packet_structs! {
                 flags: i8,
                 teleport_id: VarInt,
             }
+            packet Position_755 {
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: f32,
+                pitch: f32,
+                flags: i8,
+                teleport_id: VarInt,
+                dismount_vehicle: bool,
+            }
             packet RelEntityMove_5 {
                 entity_id: i32,
                 d_x: FixedPoint8,
@@ -627,9 +797,9 @@ packet_structs! {
             }
             packet RelEntityMove_107 {
                 entity_id: VarInt,
-                d_x: i16,
-                d_y: i16,
-                d_z: i16,
+                d_x: FixedPoint16,
+                d_y: FixedPoint16,
+                d_z: FixedPoint16,
                 on_ground: bool,
             }
             packet RemoveEntityEffect_5 {
@@ -650,6 +820,29 @@ packet_structs! {
                 gamemode: u8,
                 level_kind: String,
             }
+            packet Respawn_751 {
+                dimension: nbt::Blob,
+                world_name: String,
+                hashed_seed: i64,
+                gamemode: u8,
+                previous_gamemode: i8,
+                is_debug: bool,
+                is_flat: bool,
+                copy_metadata: bool,
+            }
+            packet Respawn_759 {
+                dimension_type: String,
+                world_name: String,
+                hashed_seed: i64,
+                gamemode: u8,
+                previous_gamemode: i8,
+                is_debug: bool,
+                is_flat: bool,
+                copy_metadata: bool,
+                has_death_location: bool,
+                death_dimension: Option<String> > when(|p: &Respawn_759| p.has_death_location),
+                death_location: Option<Position> > when(|p: &Respawn_759| p.has_death_location),
+            }
             packet ScoreboardDisplayObjective_5 {
                 position: i8,
                 name: String,
@@ -794,6 +987,20 @@ packet_structs! {
                 velocity_z: i16,
                 metadata: EntityMeta,
             }
+            packet SpawnEntityLiving_573 {
+                entity_id: VarInt,
+                entity_u_u_i_d: uuid::Uuid,
+                kind: VarInt,
+                x: f64,
+                y: f64,
+                z: f64,
+                yaw: i8,
+                pitch: i8,
+                head_pitch: i8,
+                velocity_x: i16,
+                velocity_y: i16,
+                velocity_z: i16,
+            }
             packet SpawnEntityPainting_5 {
                 entity_id: VarInt,
                 title: String,
@@ -827,7 +1034,6 @@ packet_structs! {
                 y: f64,
                 z: f64,
             }
-            // TODO: Unfinished
             packet SpawnEntity_5 {
                 entity_id: VarInt,
                 kind: i8,
@@ -836,7 +1042,7 @@ packet_structs! {
                 z: FixedPoint32,
                 pitch: i8,
                 yaw: i8,
-                // object_data: ['container', [{'name': 'intField', 'type': 'i32'}, {'name': 'velocityX', 'type': ['switch', {'compareTo': 'intField', 'fields': {'0': 'void'}, 'default': 'i16'}]}, {'name': 'velocityY', 'type': ['switch', {'compareTo': 'intField', 'fields': {'0': 'void'}, 'default': 'i16'}]}, {'name': 'velocityZ', 'type': ['switch', {'compareTo': 'intField', 'fields': {'0': 'void'}, 'default': 'i16'}]}]],
+                object_data: ObjectData,
             }
             packet SpawnEntity_107 {
                 entity_id: VarInt,
@@ -852,15 +1058,53 @@ packet_structs! {
                 velocity_y: i16,
                 velocity_z: i16,
             }
+            packet SpawnEntity_477 {
+                entity_id: VarInt,
+                object_u_u_i_d: uuid::Uuid,
+                kind: VarInt,
+                x: f64,
+                y: f64,
+                z: f64,
+                pitch: i8,
+                yaw: i8,
+                object_data: i32,
+                velocity_x: i16,
+                velocity_y: i16,
+                velocity_z: i16,
+            }
+            // Also used for mobs from 1.19 on
+            packet SpawnEntity_759 {
+                entity_id: VarInt,
+                object_u_u_i_d: uuid::Uuid,
+                kind: VarInt,
+                x: f64,
+                y: f64,
+                z: f64,
+                pitch: i8,
+                yaw: i8,
+                head_yaw: i8,
+                object_data: VarInt,
+                velocity_x: i16,
+                velocity_y: i16,
+                velocity_z: i16,
+            }
             packet SpawnPosition_5 {
                 location: PositionIII,
             }
             packet SpawnPosition_47 {
                 location: Position,
             }
+            packet SpawnPosition_755 {
+                location: Position,
+                angle: f32,
+            }
             packet Statistics_5 {
                 entries: PrefixedVec<StatisticsEntry, VarInt>,
             }
+            packet SystemChat_760 {
+                content: String,
+                overlay: bool,
+            }
             packet TabComplete_5 {
                 matches: PrefixedVec<String, VarInt>,
             }
@@ -879,12 +1123,17 @@ packet_structs! {
             packet TileEntityData_5 {
                 location: PositionISI,
                 action: u8,
-                nbt_data: CompressedGzData<nbt::Blob>,
+                nbt_data: OptionalNbt,
             }
             packet TileEntityData_47 {
                 location: Position,
                 action: u8,
-                nbt_data: nbt::Blob,
+                nbt_data: OptionalNbt,
+            }
+            packet TileEntityData_757 {
+                location: Position,
+                kind: VarInt,
+                nbt_data: OptionalNbt,
             }
             packet Title_47 {
                 action: VarInt,
@@ -925,12 +1174,11 @@ packet_structs! {
             // TODO: Unfinished
             packet UpdateAttributes_5 {
                 entity_id: i32,
-                // properties: ['array', {'countType': 'i32', 'type': ['container', [{'name': 'key', 'type': 'string'}, {'name': 'value', 'type': 'f64'}, {'name': 'modifiers', 'type': ['array', {'countType': 'i16', 'type': ['container', [{'name': 'uuid', 'type': 'UUID'}, {'name': 'amount', 'type': 'f64'}, {'name': 'operation', 'type': 'i8'}]]}]}]]}],
+                properties: PrefixedVec<EntityProperty, i32>,
             }
-            // TODO: Unfinished
             packet UpdateAttributes_47 {
                 entity_id: VarInt,
-                // properties: ['array', {'countType': 'i32', 'type': ['container', [{'name': 'key', 'type': 'string'}, {'name': 'value', 'type': 'f64'}, {'name': 'modifiers', 'type': ['array', {'countType': 'varint', 'type': ['container', [{'name': 'uuid', 'type': 'UUID'}, {'name': 'amount', 'type': 'f64'}, {'name': 'operation', 'type': 'i8'}]]}]}]]}],
+                properties: PrefixedVec<EntityProperty_47, i32>,
             }
             packet UpdateEntityNbt_47 {
                 entity_id: VarInt,
@@ -946,6 +1194,22 @@ packet_structs! {
                 food: VarInt,
                 food_saturation: f32,
             }
+            // The arrays aren't prefixed with their count, so `light` holds the sky light arrays followed by the block light ones
+            packet UpdateLight_735 {
+                chunk_x: VarInt,
+                chunk_z: VarInt,
+                trust_edges: bool,
+                sky_light_mask: VarInt,
+                block_light_mask: VarInt,
+                empty_sky_light_mask: VarInt,
+                empty_block_light_mask: VarInt,
+                light: Vec<PrefixedVec<u8, VarInt>>,
+            }
+            packet UpdateLight_755 {
+                chunk_x: VarInt,
+                chunk_z: VarInt,
+                light: ChunkLight,
+            }
             packet UpdateSign_5 {
                 location: PositionISI,
                 text1: String,
@@ -1086,6 +1350,17 @@ packet_structs! {
                 cursor_y: f32,
                 cursor_z: f32,
             }
+            // Sent unsigned, which servers that don't enforce secure chat accept
+            packet ChatMessage_760 {
+                message: String,
+                timestamp: i64,
+                salt: i64,
+                signature: PrefixedVec<u8, VarInt>,
+                signed_preview: bool,
+                previous_messages: PrefixedVec<PreviousMessage, VarInt>,
+                has_last_received: bool,
+                last_received: Option<PreviousMessage> > when(|p: &ChatMessage_760| p.has_last_received),
+            }
             packet ChatServerbound_5 {
                 message: String,
             }
@@ -0,0 +1,274 @@
+use std::{
+    future::Future,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::ensure;
+use base64::Engine;
+use serde::Deserialize;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use crate::{ids::IdMap, varint::VarInt};
+
+use super::{
+    chat::ChatComponent,
+    codec::MinecraftCodec,
+    versions::{decode_packet, encode_packet},
+    wrapper::AbstractPacket,
+    ConnectionState, PacketDirection, ProtocolVersion,
+};
+
+/// Status packets are the same in every version, so any protocol with packet mappings works
+const STATUS_PROTOCOL: ProtocolVersion = ProtocolVersion::Proto1_8;
+/// How long each query may take, from connecting to the last response
+const STATUS_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct VersionInfo {
+    pub name: String,
+    pub protocol: i32,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct Players {
+    pub max: i32,
+    pub online: i32,
+    /// Not every server sends a sample, and it rarely contains every online player
+    #[serde(default)]
+    pub sample: Vec<PlayerSample>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PlayerSample {
+    pub name: String,
+    pub id: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerStatus {
+    pub version: VersionInfo,
+    pub players: Players,
+    /// Also known as the MOTD
+    pub description: ChatComponent,
+    /// PNG data
+    pub favicon: Option<Vec<u8>>,
+    /// Round trip time of the ping, or of the whole query for legacy servers
+    pub latency: Duration,
+}
+
+#[derive(Deserialize)]
+struct StatusResponse {
+    version: VersionInfo,
+    #[serde(default)]
+    players: Players,
+    #[serde(default)]
+    description: ChatComponent,
+    favicon: Option<String>,
+}
+
+async fn write_packet(
+    stream: &mut TcpStream,
+    codec: &MinecraftCodec,
+    state: ConnectionState,
+    ap: AbstractPacket,
+) -> anyhow::Result<()> {
+    let p = ap.to_packet(STATUS_PROTOCOL as i32)?;
+    let rp = encode_packet(STATUS_PROTOCOL, &p, state, PacketDirection::Server)?;
+    codec.write(stream, &rp).await
+}
+
+async fn read_packet(
+    stream: &mut TcpStream,
+    codec: &MinecraftCodec,
+) -> anyhow::Result<AbstractPacket> {
+    let rp = codec.read(stream).await?;
+    let p = decode_packet(
+        STATUS_PROTOCOL,
+        &rp,
+        ConnectionState::Status,
+        PacketDirection::Client,
+    )?;
+
+    // Status packets don't contain any block or item ids
+    AbstractPacket::from_packet(p, &IdMap::default()).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Status packet 0x{:x} could not be translated to an AbstractPacket",
+            rp.id
+        )
+    })
+}
+
+/// Decodes a `data:image/png;base64,` favicon
+fn decode_favicon(favicon: &str) -> Option<Vec<u8>> {
+    let data = match favicon.strip_prefix("data:image/png;base64,") {
+        Some(d) => d,
+        None => {
+            warn!("Favicon isn't a base64 encoded PNG");
+            return None;
+        }
+    };
+
+    // Some servers wrap the base64 data
+    let data: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+    match base64::engine::general_purpose::STANDARD.decode(data) {
+        Ok(png) => Some(png),
+        Err(e) => {
+            warn!("Failed to decode favicon: {e}");
+            None
+        }
+    }
+}
+
+/// Runs a query, giving up if the server doesn't answer within `STATUS_TIMEOUT`
+async fn with_timeout<T>(query: impl Future<Output = anyhow::Result<T>>) -> anyhow::Result<T> {
+    tokio::time::timeout(STATUS_TIMEOUT, query)
+        .await
+        .unwrap_or_else(|_| anyhow::bail!("Server didn't respond within {:?}", STATUS_TIMEOUT))
+}
+
+/// Queries the server's status, falling back to the legacy server list ping for pre-1.7 servers
+pub async fn query_status(address: &str, port: u16) -> anyhow::Result<ServerStatus> {
+    match with_timeout(query_status_modern(address, port)).await {
+        Ok(s) => Ok(s),
+        Err(e) => {
+            debug!("Status query failed ({e}), trying a legacy server list ping");
+            with_timeout(query_status_legacy(address, port))
+                .await
+                .map_err(|le| anyhow::anyhow!("Status query failed: {e} (legacy ping: {le})"))
+        }
+    }
+}
+
+async fn query_status_modern(address: &str, port: u16) -> anyhow::Result<ServerStatus> {
+    let mut stream = TcpStream::connect(format!("{}:{}", address, port)).await?;
+    let codec = MinecraftCodec::default();
+
+    write_packet(
+        &mut stream,
+        &codec,
+        ConnectionState::Handshaking,
+        AbstractPacket::SetProtocol {
+            // By convention, -1 is sent when the client is determining which version to use
+            protocol_version: VarInt(-1),
+            server_host: address.to_string(),
+            server_port: port,
+            next_state: ConnectionState::Status,
+        },
+    )
+    .await?;
+
+    write_packet(
+        &mut stream,
+        &codec,
+        ConnectionState::Status,
+        AbstractPacket::ServerInfoRequest {},
+    )
+    .await?;
+
+    let response: StatusResponse = match read_packet(&mut stream, &codec).await? {
+        AbstractPacket::ServerInfo { response } => serde_json::from_str(&response)?,
+        p => anyhow::bail!(
+            "Expected a server info response, got {}",
+            <&'static str>::from(p)
+        ),
+    };
+
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64;
+    let ping_start = Instant::now();
+    write_packet(
+        &mut stream,
+        &codec,
+        ConnectionState::Status,
+        AbstractPacket::Ping { time },
+    )
+    .await?;
+
+    let latency = match read_packet(&mut stream, &codec).await? {
+        AbstractPacket::PingResponse { time: t } if t == time => ping_start.elapsed(),
+        AbstractPacket::PingResponse { time: t } => {
+            anyhow::bail!("Ping response has a mismatched payload ({} != {})", t, time)
+        }
+        p => anyhow::bail!("Expected a ping response, got {}", <&'static str>::from(p)),
+    };
+
+    Ok(ServerStatus {
+        version: response.version,
+        players: response.players,
+        description: response.description,
+        favicon: response.favicon.as_deref().and_then(decode_favicon),
+        latency,
+    })
+}
+
+/// Server list ping used by 1.6 and older, see https://wiki.vg/Server_List_Ping#1.6
+async fn query_status_legacy(address: &str, port: u16) -> anyhow::Result<ServerStatus> {
+    let mut stream = TcpStream::connect(format!("{}:{}", address, port)).await?;
+
+    let start = Instant::now();
+    // LegacyServerListPing_5, this isn't framed like a regular packet
+    stream.write_all(&[0xfe, 0x01]).await?;
+
+    let id = stream.read_u8().await?;
+    ensure!(
+        id == 0xff,
+        "Expected a kick packet in response to a legacy ping, got 0x{:x}",
+        id
+    );
+
+    let len = stream.read_u16().await?;
+    let mut utf16 = vec![0u16; len as usize];
+    for c in utf16.iter_mut() {
+        *c = stream.read_u16().await?;
+    }
+    let latency = start.elapsed();
+
+    let response = String::from_utf16(&utf16)?;
+    let fields: Vec<&str> = if let Some(r) = response.strip_prefix("§1\0") {
+        // 1.4 and up: protocol, version, MOTD, online players and max players
+        r.split('\0').collect()
+    } else {
+        // Beta 1.8 to 1.3: MOTD, online players and max players
+        ["-1", ""].into_iter().chain(response.split('§')).collect()
+    };
+
+    ensure!(
+        fields.len() == 5,
+        "Legacy ping response has {} fields, expected 5",
+        fields.len()
+    );
+
+    Ok(ServerStatus {
+        version: VersionInfo {
+            name: fields[1].to_string(),
+            protocol: fields[0].parse()?,
+        },
+        players: Players {
+            online: fields[3].parse()?,
+            max: fields[4].parse()?,
+            sample: vec![],
+        },
+        description: ChatComponent::from(fields[2].to_string()),
+        favicon: None,
+        latency,
+    })
+}
+
+/// Asks the server which protocol version it's running
+pub async fn detect_protocol(address: &str, port: u16) -> anyhow::Result<ProtocolVersion> {
+    let version = query_status(address, port).await?.version;
+
+    ProtocolVersion::from_protocol(version.protocol).map_err(|e| {
+        anyhow::anyhow!(
+            "Server is running {} (protocol {}): {}",
+            version.name,
+            version.protocol,
+            e
+        )
+    })
+}
@@ -1,4 +1,7 @@
-use std::{fmt::Debug, io::Cursor};
+use std::{
+    fmt::Debug,
+    io::{Cursor, Read},
+};
 
 use intmap::IntMap;
 use minecrab_derive::Serializable;
@@ -144,6 +147,14 @@ pub struct EntityProperty {
     pub modifiers: PrefixedVec<EntityModifier, i16>,
 }
 
+#[allow(non_camel_case_types)]
+#[derive(Debug, Default, Clone, PartialEq, Serializable)]
+pub struct EntityProperty_47 {
+    pub key: String,
+    pub value: f64,
+    pub modifiers: PrefixedVec<EntityModifier, VarInt>,
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Serializable)]
 pub struct EntityModifier {
     pub uuid: u128,
@@ -202,37 +213,239 @@ pub struct EntitySpawnProperty_5 {
     pub signature: String,
 }
 
+/// A property of a player's profile, such as their skin
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GameProfileProperty {
+    pub name: String,
+    pub value: String,
+    pub signature: Option<String>,
+}
+
+impl Serializable for GameProfileProperty {
+    fn read_from<R: std::io::Read>(r: &mut R) -> anyhow::Result<Self> {
+        Ok(Self {
+            name: String::read_from(r)?,
+            value: String::read_from(r)?,
+            signature: if bool::read_from(r)? {
+                Some(String::read_from(r)?)
+            } else {
+                None
+            },
+        })
+    }
+
+    fn write_to<W: std::io::Write>(&self, w: &mut W) -> anyhow::Result<()> {
+        self.name.write_to(w)?;
+        self.value.write_to(w)?;
+        self.signature.is_some().write_to(w)?;
+        if let Some(signature) = &self.signature {
+            signature.write_to(w)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The slots of an entity's equipment, 1.16+ can send several of them in a single packet
+///
+/// Every slot id but the last one has its top bit set.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EquipmentList(pub Vec<(u8, Slot)>);
+
+impl Serializable for EquipmentList {
+    fn read_from_versioned<R: std::io::Read>(r: &mut R, version: i32) -> anyhow::Result<Self> {
+        let mut list = vec![];
+        loop {
+            let slot = u8::read_from(r)?;
+            list.push((slot & 0x7f, Slot::read_from_versioned(r, version)?));
+            if slot & 0x80 == 0 {
+                return Ok(Self(list));
+            }
+        }
+    }
+
+    fn write_to_versioned<W: std::io::Write>(&self, w: &mut W, version: i32) -> anyhow::Result<()> {
+        anyhow::ensure!(!self.0.is_empty(), "Equipment lists can't be empty");
+        for (i, (slot, item)) in self.0.iter().enumerate() {
+            let more = if i + 1 < self.0.len() { 0x80 } else { 0 };
+            (slot | more).write_to(w)?;
+            item.write_to_versioned(w, version)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A chat message the client has seen, used to chain message signatures in 1.19.1+
+#[derive(Debug, Default, Clone, PartialEq, Serializable)]
+pub struct PreviousMessage {
+    pub sender: uuid::Uuid,
+    pub signature: PrefixedVec<u8, VarInt>,
+}
+
+/// A block entity sent along with a 1.18+ chunk column, its NBT doesn't contain the id or position
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ChunkBlockEntity {
+    /// X and Z relative to the column, packed as `x << 4 | z`
+    pub packed_xz: u8,
+    pub y: i16,
+    /// Block entity registry id
+    pub kind: VarInt,
+    pub data: OptionalNbt,
+}
+
+impl Serializable for ChunkBlockEntity {
+    fn read_from_versioned<R: std::io::Read>(r: &mut R, version: i32) -> anyhow::Result<Self> {
+        Ok(Self {
+            packed_xz: u8::read_from(r)?,
+            y: i16::read_from(r)?,
+            kind: VarInt::read_from(r)?,
+            data: OptionalNbt::read_from_versioned(r, version)?,
+        })
+    }
+
+    fn write_to_versioned<W: std::io::Write>(&self, w: &mut W, version: i32) -> anyhow::Result<()> {
+        self.packed_xz.write_to(w)?;
+        self.y.write_to(w)?;
+        self.kind.write_to(w)?;
+        self.data.write_to_versioned(w, version)
+    }
+}
+
+/// Block and sky light of a column as sent by 1.17+, with one array for every set bit of the light masks
+///
+/// Bit 0 of the masks is the section below the lowest one. Sections in the empty masks have no light at all.
+#[derive(Debug, Default, Clone, PartialEq, Serializable)]
+pub struct ChunkLight {
+    pub trust_edges: bool,
+    pub sky_light_mask: PrefixedVec<i64, VarInt>,
+    pub block_light_mask: PrefixedVec<i64, VarInt>,
+    pub empty_sky_light_mask: PrefixedVec<i64, VarInt>,
+    pub empty_block_light_mask: PrefixedVec<i64, VarInt>,
+    pub sky_light: PrefixedVec<PrefixedVec<u8, VarInt>, VarInt>,
+    pub block_light: PrefixedVec<PrefixedVec<u8, VarInt>, VarInt>,
+}
+
+/// An item stack, `item_id` is -1 for empty slots
+///
+/// 1.13 moved the damage into the NBT, and 1.13.2 replaced the -1 with a boolean in front of a VarInt id.
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct Slot {
     pub item_id: i16,
     pub item_count: Option<u8>,
+    /// Only sent before 1.13
     pub item_damage: Option<i16>,
     pub data: Option<nbt::Blob>,
 }
 
 impl Serializable for Slot {
-    fn read_from<R: std::io::Read>(r: &mut R) -> anyhow::Result<Self> {
+    fn read_from_versioned<R: std::io::Read>(r: &mut R, version: i32) -> anyhow::Result<Self> {
+        let item_id = if version >= versions::PROTO_1_13_2 {
+            if bool::read_from(r)? {
+                VarInt::read_from(r)?.0 as i16
+            } else {
+                -1
+            }
+        } else {
+            i16::read_from(r)?
+        };
+
         let mut s = Self {
-            item_id: i16::read_from(r)?,
+            item_id,
             ..Default::default()
         };
 
         if s.item_id != -1 {
             s.item_count = Some(u8::read_from(r)?);
-            s.item_damage = Some(i16::read_from(r)?);
+            if version < versions::PROTO_1_13 {
+                s.item_damage = Some(i16::read_from(r)?);
+            }
+
+            s.data = OptionalNbt::read_from_versioned(r, version)?.0;
+        }
+
+        Ok(s)
+    }
+
+    fn write_to_versioned<W: std::io::Write>(&self, w: &mut W, version: i32) -> anyhow::Result<()> {
+        if version >= versions::PROTO_1_13_2 {
+            (self.item_id != -1).write_to(w)?;
+            if self.item_id == -1 {
+                return Ok(());
+            }
+
+            VarInt(self.item_id as i32).write_to(w)?;
+        } else {
+            self.item_id.write_to(w)?;
+            if self.item_id == -1 {
+                return Ok(());
+            }
+        }
 
+        self.item_count.unwrap_or(1).write_to(w)?;
+        if version < versions::PROTO_1_13 {
+            self.item_damage.unwrap_or(0).write_to(w)?;
+        }
+
+        write_optional_nbt(w, self.data.as_ref(), version)
+    }
+}
+
+/// An NBT compound that may be absent
+///
+/// Before 1.8 it's gzip compressed and prefixed with its length (-1 if absent), 1.8+ sends it uncompressed with a TAG_End in place of the compound if there is none.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct OptionalNbt(pub Option<nbt::Blob>);
+
+impl Serializable for OptionalNbt {
+    fn read_from_versioned<R: std::io::Read>(r: &mut R, version: i32) -> anyhow::Result<Self> {
+        if version < versions::PROTO_1_8 {
             let nbt_length = i16::read_from(r)?;
-            if nbt_length != -1 {
-                let mut data = vec![0u8; nbt_length as usize];
-                r.read_exact(&mut data)?;
-                let mut c = Cursor::new(&data);
-                let nbt_data = CompressedGzData::<nbt::Blob>::read_from(&mut c)?.0;
-                s.data = Some(nbt_data);
+            if nbt_length < 0 {
+                return Ok(Self(None));
+            }
+
+            let mut data = vec![0u8; nbt_length as usize];
+            r.read_exact(&mut data)?;
+            let mut c = Cursor::new(&data);
+            Ok(Self(Some(
+                CompressedGzData::<nbt::Blob>::read_from(&mut c)?.0,
+            )))
+        } else {
+            let tag = u8::read_from(r)?;
+            if tag == 0 {
+                return Ok(Self(None));
             }
+
+            Ok(Self(Some(nbt::Blob::read_from(
+                &mut [tag].as_slice().chain(r),
+            )?)))
         }
+    }
 
-        Ok(s)
+    fn write_to_versioned<W: std::io::Write>(&self, w: &mut W, version: i32) -> anyhow::Result<()> {
+        write_optional_nbt(w, self.0.as_ref(), version)
+    }
+}
+
+fn write_optional_nbt<W: std::io::Write>(
+    w: &mut W,
+    data: Option<&nbt::Blob>,
+    version: i32,
+) -> anyhow::Result<()> {
+    match data {
+        Some(data) if version < versions::PROTO_1_8 => {
+            let mut buf = vec![];
+            data.to_gzip_writer(&mut buf)?;
+            (buf.len() as i16).write_to(w)?;
+            w.write_all(&buf)?;
+        }
+        Some(data) => data.write_to(w)?,
+        None if version < versions::PROTO_1_8 => (-1i16).write_to(w)?,
+        None => 0u8.write_to(w)?,
     }
+
+    Ok(())
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -241,7 +454,9 @@ pub struct PrefixedVec<T: Serializable, C: Serializable + TryInto<isize>> {
     _m: std::marker::PhantomData<C>,
 }
 
-impl<T: Serializable, C: Serializable + TryInto<isize>> Serializable for PrefixedVec<T, C> {
+impl<T: Serializable, C: Serializable + TryInto<isize> + TryFrom<isize>> Serializable
+    for PrefixedVec<T, C>
+{
     fn read_from<R: std::io::Read>(r: &mut R) -> anyhow::Result<Self> {
         let count = C::read_from(r)?;
         let icount: isize = if let Ok(s) = count.try_into() {
@@ -257,6 +472,68 @@ impl<T: Serializable, C: Serializable + TryInto<isize>> Serializable for Prefixe
 
         Ok(v)
     }
+
+    fn write_to<W: std::io::Write>(&self, w: &mut W) -> anyhow::Result<()> {
+        let count = if let Ok(c) = C::try_from(self.data.len() as isize) {
+            c
+        } else {
+            anyhow::bail!(
+                "PrefixedVec length {} doesn't fit in its count type",
+                self.data.len()
+            )
+        };
+        count.write_to(w)?;
+
+        for v in &self.data {
+            v.write_to(w)?;
+        }
+
+        Ok(())
+    }
+
+    // The versioned variants pass the version on to the elements, for types like Slot that only implement those
+    fn read_from_versioned<R: std::io::Read>(r: &mut R, version: i32) -> anyhow::Result<Self> {
+        let count = C::read_from(r)?;
+        let icount: isize = if let Ok(s) = count.try_into() {
+            s
+        } else {
+            anyhow::bail!("Failed to cast count type for PrefixedVec to isize")
+        };
+        let mut v = PrefixedVec::default();
+
+        for _ in 0..icount {
+            v.data.push(T::read_from_versioned(r, version)?);
+        }
+
+        Ok(v)
+    }
+
+    fn write_to_versioned<W: std::io::Write>(&self, w: &mut W, version: i32) -> anyhow::Result<()> {
+        let count = if let Ok(c) = C::try_from(self.data.len() as isize) {
+            c
+        } else {
+            anyhow::bail!(
+                "PrefixedVec length {} doesn't fit in its count type",
+                self.data.len()
+            )
+        };
+        count.write_to(w)?;
+
+        for v in &self.data {
+            v.write_to_versioned(w, version)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: Serializable, C: Serializable + TryInto<isize>> From<Vec<T>> for PrefixedVec<T, C> {
+    fn from(data: Vec<T>) -> Self {
+        Self {
+            data,
+            _m: std::marker::PhantomData,
+        }
+    }
 }
 
 impl<T: Serializable, C: Serializable + TryInto<isize>> Default for PrefixedVec<T, C> {
@@ -271,23 +548,305 @@ impl<T: Serializable, C: Serializable + TryInto<isize>> Default for PrefixedVec<
 #[derive(Debug, Clone, PartialEq)]
 pub enum MetadataField {
     Byte(u8),
+    /// Only used before 1.9
     Short(i16),
+    /// Only used before 1.9
     Int(i32),
     Float(f32),
     String(String),
     Slot(Slot),
-    // Position(PositionIII),
+    /// Only used before 1.9
+    Vector(i32, i32, i32),
+    Rotation(f32, f32, f32),
+    VarInt(i32),
+    Chat(String),
+    Bool(bool),
+    Position(Position),
+    OptPosition(Option<Position>),
+    Direction(i32),
+    OptUuid(Option<uuid::Uuid>),
+    /// Block state id, 0 means no block
+    BlockState(i32),
+    Nbt(nbt::Blob),
+    /// Only used from 1.13 on
+    OptChat(Option<String>),
+    /// Type, profession and level, only used from 1.14 on
+    VillagerData(i32, i32, i32),
+    /// Only used from 1.14 on
+    OptVarInt(Option<i32>),
+    /// Only used from 1.14 on
+    Pose(i32),
+    /// Only used from 1.19 on
+    CatVariant(i32),
+    /// Only used from 1.19 on
+    FrogVariant(i32),
+    /// Dimension and position, only used from 1.19 on
+    OptGlobalPos(Option<(String, Position)>),
+    /// Only used from 1.19 on
+    PaintingVariant(i32),
 }
 
 impl MetadataField {
-    pub fn get_type(&self) -> u8 {
-        match self {
+    /// Returns the type id of this field in the given protocol version, or `None` if the version can't represent it
+    pub fn get_type(&self, version: i32) -> Option<u8> {
+        if version < versions::PROTO_1_9 {
+            return Some(match self {
+                MetadataField::Byte(_) => 0,
+                MetadataField::Short(_) => 1,
+                MetadataField::Int(_) => 2,
+                MetadataField::Float(_) => 3,
+                MetadataField::String(_) => 4,
+                MetadataField::Slot(_) => 5,
+                MetadataField::Vector(..) => 6,
+                MetadataField::Rotation(..) if version >= versions::PROTO_1_8 => 7,
+                _ => return None,
+            });
+        }
+
+        // 1.13 inserted the optional chat type, shifting everything after it
+        if version >= versions::PROTO_1_13 {
+            return Some(match self {
+                MetadataField::Byte(_) => 0,
+                MetadataField::VarInt(_) => 1,
+                MetadataField::Float(_) => 2,
+                MetadataField::String(_) => 3,
+                MetadataField::Chat(_) => 4,
+                MetadataField::OptChat(_) => 5,
+                MetadataField::Slot(_) => 6,
+                MetadataField::Bool(_) => 7,
+                MetadataField::Rotation(..) => 8,
+                MetadataField::Position(_) => 9,
+                MetadataField::OptPosition(_) => 10,
+                MetadataField::Direction(_) => 11,
+                MetadataField::OptUuid(_) => 12,
+                MetadataField::BlockState(_) => 13,
+                MetadataField::Nbt(_) => 14,
+                MetadataField::VillagerData(..) if version >= versions::PROTO_1_14 => 16,
+                MetadataField::OptVarInt(_) if version >= versions::PROTO_1_14 => 17,
+                MetadataField::Pose(_) if version >= versions::PROTO_1_14 => 18,
+                MetadataField::CatVariant(_) if version >= versions::PROTO_1_19 => 19,
+                MetadataField::FrogVariant(_) if version >= versions::PROTO_1_19 => 20,
+                MetadataField::OptGlobalPos(_) if version >= versions::PROTO_1_19 => 21,
+                MetadataField::PaintingVariant(_) if version >= versions::PROTO_1_19 => 22,
+                _ => return None,
+            });
+        }
+
+        Some(match self {
             MetadataField::Byte(_) => 0,
-            MetadataField::Short(_) => 1,
-            MetadataField::Int(_) => 2,
-            MetadataField::Float(_) => 3,
-            MetadataField::String(_) => 4,
+            MetadataField::VarInt(_) => 1,
+            MetadataField::Float(_) => 2,
+            MetadataField::String(_) => 3,
+            MetadataField::Chat(_) => 4,
             MetadataField::Slot(_) => 5,
+            MetadataField::Bool(_) => 6,
+            MetadataField::Rotation(..) => 7,
+            MetadataField::Position(_) => 8,
+            MetadataField::OptPosition(_) => 9,
+            MetadataField::Direction(_) => 10,
+            MetadataField::OptUuid(_) => 11,
+            MetadataField::BlockState(_) => 12,
+            MetadataField::Nbt(_) if version >= versions::PROTO_1_12 => 13,
+            _ => return None,
+        })
+    }
+
+    fn read_from_versioned<R: std::io::Read>(
+        r: &mut R,
+        kind: u8,
+        version: i32,
+    ) -> anyhow::Result<Self> {
+        if version < versions::PROTO_1_9 {
+            return Ok(match kind {
+                0 => MetadataField::Byte(Serializable::read_from(r)?),
+                1 => MetadataField::Short(Serializable::read_from(r)?),
+                2 => MetadataField::Int(Serializable::read_from(r)?),
+                3 => MetadataField::Float(Serializable::read_from(r)?),
+                4 => MetadataField::String(Serializable::read_from(r)?),
+                5 => MetadataField::Slot(Serializable::read_from_versioned(r, version)?),
+                6 => MetadataField::Vector(
+                    Serializable::read_from(r)?,
+                    Serializable::read_from(r)?,
+                    Serializable::read_from(r)?,
+                ),
+                7 if version >= versions::PROTO_1_8 => MetadataField::Rotation(
+                    Serializable::read_from(r)?,
+                    Serializable::read_from(r)?,
+                    Serializable::read_from(r)?,
+                ),
+                _ => anyhow::bail!("Invalid metadata type {}", kind),
+            });
+        }
+
+        if version >= versions::PROTO_1_13 {
+            return Ok(match kind {
+                0 => MetadataField::Byte(Serializable::read_from(r)?),
+                1 => MetadataField::VarInt(VarInt::read_from(r)?.0),
+                2 => MetadataField::Float(Serializable::read_from(r)?),
+                3 => MetadataField::String(Serializable::read_from(r)?),
+                4 => MetadataField::Chat(Serializable::read_from(r)?),
+                5 => MetadataField::OptChat(if bool::read_from(r)? {
+                    Some(Serializable::read_from(r)?)
+                } else {
+                    None
+                }),
+                6 => MetadataField::Slot(Serializable::read_from_versioned(r, version)?),
+                7 => MetadataField::Bool(Serializable::read_from(r)?),
+                8 => MetadataField::Rotation(
+                    Serializable::read_from(r)?,
+                    Serializable::read_from(r)?,
+                    Serializable::read_from(r)?,
+                ),
+                9 => MetadataField::Position(Serializable::read_from_versioned(r, version)?),
+                10 => MetadataField::OptPosition(if bool::read_from(r)? {
+                    Some(Serializable::read_from_versioned(r, version)?)
+                } else {
+                    None
+                }),
+                11 => MetadataField::Direction(VarInt::read_from(r)?.0),
+                12 => MetadataField::OptUuid(if bool::read_from(r)? {
+                    Some(Serializable::read_from(r)?)
+                } else {
+                    None
+                }),
+                13 => MetadataField::BlockState(VarInt::read_from(r)?.0),
+                14 => MetadataField::Nbt(Serializable::read_from(r)?),
+                // The particle data depends on the particle, which we don't have a table for
+                15 => anyhow::bail!("Particle metadata isn't supported"),
+                16 if version >= versions::PROTO_1_14 => MetadataField::VillagerData(
+                    VarInt::read_from(r)?.0,
+                    VarInt::read_from(r)?.0,
+                    VarInt::read_from(r)?.0,
+                ),
+                // 0 is absent, anything else is the value plus one
+                17 if version >= versions::PROTO_1_14 => {
+                    let v = VarInt::read_from(r)?.0;
+                    MetadataField::OptVarInt((v != 0).then_some(v - 1))
+                }
+                18 if version >= versions::PROTO_1_14 => {
+                    MetadataField::Pose(VarInt::read_from(r)?.0)
+                }
+                19 if version >= versions::PROTO_1_19 => {
+                    MetadataField::CatVariant(VarInt::read_from(r)?.0)
+                }
+                20 if version >= versions::PROTO_1_19 => {
+                    MetadataField::FrogVariant(VarInt::read_from(r)?.0)
+                }
+                21 if version >= versions::PROTO_1_19 => {
+                    MetadataField::OptGlobalPos(if bool::read_from(r)? {
+                        Some((
+                            Serializable::read_from(r)?,
+                            Serializable::read_from_versioned(r, version)?,
+                        ))
+                    } else {
+                        None
+                    })
+                }
+                22 if version >= versions::PROTO_1_19 => {
+                    MetadataField::PaintingVariant(VarInt::read_from(r)?.0)
+                }
+                _ => anyhow::bail!("Invalid metadata type {}", kind),
+            });
+        }
+
+        Ok(match kind {
+            0 => MetadataField::Byte(Serializable::read_from(r)?),
+            1 => MetadataField::VarInt(VarInt::read_from(r)?.0),
+            2 => MetadataField::Float(Serializable::read_from(r)?),
+            3 => MetadataField::String(Serializable::read_from(r)?),
+            4 => MetadataField::Chat(Serializable::read_from(r)?),
+            5 => MetadataField::Slot(Serializable::read_from_versioned(r, version)?),
+            6 => MetadataField::Bool(Serializable::read_from(r)?),
+            7 => MetadataField::Rotation(
+                Serializable::read_from(r)?,
+                Serializable::read_from(r)?,
+                Serializable::read_from(r)?,
+            ),
+            8 => MetadataField::Position(Serializable::read_from_versioned(r, version)?),
+            9 => MetadataField::OptPosition(if bool::read_from(r)? {
+                Some(Serializable::read_from_versioned(r, version)?)
+            } else {
+                None
+            }),
+            10 => MetadataField::Direction(VarInt::read_from(r)?.0),
+            11 => MetadataField::OptUuid(if bool::read_from(r)? {
+                Some(Serializable::read_from(r)?)
+            } else {
+                None
+            }),
+            12 => MetadataField::BlockState(VarInt::read_from(r)?.0),
+            13 if version >= versions::PROTO_1_12 => {
+                MetadataField::Nbt(Serializable::read_from(r)?)
+            }
+            _ => anyhow::bail!("Invalid metadata type {}", kind),
+        })
+    }
+
+    fn write_to_versioned<W: std::io::Write>(&self, w: &mut W, version: i32) -> anyhow::Result<()> {
+        match self {
+            MetadataField::Byte(v) => v.write_to(w),
+            MetadataField::Short(v) => v.write_to(w),
+            MetadataField::Int(v) => v.write_to(w),
+            MetadataField::Float(v) => v.write_to(w),
+            MetadataField::String(v) | MetadataField::Chat(v) => v.write_to(w),
+            MetadataField::Slot(v) => v.write_to_versioned(w, version),
+            MetadataField::Vector(x, y, z) => {
+                x.write_to(w)?;
+                y.write_to(w)?;
+                z.write_to(w)
+            }
+            MetadataField::Rotation(x, y, z) => {
+                x.write_to(w)?;
+                y.write_to(w)?;
+                z.write_to(w)
+            }
+            MetadataField::VarInt(v)
+            | MetadataField::Direction(v)
+            | MetadataField::BlockState(v)
+            | MetadataField::Pose(v)
+            | MetadataField::CatVariant(v)
+            | MetadataField::FrogVariant(v)
+            | MetadataField::PaintingVariant(v) => VarInt(*v).write_to(w),
+            MetadataField::Bool(v) => v.write_to(w),
+            MetadataField::Position(v) => v.write_to_versioned(w, version),
+            MetadataField::OptPosition(v) => {
+                v.is_some().write_to(w)?;
+                match v {
+                    Some(v) => v.write_to_versioned(w, version),
+                    None => Ok(()),
+                }
+            }
+            MetadataField::OptUuid(v) => {
+                v.is_some().write_to(w)?;
+                match v {
+                    Some(v) => v.write_to(w),
+                    None => Ok(()),
+                }
+            }
+            MetadataField::Nbt(v) => v.write_to(w),
+            MetadataField::OptChat(v) => {
+                v.is_some().write_to(w)?;
+                match v {
+                    Some(v) => v.write_to(w),
+                    None => Ok(()),
+                }
+            }
+            MetadataField::VillagerData(kind, profession, level) => {
+                VarInt(*kind).write_to(w)?;
+                VarInt(*profession).write_to(w)?;
+                VarInt(*level).write_to(w)
+            }
+            MetadataField::OptVarInt(v) => VarInt(v.map_or(0, |v| v + 1)).write_to(w),
+            MetadataField::OptGlobalPos(v) => {
+                v.is_some().write_to(w)?;
+                match v {
+                    Some((dimension, position)) => {
+                        dimension.write_to(w)?;
+                        position.write_to_versioned(w, version)
+                    }
+                    None => Ok(()),
+                }
+            }
         }
     }
 }
@@ -298,50 +857,60 @@ pub struct EntityMeta {
 }
 
 impl Serializable for EntityMeta {
-    fn read_from<R: std::io::Read>(r: &mut R) -> anyhow::Result<Self> {
+    fn read_from_versioned<R: std::io::Read>(r: &mut R, version: i32) -> anyhow::Result<Self> {
         let mut m = EntityMeta::default();
 
         for _ in 0..256 {
-            let tb = u8::read_from(r)?;
-            if tb == 0x7f {
-                break;
-            }
-
-            let index = tb & 0x1f;
-            let kind = (tb >> 5) & 0x07;
-
-            let v = match kind {
-                0 => MetadataField::Byte(Serializable::read_from(r)?),
-                1 => MetadataField::Short(Serializable::read_from(r)?),
-                2 => MetadataField::Int(Serializable::read_from(r)?),
-                3 => MetadataField::Float(Serializable::read_from(r)?),
-                4 => MetadataField::String(Serializable::read_from(r)?),
-                5 => MetadataField::Slot(Serializable::read_from(r)?),
-                _ => anyhow::bail!("Invalid metadata type {}", kind),
+            // Before 1.9 the index and type are packed into a single byte, with 0x7f marking the end
+            let (index, kind) = if version < versions::PROTO_1_9 {
+                let tb = u8::read_from(r)?;
+                if tb == 0x7f {
+                    break;
+                }
+
+                (tb & 0x1f, (tb >> 5) & 0x07)
+            } else {
+                let index = u8::read_from(r)?;
+                if index == 0xff {
+                    break;
+                }
+
+                (index, VarInt::read_from(r)?.0 as u8)
             };
 
+            let v = MetadataField::read_from_versioned(r, kind, version)?;
             m.meta.insert(index as u64, v);
         }
 
         Ok(m)
     }
 
-    fn write_to<W: std::io::Write>(&self, w: &mut W) -> anyhow::Result<()> {
+    fn write_to_versioned<W: std::io::Write>(&self, w: &mut W, version: i32) -> anyhow::Result<()> {
         for (k, v) in self.meta.iter() {
-            let kind = v.get_type();
-            u8::write_to(&(*k as u8 | (kind << 5)), w)?;
-
-            match v {
-                MetadataField::Byte(v) => Serializable::write_to(v, w)?,
-                MetadataField::Short(v) => Serializable::write_to(v, w)?,
-                MetadataField::Int(v) => Serializable::write_to(v, w)?,
-                MetadataField::Float(v) => Serializable::write_to(v, w)?,
-                MetadataField::String(v) => Serializable::write_to(v, w)?,
-                MetadataField::Slot(v) => Serializable::write_to(v, w)?,
+            let kind = match v.get_type(version) {
+                Some(kind) => kind,
+                None => anyhow::bail!(
+                    "Metadata field {:?} can't be sent to protocol {}",
+                    v,
+                    version
+                ),
             };
+
+            if version < versions::PROTO_1_9 {
+                u8::write_to(&(*k as u8 | (kind << 5)), w)?;
+            } else {
+                u8::write_to(&(*k as u8), w)?;
+                VarInt(kind as i32).write_to(w)?;
+            }
+
+            v.write_to_versioned(w, version)?;
         }
 
-        u8::write_to(&0x7f, w)?;
+        if version < versions::PROTO_1_9 {
+            u8::write_to(&0x7f, w)?;
+        } else {
+            u8::write_to(&0xff, w)?;
+        }
 
         Ok(())
     }
@@ -364,6 +933,10 @@ impl Serializable for nbt::Blob {
     fn read_from<R: std::io::Read>(r: &mut R) -> anyhow::Result<Self> {
         Ok(nbt::Blob::from_reader(r)?)
     }
+
+    fn write_to<W: std::io::Write>(&self, w: &mut W) -> anyhow::Result<()> {
+        Ok(self.to_writer(w)?)
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -394,6 +967,18 @@ impl Serializable for ObjectData {
 
         Ok(Self { data, velocity })
     }
+
+    fn write_to<W: std::io::Write>(&self, w: &mut W) -> anyhow::Result<()> {
+        self.data.write_to(w)?;
+        if self.data != 0 {
+            let (x, y, z) = self.velocity.unwrap_or_default();
+            x.write_to(w)?;
+            y.write_to(w)?;
+            z.write_to(w)?;
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Default, Clone, PartialEq)]
@@ -438,18 +1023,19 @@ impl Debug for FixedPoint8 {
     }
 }
 
+/// Relative movement used from 1.9 onwards, in 1/4096ths of a block
 #[derive(Default, Clone, PartialEq)]
 pub struct FixedPoint16(pub f64);
 
 impl Serializable for FixedPoint16 {
     fn read_from<R: std::io::Read>(r: &mut R) -> anyhow::Result<Self> {
-        let v = i8::read_from(r)?;
+        let v = i16::read_from(r)?;
 
-        Ok(Self(v as f64 / 32.0))
+        Ok(Self(v as f64 / 4096.0))
     }
 
     fn write_to<W: std::io::Write>(&self, w: &mut W) -> anyhow::Result<()> {
-        ((self.0 * 32.0) as i16).write_to(w)
+        ((self.0 * 4096.0) as i16).write_to(w)
     }
 }
 
@@ -1,14 +1,22 @@
-use crate::net::versions::{PROTO_1_9, PROTO_MAX};
-use anyhow::Result;
+use crate::net::versions::{
+    PROTO_1_12_1, PROTO_1_12_2, PROTO_1_13, PROTO_1_19, PROTO_1_19_2, PROTO_1_9, PROTO_MAX,
+};
 use bitflags::bitflags;
-use cgmath::{Point3, Vector3};
+use cgmath::{Point3, Vector3, Zero};
 use num_traits::{FromPrimitive, ToPrimitive};
+use uuid::Uuid;
 
-use crate::varint::*;
+use crate::{
+    ids::{IdMap, ItemStack},
+    varint::*,
+    world::BlockState,
+};
 
 use super::{
+    chat::ChatComponent,
+    error::{Error, Result},
     packets::Packet,
-    types::Position,
+    types::{EntityMeta, EntityModifier, Position, Slot},
     versions::{PROTO_1_7, PROTO_1_7_6, PROTO_1_8},
     ConnectionState,
 };
@@ -22,6 +30,13 @@ pub enum ChunkData {
     Single_47(crate::net::packets::play::clientbound::MapChunk_47),
     Single_107(crate::net::packets::play::clientbound::MapChunk_107),
     Single_110(crate::net::packets::play::clientbound::MapChunk_110),
+    Single_393(crate::net::packets::play::clientbound::MapChunk_393),
+    Single_751(crate::net::packets::play::clientbound::MapChunk_751),
+    /// Boxed as the light makes it much larger than the other variants
+    Single_757(Box<crate::net::packets::play::clientbound::MapChunk_757>),
+    Light_735(crate::net::packets::play::clientbound::UpdateLight_735),
+    Light_755(crate::net::packets::play::clientbound::UpdateLight_755),
+    Unload_107(crate::net::packets::play::clientbound::UnloadChunk_107),
 }
 
 bitflags! {
@@ -39,6 +54,125 @@ bitflags! {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum EquipmentSlot {
+    MainHand,
+    OffHand,
+    Boots,
+    Leggings,
+    Chestplate,
+    Helmet,
+}
+
+impl EquipmentSlot {
+    pub fn from_id(slot: i32) -> Option<Self> {
+        Some(match slot {
+            0 => Self::MainHand,
+            1 => Self::OffHand,
+            2 => Self::Boots,
+            3 => Self::Leggings,
+            4 => Self::Chestplate,
+            5 => Self::Helmet,
+            _ => return None,
+        })
+    }
+
+    /// Converts a slot from before 1.9, which had no off hand
+    pub fn from_legacy(slot: i16) -> Option<Self> {
+        match slot {
+            0 => Some(Self::MainHand),
+            1..=4 => Self::from_id(slot as i32 + 1),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute {
+    pub key: String,
+    /// Base value, before modifiers are applied
+    pub value: f64,
+    pub modifiers: Vec<AttributeModifier>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeModifier {
+    pub uuid: Uuid,
+    pub amount: f64,
+    /// 0 adds to the base value, 1 multiplies the base value and 2 multiplies the result
+    pub operation: u8,
+}
+
+impl From<EntityModifier> for AttributeModifier {
+    fn from(m: EntityModifier) -> Self {
+        Self {
+            uuid: Uuid::from_u128(m.uuid),
+            amount: m.amount,
+            operation: m.operation,
+        }
+    }
+}
+
+/// A single block of a `MultiBlockChange`, x and z are relative to the chunk and y is absolute
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockChangeRecord {
+    pub x: u8,
+    pub y: i32,
+    pub z: u8,
+    pub state: BlockState,
+}
+
+/// Converts an angle in 1/256ths of a turn to degrees
+fn angle(v: i8) -> f32 {
+    v as f32 * 360. / 256.
+}
+
+/// Maps a 1.16+ dimension identifier to the numeric id older versions use
+fn dimension_id(name: &str) -> i32 {
+    match name {
+        "minecraft:the_nether" => -1,
+        "minecraft:the_end" => 1,
+        _ => 0,
+    }
+}
+
+/// Reads the `min_y` of a 1.16+ dimension type, those before 1.17 don't have one and start at 0
+fn dimension_min_y(min_y: Option<&nbt::Value>) -> i32 {
+    match min_y {
+        Some(&nbt::Value::Int(y)) => y,
+        _ => 0,
+    }
+}
+
+/// The lowest block y of every dimension type in a 1.19+ registry codec, by name
+fn dimension_types(registry_codec: &nbt::Blob) -> Vec<(String, i32)> {
+    let Some(nbt::Value::Compound(registry)) = registry_codec.get("minecraft:dimension_type")
+    else {
+        return vec![];
+    };
+    let Some(nbt::Value::List(types)) = registry.get("value") else {
+        return vec![];
+    };
+
+    types
+        .iter()
+        .filter_map(|t| match t {
+            nbt::Value::Compound(t) => match (t.get("name"), t.get("element")) {
+                (Some(nbt::Value::String(name)), Some(nbt::Value::Compound(element))) => {
+                    Some((name.clone(), dimension_min_y(element.get("min_y"))))
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Converts a velocity in 1/8000ths of a block per tick to blocks per tick
+fn velocity(x: i16, y: i16, z: i16) -> Vector3<f64> {
+    Vector3::new(x as f64, y as f64, z as f64) / 8000.
+}
+
 #[derive(strum::IntoStaticStr, Clone)]
 pub enum AbstractPacket {
     // * Handshake
@@ -73,6 +207,17 @@ pub enum AbstractPacket {
         shared_secret: Vec<u8>,
         verify_token: Vec<u8>,
     },
+    /// Custom login step, only sent by 1.13+
+    LoginPluginRequest {
+        message_id: i32,
+        channel: String,
+        data: Vec<u8>,
+    },
+    LoginPluginResponse {
+        message_id: i32,
+        /// `None` if the channel isn't understood
+        data: Option<Vec<u8>>,
+    },
 
     // * Status
     ServerInfoRequest {},
@@ -122,16 +267,89 @@ pub enum AbstractPacket {
         player_motion: Vector3<f64>,
     },
 
+    JoinGame {
+        entity_id: i32,
+        gamemode: u8,
+        dimension: i32,
+        difficulty: u8,
+        max_players: u8,
+        level_kind: String,
+        /// Only sent by 1.8+
+        reduced_debug_info: Option<bool>,
+        /// Lowest block y of the dimension, below 0 for 1.18+ worlds
+        min_y: i32,
+        /// Lowest block y of every dimension type by name, 1.19+ `Respawn` only names the one it moves us to
+        dimension_types: Vec<(String, i32)>,
+    },
+
+    SpawnPosition {
+        location: Position,
+    },
+
+    UpdateTime {
+        /// Ticks since the world was created
+        world_age: i64,
+        /// Negative if the daylight cycle is disabled
+        time_of_day: i64,
+    },
+
     Respawn {
         dimension: i32,
         difficulty: u8, // TODO: Enum?
         gamemode: u8,   // TODO: Enum?
         level_kind: String,
+        /// Lowest block y of the dimension, unless the packet only names its type
+        min_y: Option<i32>,
+        /// Set by 1.19+, the type is one of the `dimension_types` sent in `JoinGame`
+        dimension_type: Option<String>,
     },
 
     BlockChange {
         location: Position,
-        kind: i32,
+        state: BlockState,
+    },
+
+    MultiBlockChange {
+        chunk_x: i32,
+        chunk_z: i32,
+        records: Vec<BlockChangeRecord>,
+    },
+
+    /// Note blocks, pistons and chests opening/closing
+    BlockAction {
+        location: Position,
+        action_id: u8,
+        action_param: u8,
+        block_id: i32,
+    },
+
+    BlockBreakAnimation {
+        entity_id: i32,
+        location: Position,
+        /// 0-9, anything else removes the animation
+        destroy_stage: i8,
+    },
+
+    /// Creates or replaces a block entity, or removes it if there's no NBT
+    TileEntityData {
+        location: Position,
+        /// The type of update, this is mostly redundant with the id in the NBT
+        action: u8,
+        nbt: Option<nbt::Blob>,
+    },
+
+    UpdateSign {
+        location: Position,
+        lines: [String; 4],
+    },
+
+    /// Sounds and particles tied to a block, such as doors opening or blocks breaking
+    WorldEvent {
+        effect_id: i32,
+        location: Position,
+        data: i32,
+        /// Play the effect regardless of distance
+        global: bool,
     },
 
     NamedSoundEffect {
@@ -142,14 +360,158 @@ pub enum AbstractPacket {
         pitch: f32,
     },
 
+    // * Entities
+    // Angles are in degrees, velocities in blocks per tick and relative moves in blocks
+    SpawnObject {
+        entity_id: i32,
+        /// Only sent by 1.9+
+        uuid: Option<Uuid>,
+        kind: i8,
+        pos: Point3<f64>,
+        yaw: f32,
+        pitch: f32,
+        /// Meaning depends on the object kind, eg. the block of a falling block or the shooter of an arrow
+        data: i32,
+        velocity: Vector3<f64>,
+    },
+
+    SpawnMob {
+        entity_id: i32,
+        /// Only sent by 1.9+
+        uuid: Option<Uuid>,
+        kind: i32,
+        pos: Point3<f64>,
+        yaw: f32,
+        pitch: f32,
+        head_pitch: f32,
+        velocity: Vector3<f64>,
+        metadata: EntityMeta,
+    },
+
+    SpawnPlayer {
+        entity_id: i32,
+        uuid: Uuid,
+        /// Only sent by 1.7, later versions take it from the player list
+        name: Option<String>,
+        pos: Point3<f64>,
+        yaw: f32,
+        pitch: f32,
+        /// Name of the held item, only sent before 1.9, later versions use `EntityEquipment`
+        current_item: Option<String>,
+        metadata: EntityMeta,
+    },
+
+    SpawnPainting {
+        entity_id: i32,
+        /// Only sent by 1.9+
+        uuid: Option<Uuid>,
+        title: String,
+        location: Position,
+        direction: u8,
+    },
+
+    SpawnExperienceOrb {
+        entity_id: i32,
+        pos: Point3<f64>,
+        count: i16,
+    },
+
+    /// Lightning bolts
+    SpawnGlobalEntity {
+        entity_id: i32,
+        kind: i8,
+        pos: Point3<f64>,
+    },
+
+    EntityMove {
+        entity_id: i32,
+        delta: Vector3<f64>,
+        /// Not sent by 1.7
+        on_ground: Option<bool>,
+    },
+
+    EntityLook {
+        entity_id: i32,
+        yaw: f32,
+        pitch: f32,
+        on_ground: Option<bool>,
+    },
+
+    EntityMoveLook {
+        entity_id: i32,
+        delta: Vector3<f64>,
+        yaw: f32,
+        pitch: f32,
+        on_ground: Option<bool>,
+    },
+
+    EntityTeleport {
+        entity_id: i32,
+        pos: Point3<f64>,
+        yaw: f32,
+        pitch: f32,
+        on_ground: Option<bool>,
+    },
+
+    EntityVelocity {
+        entity_id: i32,
+        velocity: Vector3<f64>,
+    },
+
+    EntityHeadRotation {
+        entity_id: i32,
+        head_yaw: f32,
+    },
+
+    EntityMetadata {
+        entity_id: i32,
+        metadata: EntityMeta,
+    },
+
+    /// 1.16+ can update several slots at once, older versions always send a single one
+    EntityEquipment {
+        entity_id: i32,
+        /// The item is `None` if the slot was emptied
+        items: Vec<(EquipmentSlot, Option<ItemStack>)>,
+    },
+
+    EntityDestroy {
+        entity_ids: Vec<i32>,
+    },
+
+    /// Before 1.9 this is also used for riding, with `vehicle_id` set to -1 when dismounting.
+    /// 1.9+ only uses it for leashes and sends `SetPassengers` for riding.
+    AttachEntity {
+        entity_id: i32,
+        vehicle_id: i32,
+        leash: bool,
+    },
+
+    EntityAttributes {
+        entity_id: i32,
+        attributes: Vec<Attribute>,
+    },
+
+    /// Replaces all passengers of the vehicle, only sent by 1.9+
+    SetPassengers {
+        entity_id: i32,
+        passengers: Vec<i32>,
+    },
+
+    ChatMessage {
+        message: ChatComponent,
+        /// Shown above the hotbar instead of in the chat
+        overlay: bool,
+    },
     ChatServerbound(String),
 
     Chunks(ChunkData),
 }
 
 impl AbstractPacket {
+    /// Block and item ids are converted with `ids`, so the result doesn't depend on the protocol
     // TODO: Error handling
-    pub fn from_packet(packet: Packet) -> Option<Self> {
+    pub fn from_packet(packet: Packet, ids: &IdMap) -> Option<Self> {
         Some(match packet {
             // * Handshake
             Packet::SetProtocol_5(p) => Self::SetProtocol {
@@ -159,12 +521,47 @@ impl AbstractPacket {
                 next_state: ConnectionState::from_i32(p.next_state.0)?,
             },
             // * Login
+            Packet::Compress_47(p) => Self::SetCompression {
+                threshold: p.threshold,
+            },
             Packet::SetCompression_47(p) => Self::SetCompression {
                 threshold: p.threshold,
             },
             Packet::LoginStart_5(p) => Self::LoginStart {
                 username: p.username.clone(),
             },
+            Packet::EncryptionBegin_5(p) => Self::EncryptionBeginClientbound {
+                server_id: p.server_id,
+                public_key: p.public_key.data,
+                verify_token: p.verify_token.data,
+            },
+            Packet::EncryptionBegin_47(p) => Self::EncryptionBeginClientbound {
+                server_id: p.server_id,
+                public_key: p.public_key.data,
+                verify_token: p.verify_token.data,
+            },
+            Packet::LoginPluginRequest_393(p) => Self::LoginPluginRequest {
+                message_id: p.message_id.0,
+                channel: p.channel,
+                data: p.data,
+            },
+
+            // * Status
+            Packet::ServerInfo_5(p) => Self::ServerInfo {
+                response: p.response,
+            },
+            Packet::Ping_5(p) => Self::PingResponse { time: p.time },
+
+            // * Play
+            Packet::KeepAlive_5(p) => Self::KeepAlive {
+                keep_alive_id: p.keep_alive_id as i64,
+            },
+            Packet::KeepAlive_47(p) => Self::KeepAlive {
+                keep_alive_id: p.keep_alive_id.0 as i64,
+            },
+            Packet::KeepAlive_340(p) => Self::KeepAlive {
+                keep_alive_id: p.keep_alive_id,
+            },
 
             // * Chunk data
             Packet::MapChunkBulk_5(p) => Self::Chunks(ChunkData::Bulk_5(p)),
@@ -173,6 +570,12 @@ impl AbstractPacket {
             Packet::MapChunk_47(p) => Self::Chunks(ChunkData::Single_47(p)),
             Packet::MapChunk_107(p) => Self::Chunks(ChunkData::Single_107(p)),
             Packet::MapChunk_110(p) => Self::Chunks(ChunkData::Single_110(p)),
+            Packet::MapChunk_393(p) => Self::Chunks(ChunkData::Single_393(p)),
+            Packet::MapChunk_751(p) => Self::Chunks(ChunkData::Single_751(p)),
+            Packet::MapChunk_757(p) => Self::Chunks(ChunkData::Single_757(Box::new(p))),
+            Packet::UpdateLight_735(p) => Self::Chunks(ChunkData::Light_735(p)),
+            Packet::UpdateLight_755(p) => Self::Chunks(ChunkData::Light_755(p)),
+            Packet::UnloadChunk_107(p) => Self::Chunks(ChunkData::Unload_107(p)),
 
             Packet::Position_5(p) => Self::PositionLookClientBound {
                 pos: Point3::new(p.x, p.y, p.z),
@@ -195,10 +598,58 @@ impl AbstractPacket {
                 flags: PositionFlags::from_bits(p.flags),
                 teleport_id: Some(p.teleport_id.0),
             },
+            Packet::Position_755(p) => Self::PositionLookClientBound {
+                pos: Point3::new(p.x, p.y, p.z),
+                yaw: p.yaw,
+                pitch: p.pitch,
+                flags: PositionFlags::from_bits(p.flags),
+                teleport_id: Some(p.teleport_id.0),
+            },
+            Packet::Disconnect_5(p) => Self::Disconnect { reason: p.reason },
+            Packet::KickDisconnect_5(p) => Self::Disconnect { reason: p.reason },
             Packet::Success_5(p) => Self::LoginSuccess {
                 username: p.username,
                 uuid: p.uuid,
             },
+            Packet::Success_735(p) => Self::LoginSuccess {
+                username: p.username,
+                uuid: p.uuid.to_string(),
+            },
+            Packet::Success_759(p) => Self::LoginSuccess {
+                username: p.username,
+                uuid: p.uuid.to_string(),
+            },
+            Packet::Chat_5(p) => Self::ChatMessage {
+                message: ChatComponent::from_json(&p.message),
+                overlay: false,
+            },
+            Packet::Chat_47(p) => Self::ChatMessage {
+                message: ChatComponent::from_json(&p.message),
+                overlay: p.position == 2,
+            },
+            Packet::Chat_735(p) => Self::ChatMessage {
+                message: ChatComponent::from_json(&p.message),
+                overlay: p.position == 2,
+            },
+            Packet::SystemChat_760(p) => Self::ChatMessage {
+                message: ChatComponent::from_json(&p.content),
+                overlay: p.overlay,
+            },
+            // The decoration comes from the chat type registry sent on join, assume the default "<name> message"
+            Packet::PlayerChat_760(p) => Self::ChatMessage {
+                message: ChatComponent {
+                    translate: Some("chat.type.text".to_string()),
+                    with: vec![
+                        ChatComponent::from_json(&p.network_name),
+                        match p.unsigned_content.or(p.formatted_message) {
+                            Some(json) => ChatComponent::from_json(&json),
+                            None => ChatComponent::from(p.plain_message),
+                        },
+                    ],
+                    ..Default::default()
+                },
+                overlay: false,
+            },
             Packet::Explosion_5(p) => Self::Explosion {
                 pos: Point3::new(p.x as f64, p.y as f64, p.z as f64),
                 radius: p.radius,
@@ -215,21 +666,242 @@ impl AbstractPacket {
                 ),
             },
 
+            Packet::Login_5(p) => Self::JoinGame {
+                entity_id: p.entity_id,
+                gamemode: p.game_mode,
+                dimension: p.dimension as i32,
+                difficulty: p.difficulty,
+                max_players: p.max_players,
+                level_kind: p.level_kind,
+                reduced_debug_info: None,
+                min_y: 0,
+                dimension_types: vec![],
+            },
+
+            Packet::Login_47(p) => Self::JoinGame {
+                entity_id: p.entity_id,
+                gamemode: p.game_mode,
+                dimension: p.dimension as i32,
+                difficulty: p.difficulty,
+                max_players: p.max_players,
+                level_kind: p.level_kind,
+                reduced_debug_info: Some(p.reduced_debug_info),
+                min_y: 0,
+                dimension_types: vec![],
+            },
+
+            Packet::Login_109(p) => Self::JoinGame {
+                entity_id: p.entity_id,
+                gamemode: p.game_mode,
+                dimension: p.dimension,
+                difficulty: p.difficulty,
+                max_players: p.max_players,
+                level_kind: p.level_kind,
+                reduced_debug_info: Some(p.reduced_debug_info),
+                min_y: 0,
+                dimension_types: vec![],
+            },
+
+            // 1.14+ sends the difficulty in a separate packet
+            Packet::Login_751(p) => Self::JoinGame {
+                entity_id: p.entity_id,
+                gamemode: p.game_mode,
+                dimension: dimension_id(&p.world_name),
+                difficulty: 0,
+                max_players: p.max_players.0.clamp(0, u8::MAX as i32) as u8,
+                level_kind: if p.is_flat { "flat" } else { "default" }.to_string(),
+                reduced_debug_info: Some(p.reduced_debug_info),
+                min_y: dimension_min_y(p.dimension.get("min_y")),
+                dimension_types: vec![],
+            },
+
+            Packet::Login_759(p) => {
+                let dimension_types = dimension_types(&p.registry_codec);
+                let min_y = dimension_types
+                    .iter()
+                    .find(|(name, _)| *name == p.dimension_type)
+                    .map_or(0, |&(_, min_y)| min_y);
+                Self::JoinGame {
+                    entity_id: p.entity_id,
+                    gamemode: p.game_mode,
+                    dimension: dimension_id(&p.dimension_type),
+                    difficulty: 0,
+                    max_players: p.max_players.0.clamp(0, u8::MAX as i32) as u8,
+                    level_kind: if p.is_flat { "flat" } else { "default" }.to_string(),
+                    reduced_debug_info: Some(p.reduced_debug_info),
+                    min_y,
+                    dimension_types,
+                }
+            }
+
+            Packet::SpawnPosition_5(p) => Self::SpawnPosition {
+                location: p.location.into(),
+            },
+
+            Packet::SpawnPosition_47(p) => Self::SpawnPosition {
+                location: p.location,
+            },
+
+            Packet::SpawnPosition_755(p) => Self::SpawnPosition {
+                location: p.location,
+            },
+
+            Packet::UpdateTime_5(p) => Self::UpdateTime {
+                world_age: p.age,
+                time_of_day: p.time,
+            },
+
             Packet::Respawn_5(p) => Self::Respawn {
                 dimension: p.dimension,
                 difficulty: p.difficulty,
                 gamemode: p.gamemode,
                 level_kind: p.level_kind,
+                min_y: Some(0),
+                dimension_type: None,
+            },
+
+            Packet::Respawn_751(p) => Self::Respawn {
+                dimension: dimension_id(&p.world_name),
+                difficulty: 0,
+                gamemode: p.gamemode,
+                level_kind: if p.is_flat { "flat" } else { "default" }.to_string(),
+                min_y: Some(dimension_min_y(p.dimension.get("min_y"))),
+                dimension_type: None,
+            },
+
+            Packet::Respawn_759(p) => Self::Respawn {
+                dimension: dimension_id(&p.dimension_type),
+                difficulty: 0,
+                gamemode: p.gamemode,
+                level_kind: if p.is_flat { "flat" } else { "default" }.to_string(),
+                min_y: None,
+                dimension_type: Some(p.dimension_type),
             },
 
             Packet::BlockChange_5(p) => Self::BlockChange {
                 location: p.location.into(),
-                kind: p.kind.0,
+                state: ids.block_from_wire(p.kind.0 << 4 | p.metadata as i32),
             },
 
             Packet::BlockChange_47(p) => Self::BlockChange {
                 location: p.location,
-                kind: p.kind.0,
+                state: ids.block_from_wire(p.kind.0),
+            },
+
+            Packet::MultiBlockChange_5(p) => Self::MultiBlockChange {
+                chunk_x: p.chunk_x,
+                chunk_z: p.chunk_z,
+                records: p
+                    .records
+                    .iter()
+                    .map(|r| BlockChangeRecord {
+                        x: r.x,
+                        y: r.y as i32,
+                        z: r.z,
+                        state: ids.block_from_wire((r.block_id as i32) << 4 | r.block_meta as i32),
+                    })
+                    .collect(),
+            },
+
+            Packet::MultiBlockChange_47(p) => Self::MultiBlockChange {
+                chunk_x: p.chunk_x,
+                chunk_z: p.chunk_z,
+                records: p
+                    .records
+                    .data
+                    .iter()
+                    .map(|r| BlockChangeRecord {
+                        x: r.pos_horizontal >> 4,
+                        y: r.y as i32,
+                        z: r.pos_horizontal & 0x0f,
+                        state: ids.block_from_wire(r.block_id.0),
+                    })
+                    .collect(),
+            },
+
+            Packet::MultiBlockChange_751(p) => Self::MultiBlockChange {
+                chunk_x: (p.section >> 42) as i32,
+                chunk_z: (p.section << 22 >> 42) as i32,
+                records: p
+                    .records
+                    .data
+                    .iter()
+                    .map(|r| BlockChangeRecord {
+                        x: (r.0 >> 8 & 0xf) as u8,
+                        y: ((p.section << 44 >> 44) * 16 + (r.0 & 0xf)) as i32,
+                        z: (r.0 >> 4 & 0xf) as u8,
+                        state: ids.block_from_wire((r.0 >> 12) as i32),
+                    })
+                    .collect(),
+            },
+
+            Packet::BlockAction_5(p) => Self::BlockAction {
+                location: p.location.into(),
+                action_id: p.byte1,
+                action_param: p.byte2,
+                block_id: p.block_id.0,
+            },
+
+            Packet::BlockAction_47(p) => Self::BlockAction {
+                location: p.location,
+                action_id: p.byte1,
+                action_param: p.byte2,
+                block_id: p.block_id.0,
+            },
+
+            Packet::BlockBreakAnimation_5(p) => Self::BlockBreakAnimation {
+                entity_id: p.entity_id.0,
+                location: p.location.into(),
+                destroy_stage: p.destroy_stage,
+            },
+
+            Packet::BlockBreakAnimation_47(p) => Self::BlockBreakAnimation {
+                entity_id: p.entity_id.0,
+                location: p.location,
+                destroy_stage: p.destroy_stage,
+            },
+
+            Packet::TileEntityData_5(p) => Self::TileEntityData {
+                location: p.location.into(),
+                action: p.action,
+                nbt: p.nbt_data.0,
+            },
+
+            Packet::TileEntityData_47(p) => Self::TileEntityData {
+                location: p.location,
+                action: p.action,
+                nbt: p.nbt_data.0,
+            },
+
+            // The action was replaced by the block entity type, which is only used as a hint
+            Packet::TileEntityData_757(p) => Self::TileEntityData {
+                location: p.location,
+                action: p.kind.0 as u8,
+                nbt: p.nbt_data.0,
+            },
+
+            Packet::UpdateSign_5(p) => Self::UpdateSign {
+                location: p.location.into(),
+                lines: [p.text1, p.text2, p.text3, p.text4],
+            },
+
+            Packet::UpdateSign_47(p) => Self::UpdateSign {
+                location: p.location,
+                lines: [p.text1, p.text2, p.text3, p.text4],
+            },
+
+            Packet::WorldEvent_5(p) => Self::WorldEvent {
+                effect_id: p.effect_id,
+                location: p.location.into(),
+                data: p.data,
+                global: p.global,
+            },
+
+            Packet::WorldEvent_47(p) => Self::WorldEvent {
+                effect_id: p.effect_id,
+                location: p.location,
+                data: p.data,
+                global: p.global,
             },
 
             Packet::NamedSoundEffect_5(p) => Self::NamedSoundEffect {
@@ -256,25 +928,407 @@ impl AbstractPacket {
                 pitch: p.pitch,
             },
 
-            Packet::EntityHeadRotation_5 { .. }
-            | Packet::EntityLook_5 { .. }
-            | Packet::EntityMetadata_5 { .. }
-            | Packet::EntityMoveLook_5 { .. }
-            | Packet::EntityVelocity_5 { .. }
-            | Packet::RelEntityMove_5 { .. }
-            | Packet::EntityDestroy_5 { .. }
-            | Packet::EntityTeleport_5 { .. }
-            | Packet::SpawnEntityLiving_5 { .. }
-            | Packet::EntityHeadRotation_47 { .. }
-            | Packet::EntityLook_47 { .. }
-            | Packet::EntityMetadata_47 { .. }
-            | Packet::EntityMoveLook_47 { .. }
-            | Packet::EntityVelocity_47 { .. }
-            | Packet::RelEntityMove_47 { .. }
-            | Packet::EntityDestroy_47 { .. }
-            | Packet::EntityTeleport_47 { .. }
-            | Packet::UpdateAttributes_5 { .. }
-            | Packet::UpdateAttributes_47 { .. } => {
+            Packet::NamedSoundEffect_759(p) => Self::NamedSoundEffect {
+                sound_name: p.sound_name,
+                sound_category: Some(p.sound_category.0),
+                pos: Point3::new(p.x as f64 / 8., p.y as f64 / 8., p.z as f64 / 8.),
+                volume: p.volume,
+                pitch: p.pitch,
+            },
+
+            // * Entities
+            Packet::SpawnEntity_5(p) => Self::SpawnObject {
+                entity_id: p.entity_id.0,
+                uuid: None,
+                kind: p.kind,
+                pos: Point3::new(p.x.0, p.y.0, p.z.0),
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                data: p.object_data.data,
+                velocity: p
+                    .object_data
+                    .velocity
+                    .map_or_else(Vector3::zero, |(x, y, z)| velocity(x, y, z)),
+            },
+            Packet::SpawnEntity_107(p) => Self::SpawnObject {
+                entity_id: p.entity_id.0,
+                uuid: Some(p.object_u_u_i_d),
+                kind: p.kind,
+                pos: Point3::new(p.x, p.y, p.z),
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                data: p.object_data,
+                velocity: velocity(p.velocity_x, p.velocity_y, p.velocity_z),
+            },
+            Packet::SpawnEntity_477(p) => Self::SpawnObject {
+                entity_id: p.entity_id.0,
+                uuid: Some(p.object_u_u_i_d),
+                kind: p.kind.0 as i8,
+                pos: Point3::new(p.x, p.y, p.z),
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                data: p.object_data,
+                velocity: velocity(p.velocity_x, p.velocity_y, p.velocity_z),
+            },
+            Packet::SpawnEntity_759(p) => Self::SpawnObject {
+                entity_id: p.entity_id.0,
+                uuid: Some(p.object_u_u_i_d),
+                kind: p.kind.0 as i8,
+                pos: Point3::new(p.x, p.y, p.z),
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                data: p.object_data.0,
+                velocity: velocity(p.velocity_x, p.velocity_y, p.velocity_z),
+            },
+            Packet::SpawnEntityLiving_5(p) => Self::SpawnMob {
+                entity_id: p.entity_id.0,
+                uuid: None,
+                kind: p.kind as i32,
+                pos: Point3::new(p.x.0, p.y.0, p.z.0),
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                head_pitch: angle(p.head_pitch),
+                velocity: velocity(p.velocity_x, p.velocity_y, p.velocity_z),
+                metadata: p.metadata,
+            },
+            Packet::SpawnEntityLiving_107(p) => Self::SpawnMob {
+                entity_id: p.entity_id.0,
+                uuid: Some(p.entity_u_u_i_d),
+                kind: p.kind as i32,
+                pos: Point3::new(p.x, p.y, p.z),
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                head_pitch: angle(p.head_pitch),
+                velocity: velocity(p.velocity_x, p.velocity_y, p.velocity_z),
+                metadata: p.metadata,
+            },
+            Packet::SpawnEntityLiving_315(p) => Self::SpawnMob {
+                entity_id: p.entity_id.0,
+                uuid: Some(p.entity_u_u_i_d),
+                kind: p.kind.0,
+                pos: Point3::new(p.x, p.y, p.z),
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                head_pitch: angle(p.head_pitch),
+                velocity: velocity(p.velocity_x, p.velocity_y, p.velocity_z),
+                metadata: p.metadata,
+            },
+            // 1.15+ sends the metadata separately
+            Packet::SpawnEntityLiving_573(p) => Self::SpawnMob {
+                entity_id: p.entity_id.0,
+                uuid: Some(p.entity_u_u_i_d),
+                kind: p.kind.0,
+                pos: Point3::new(p.x, p.y, p.z),
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                head_pitch: angle(p.head_pitch),
+                velocity: velocity(p.velocity_x, p.velocity_y, p.velocity_z),
+                metadata: EntityMeta::default(),
+            },
+            Packet::NamedEntitySpawn_5(p) => Self::SpawnPlayer {
+                entity_id: p.entity_id.0,
+                uuid: Uuid::parse_str(&p.player_u_u_i_d).unwrap_or_else(|_| {
+                    warn!(
+                        "Player {} has an invalid UUID {}",
+                        p.player_name, p.player_u_u_i_d
+                    );
+                    Uuid::nil()
+                }),
+                name: Some(p.player_name),
+                pos: Point3::new(p.x.0, p.y.0, p.z.0),
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                current_item: ids
+                    .item_from_wire(&Slot {
+                        item_id: p.current_item,
+                        ..Default::default()
+                    })
+                    .map(|stack| stack.item),
+                metadata: p.metadata,
+            },
+            Packet::NamedEntitySpawn_47(p) => Self::SpawnPlayer {
+                entity_id: p.entity_id.0,
+                uuid: p.player_u_u_i_d,
+                name: None,
+                pos: Point3::new(p.x.0, p.y.0, p.z.0),
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                current_item: ids
+                    .item_from_wire(&Slot {
+                        item_id: p.current_item,
+                        ..Default::default()
+                    })
+                    .map(|stack| stack.item),
+                metadata: p.metadata,
+            },
+            Packet::NamedEntitySpawn_107(p) => Self::SpawnPlayer {
+                entity_id: p.entity_id.0,
+                uuid: p.player_u_u_i_d,
+                name: None,
+                pos: Point3::new(p.x, p.y, p.z),
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                current_item: None,
+                metadata: p.metadata,
+            },
+            Packet::NamedEntitySpawn_573(p) => Self::SpawnPlayer {
+                entity_id: p.entity_id.0,
+                uuid: p.player_u_u_i_d,
+                name: None,
+                pos: Point3::new(p.x, p.y, p.z),
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                current_item: None,
+                metadata: EntityMeta::default(),
+            },
+            Packet::SpawnEntityPainting_5(p) => Self::SpawnPainting {
+                entity_id: p.entity_id.0,
+                uuid: None,
+                title: p.title,
+                location: p.location.into(),
+                direction: p.direction as u8,
+            },
+            Packet::SpawnEntityPainting_47(p) => Self::SpawnPainting {
+                entity_id: p.entity_id.0,
+                uuid: None,
+                title: p.title,
+                location: p.location,
+                direction: p.direction,
+            },
+            Packet::SpawnEntityPainting_107(p) => Self::SpawnPainting {
+                entity_id: p.entity_id.0,
+                uuid: Some(p.entity_u_u_i_d),
+                title: p.title,
+                location: p.location,
+                direction: p.direction,
+            },
+            Packet::SpawnEntityExperienceOrb_5(p) => Self::SpawnExperienceOrb {
+                entity_id: p.entity_id.0,
+                pos: Point3::new(p.x.0, p.y.0, p.z.0),
+                count: p.count,
+            },
+            Packet::SpawnEntityExperienceOrb_107(p) => Self::SpawnExperienceOrb {
+                entity_id: p.entity_id.0,
+                pos: Point3::new(p.x, p.y, p.z),
+                count: p.count,
+            },
+            Packet::SpawnEntityWeather_5(p) => Self::SpawnGlobalEntity {
+                entity_id: p.entity_id.0,
+                kind: p.kind,
+                pos: Point3::new(p.x as f64 / 32., p.y as f64 / 32., p.z as f64 / 32.),
+            },
+            Packet::SpawnEntityWeather_107(p) => Self::SpawnGlobalEntity {
+                entity_id: p.entity_id.0,
+                kind: p.kind,
+                pos: Point3::new(p.x, p.y, p.z),
+            },
+
+            Packet::RelEntityMove_5(p) => Self::EntityMove {
+                entity_id: p.entity_id,
+                delta: Vector3::new(p.d_x.0, p.d_y.0, p.d_z.0),
+                on_ground: None,
+            },
+            Packet::RelEntityMove_47(p) => Self::EntityMove {
+                entity_id: p.entity_id.0,
+                delta: Vector3::new(p.d_x.0, p.d_y.0, p.d_z.0),
+                on_ground: Some(p.on_ground),
+            },
+            Packet::RelEntityMove_107(p) => Self::EntityMove {
+                entity_id: p.entity_id.0,
+                delta: Vector3::new(p.d_x.0, p.d_y.0, p.d_z.0),
+                on_ground: Some(p.on_ground),
+            },
+            Packet::EntityLook_5(p) => Self::EntityLook {
+                entity_id: p.entity_id,
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                on_ground: None,
+            },
+            Packet::EntityLook_47(p) => Self::EntityLook {
+                entity_id: p.entity_id.0,
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                on_ground: Some(p.on_ground),
+            },
+            Packet::EntityMoveLook_5(p) => Self::EntityMoveLook {
+                entity_id: p.entity_id,
+                delta: Vector3::new(p.d_x.0, p.d_y.0, p.d_z.0),
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                on_ground: None,
+            },
+            Packet::EntityMoveLook_47(p) => Self::EntityMoveLook {
+                entity_id: p.entity_id.0,
+                delta: Vector3::new(p.d_x.0, p.d_y.0, p.d_z.0),
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                on_ground: Some(p.on_ground),
+            },
+            Packet::EntityMoveLook_107(p) => Self::EntityMoveLook {
+                entity_id: p.entity_id.0,
+                delta: Vector3::new(p.d_x.0, p.d_y.0, p.d_z.0),
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                on_ground: Some(p.on_ground),
+            },
+            Packet::EntityTeleport_5(p) => Self::EntityTeleport {
+                entity_id: p.entity_id,
+                pos: Point3::new(p.x.0, p.y.0, p.z.0),
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                on_ground: None,
+            },
+            Packet::EntityTeleport_47(p) => Self::EntityTeleport {
+                entity_id: p.entity_id.0,
+                pos: Point3::new(p.x.0, p.y.0, p.z.0),
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                on_ground: Some(p.on_ground),
+            },
+            Packet::EntityTeleport_107(p) => Self::EntityTeleport {
+                entity_id: p.entity_id.0,
+                pos: Point3::new(p.x, p.y, p.z),
+                yaw: angle(p.yaw),
+                pitch: angle(p.pitch),
+                on_ground: Some(p.on_ground),
+            },
+            Packet::EntityVelocity_5(p) => Self::EntityVelocity {
+                entity_id: p.entity_id,
+                velocity: velocity(p.velocity_x, p.velocity_y, p.velocity_z),
+            },
+            Packet::EntityVelocity_47(p) => Self::EntityVelocity {
+                entity_id: p.entity_id.0,
+                velocity: velocity(p.velocity_x, p.velocity_y, p.velocity_z),
+            },
+            Packet::EntityHeadRotation_5(p) => Self::EntityHeadRotation {
+                entity_id: p.entity_id,
+                head_yaw: angle(p.head_yaw),
+            },
+            Packet::EntityHeadRotation_47(p) => Self::EntityHeadRotation {
+                entity_id: p.entity_id.0,
+                head_yaw: angle(p.head_yaw),
+            },
+
+            Packet::EntityMetadata_5(p) => Self::EntityMetadata {
+                entity_id: p.entity_id,
+                metadata: p.metadata,
+            },
+            Packet::EntityMetadata_47(p) => Self::EntityMetadata {
+                entity_id: p.entity_id.0,
+                metadata: p.metadata,
+            },
+            Packet::EntityEquipment_5(p) => Self::EntityEquipment {
+                entity_id: p.entity_id,
+                items: vec![(
+                    EquipmentSlot::from_legacy(p.slot)?,
+                    ids.item_from_wire(&p.item),
+                )],
+            },
+            Packet::EntityEquipment_47(p) => Self::EntityEquipment {
+                entity_id: p.entity_id.0,
+                items: vec![(
+                    EquipmentSlot::from_legacy(p.slot)?,
+                    ids.item_from_wire(&p.item),
+                )],
+            },
+            Packet::EntityEquipment_107(p) => Self::EntityEquipment {
+                entity_id: p.entity_id.0,
+                items: vec![(
+                    EquipmentSlot::from_id(p.slot.0)?,
+                    ids.item_from_wire(&p.item),
+                )],
+            },
+            Packet::EntityEquipment_735(p) => Self::EntityEquipment {
+                entity_id: p.entity_id.0,
+                items: p
+                    .equipment
+                    .0
+                    .iter()
+                    .filter_map(|(slot, item)| {
+                        Some((
+                            EquipmentSlot::from_id(*slot as i32)?,
+                            ids.item_from_wire(item),
+                        ))
+                    })
+                    .collect(),
+            },
+            Packet::EntityDestroy_5(p) => Self::EntityDestroy {
+                entity_ids: p.entity_ids.data,
+            },
+            Packet::EntityDestroy_47(p) => Self::EntityDestroy {
+                entity_ids: p.entity_ids.data.into_iter().map(|id| id.0).collect(),
+            },
+            Packet::AttachEntity_5(p) => Self::AttachEntity {
+                entity_id: p.entity_id,
+                vehicle_id: p.vehicle_id,
+                leash: p.leash,
+            },
+            Packet::AttachEntity_107(p) => Self::AttachEntity {
+                entity_id: p.entity_id,
+                vehicle_id: p.vehicle_id,
+                leash: true,
+            },
+            Packet::SetPassengers_107(p) => Self::SetPassengers {
+                entity_id: p.entity_id.0,
+                passengers: p.passengers.data.into_iter().map(|id| id.0).collect(),
+            },
+
+            Packet::UpdateAttributes_5(p) => Self::EntityAttributes {
+                entity_id: p.entity_id,
+                attributes: p
+                    .properties
+                    .data
+                    .into_iter()
+                    .map(|p| Attribute {
+                        key: p.key,
+                        value: p.value,
+                        modifiers: p.modifiers.data.into_iter().map(Into::into).collect(),
+                    })
+                    .collect(),
+            },
+            Packet::UpdateAttributes_47(p) => Self::EntityAttributes {
+                entity_id: p.entity_id.0,
+                attributes: p
+                    .properties
+                    .data
+                    .into_iter()
+                    .map(|p| Attribute {
+                        key: p.key,
+                        value: p.value,
+                        modifiers: p.modifiers.data.into_iter().map(Into::into).collect(),
+                    })
+                    .collect(),
+            },
+            Packet::EntityUpdateAttributes_107(p) => Self::EntityAttributes {
+                entity_id: p.entity_id.0,
+                attributes: p
+                    .properties
+                    .data
+                    .into_iter()
+                    .map(|p| Attribute {
+                        key: p.key,
+                        value: p.value,
+                        modifiers: p.modifiers.data.into_iter().map(Into::into).collect(),
+                    })
+                    .collect(),
+            },
+
+            Packet::EntityUpdateAttributes_755(p) => Self::EntityAttributes {
+                entity_id: p.entity_id.0,
+                attributes: p
+                    .properties
+                    .data
+                    .into_iter()
+                    .map(|p| Attribute {
+                        key: p.key,
+                        value: p.value,
+                        modifiers: p.modifiers.data.into_iter().map(Into::into).collect(),
+                    })
+                    .collect(),
+            },
+
+            // Only tells the client that the entity still exists
+            Packet::Entity_5 { .. } | Packet::Entity_47 { .. } => {
                 trace!("Packet {packet:?} suppressed");
                 return None;
             }
@@ -290,11 +1344,10 @@ impl AbstractPacket {
     pub fn to_packet(self, protocol: i32) -> Result<Packet> {
         macro_rules! quick_bail {
             () => {
-                anyhow::bail!(
-                    "No mapping for protocol {} for packet {}",
+                return Err(Error::NoMapping {
+                    packet: (&self).into(),
                     protocol,
-                    <&'static str>::from(self)
-                )
+                })
             };
         }
 
@@ -312,9 +1365,96 @@ impl AbstractPacket {
                 next_state: VarInt(next_state.to_i32().unwrap()),
             }),
 
+            // Without a signing key, servers that enforce secure chat will refuse the login
+            AbstractPacket::LoginStart { username } if protocol >= PROTO_1_19_2 => {
+                Packet::LoginStart_760(super::packets::login::serverbound::LoginStart_760 {
+                    username,
+                    has_sig_data: false,
+                    timestamp: None,
+                    public_key: None,
+                    signature: None,
+                    has_player_uuid: false,
+                    player_uuid: None,
+                })
+            }
             AbstractPacket::LoginStart { username } => {
                 Packet::LoginStart_5(super::packets::login::serverbound::LoginStart_5 { username })
             }
+            // Guarded, as the fields can't be moved out before bailing on an unknown protocol
+            AbstractPacket::EncryptionBeginServerbound {
+                shared_secret,
+                verify_token,
+            } if (PROTO_1_7..=PROTO_1_7_6).contains(&protocol) => {
+                Packet::EncryptionBeginServerbound_5(
+                    super::packets::login::serverbound::EncryptionBeginServerbound_5 {
+                        shared_secret: shared_secret.into(),
+                        verify_token: verify_token.into(),
+                    },
+                )
+            }
+            AbstractPacket::EncryptionBeginServerbound {
+                shared_secret,
+                verify_token,
+            } if (PROTO_1_8..PROTO_1_19).contains(&protocol) => {
+                Packet::EncryptionBeginServerbound_47(
+                    super::packets::login::serverbound::EncryptionBeginServerbound_47 {
+                        shared_secret: shared_secret.into(),
+                        verify_token: verify_token.into(),
+                    },
+                )
+            }
+            AbstractPacket::EncryptionBeginServerbound {
+                shared_secret,
+                verify_token,
+            } if (PROTO_1_19..=PROTO_MAX).contains(&protocol) => {
+                Packet::EncryptionBeginServerbound_759(
+                    super::packets::login::serverbound::EncryptionBeginServerbound_759 {
+                        shared_secret: shared_secret.into(),
+                        has_verify_token: true,
+                        verify_token: Some(verify_token.into()),
+                        salt: None,
+                        message_signature: None,
+                    },
+                )
+            }
+            AbstractPacket::LoginPluginResponse { message_id, data }
+                if (PROTO_1_13..=PROTO_MAX).contains(&protocol) =>
+            {
+                Packet::LoginPluginResponse_393(
+                    super::packets::login::serverbound::LoginPluginResponse_393 {
+                        message_id: VarInt(message_id),
+                        successful: data.is_some(),
+                        data: data.unwrap_or_default(),
+                    },
+                )
+            }
+            AbstractPacket::ServerInfoRequest {} => {
+                Packet::PingStart_5(super::packets::status::serverbound::PingStart_5 {})
+            }
+            // The serverbound ping uses the same struct as the clientbound one
+            AbstractPacket::Ping { time } => {
+                Packet::Ping_5(super::packets::status::clientbound::Ping_5 { time })
+            }
+
+            // Serverbound keep-alives share their structs with the clientbound ones
+            AbstractPacket::KeepAlive { keep_alive_id } => match protocol {
+                PROTO_1_7..=PROTO_1_7_6 => {
+                    Packet::KeepAlive_5(super::packets::play::clientbound::KeepAlive_5 {
+                        keep_alive_id: keep_alive_id as i32,
+                    })
+                }
+                PROTO_1_8..=PROTO_1_12_1 => {
+                    Packet::KeepAlive_47(super::packets::play::clientbound::KeepAlive_47 {
+                        keep_alive_id: VarInt(keep_alive_id as i32),
+                    })
+                }
+                PROTO_1_12_2..=PROTO_MAX => {
+                    Packet::KeepAlive_340(super::packets::play::clientbound::KeepAlive_340 {
+                        keep_alive_id,
+                    })
+                }
+                _ => quick_bail!(),
+            },
             AbstractPacket::PositionLookServerBound {
                 pos,
                 yaw,
@@ -362,6 +1502,20 @@ impl AbstractPacket {
                 ),
                 _ => quick_bail!(),
             },
+            AbstractPacket::ChatServerbound(message) if protocol >= PROTO_1_19_2 => {
+                Packet::ChatMessage_760(super::packets::play::serverbound::ChatMessage_760 {
+                    message,
+                    timestamp: std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map_or(0, |d| d.as_millis() as i64),
+                    salt: 0,
+                    signature: vec![].into(),
+                    signed_preview: false,
+                    previous_messages: vec![].into(),
+                    has_last_received: false,
+                    last_received: None,
+                })
+            }
             AbstractPacket::ChatServerbound(m) => {
                 Packet::ChatServerbound_5(super::packets::play::serverbound::ChatServerbound_5 {
                     message: m,
@@ -4,7 +4,10 @@ use cgmath::Point3;
 use tokio::sync::mpsc;
 use wgpu::util::DeviceExt;
 
-use crate::world::ChunkManager;
+use crate::{
+    block::{BlockRegistry, RenderLayer},
+    world::{BlockState, ChunkManager},
+};
 
 use super::chunk::ChunkRenderData;
 
@@ -36,32 +39,22 @@ fn vertex_ao(side1: bool, side2: bool, corner: bool) -> u8 {
     3 - (side1 as u8 + side2 as u8 + corner as u8)
 }
 
-fn is_opaque(bid: u8) -> bool {
-    match bid {
-        0 | 6 | 8 | 9 | 10 | 11 | 18 | 20 | 26 | 27 | 28 | 29 | 30 | 31 | 32 | 33 | 36 | 37
-        | 38 | 39 | 40 | 43 | 44 | 50 | 51 | 52 | 53 | 54 | 55 | 59 | 60 | 61 | 62 | 63 | 64
-        | 65 | 66 | 67 | 68 | 69 | 70 | 71 | 72 | 73 | 74 | 75 | 76 | 77 | 78 | 79 | 81 | 83
-        | 85 | 86 | 89 | 90 | 91 | 92 | 93 | 94 | 95 | 96 | 101 | 102 | 103 | 104 | 105 | 106
-        | 107 | 108 | 109 | 111 | 113 | 114 | 115 | 116 | 117 | 118 | 119 | 120 | 122 | 123
-        | 124 | 125 | 126 | 127 | 128 | 130 | 131 | 132 | 134 | 135 | 136 | 138 | 139 | 141
-        | 142 | 143 | 144 | 145 | 146 | 147 | 148 | 149 | 150 | 151 | 152 | 154 | 156 | 157
-        | 160 | 161 | 163 | 164 | 166 | 167 | 171 | 175 => false,
-        _ => true,
-    }
-}
-
 /// Struct representing the section blocks to be meshed and the blocks around it (used for face culling and AO)
-#[derive(Default)]
 pub struct ChunkSectionContext {
-    blocks: [[[u8; 18]; 18]; 18],
+    blocks: [[[BlockState; 18]; 18]; 18],
     light: [[[u8; 18]; 18]; 18],
+    registry: Arc<BlockRegistry>,
 }
 
 impl ChunkSectionContext {
     pub fn new(cm: &ChunkManager, position: Point3<i32>) -> Self {
         let base = position * 16 - Point3::new(1, 1, 1);
 
-        let mut r = Self::default();
+        let mut r = Self {
+            blocks: Default::default(),
+            light: Default::default(),
+            registry: cm.blocks.clone(),
+        };
 
         // TODO: Can probably be optimized a bit?
         for y in 0..18 {
@@ -80,14 +73,18 @@ impl ChunkSectionContext {
     }
 
     /// Returns minecraft:air for out of bounds coordinates
-    pub fn get_block(&self, x: i32, y: i32, z: i32) -> u8 {
+    pub fn get_block(&self, x: i32, y: i32, z: i32) -> BlockState {
         if x < -1 || x > 17 || y < -1 || y > 17 || z < -1 || z > 17 {
-            return 0;
+            return BlockState::AIR;
         }
 
         self.blocks[1 + y as usize][1 + x as usize][1 + z as usize]
     }
 
+    pub fn is_opaque(&self, x: i32, y: i32, z: i32) -> bool {
+        self.registry.get(self.get_block(x, y, z)).opaque
+    }
+
     pub fn get_block_light(&self, x: i32, y: i32, z: i32) -> (u8, u8) {
         if x < -1 || x > 17 || y < -1 || y > 17 || z < -1 || z > 17 {
             return (0, 0);
@@ -111,7 +108,10 @@ impl ChunkSectionContext {
                 let (tx, ty, tz) = (x + $x, y + $y, z + $z);
 
                 let block = self.get_block(tx, ty, tz);
-                is_opaque(block) || (block == center_block && block != 18)
+                let info = self.registry.get(block);
+                info.opaque
+                    || (block.id() == center_block.id()
+                        && info.render_layer != RenderLayer::CutoutMipped)
             }};
         }
 
@@ -153,9 +153,9 @@ pub fn mesh_chunk(c: &ChunkSectionContext) -> (Vec<ChunkVertex>, Vec<u16>) {
     macro_rules! calculate_ao {
         ($side1:expr, $corner:expr, $side2:expr) => {
             vertex_ao(
-                is_opaque(c.get_block($side1.0, $side1.1, $side1.2)),
-                is_opaque(c.get_block($corner.0, $corner.1, $corner.2)),
-                is_opaque(c.get_block($side2.0, $side2.1, $side2.2)),
+                c.is_opaque($side1.0, $side1.1, $side1.2),
+                c.is_opaque($corner.0, $corner.1, $corner.2),
+                c.is_opaque($side2.0, $side2.1, $side2.2),
             )
         };
     }
@@ -237,8 +237,9 @@ pub fn mesh_chunk(c: &ChunkSectionContext) -> (Vec<ChunkVertex>, Vec<u16>) {
     for x in 0..16 {
         for z in 0..16 {
             for y in 0..16 {
-                let block = c.get_block(x, y, z);
-                if block != 0 {
+                let block = c.registry.get(c.get_block(x, y, z));
+                if block.render_layer != RenderLayer::Invisible {
+                    let textures = block.textures;
                     let (nup, ndown, nleft, nright, nfront, nback) =
                         c.get_neighbors_merged_opaques(x, y, z);
 
@@ -268,7 +269,7 @@ pub fn mesh_chunk(c: &ChunkSectionContext) -> (Vec<ChunkVertex>, Vec<u16>) {
 
                         independent_face!(
                             0,
-                            block,
+                            textures.top,
                             (x + 0, y + 1, z + 0), // Bottom left
                             (x + 0, y + 1, z + 1), // Top left
                             (x + 1, y + 1, z + 1), // Top right
@@ -301,7 +302,7 @@ pub fn mesh_chunk(c: &ChunkSectionContext) -> (Vec<ChunkVertex>, Vec<u16>) {
 
                         independent_face!(
                             1,
-                            block,
+                            textures.bottom,
                             (x + 1, y + 0, z + 0),
                             (x + 1, y + 0, z + 1),
                             (x + 0, y + 0, z + 1),
@@ -334,7 +335,7 @@ pub fn mesh_chunk(c: &ChunkSectionContext) -> (Vec<ChunkVertex>, Vec<u16>) {
 
                         independent_face!(
                             2,
-                            block,
+                            textures.side,
                             (x + 0, y + 0, z + 1), // Bottom left
                             (x + 0, y + 1, z + 1), // Top left
                             (x + 0, y + 1, z + 0), // Top right
@@ -367,7 +368,7 @@ pub fn mesh_chunk(c: &ChunkSectionContext) -> (Vec<ChunkVertex>, Vec<u16>) {
 
                         independent_face!(
                             3,
-                            block,
+                            textures.side,
                             (x + 1, y + 0, z + 0), // Bottom left
                             (x + 1, y + 1, z + 0), // Top left
                             (x + 1, y + 1, z + 1), // Top right
@@ -400,7 +401,7 @@ pub fn mesh_chunk(c: &ChunkSectionContext) -> (Vec<ChunkVertex>, Vec<u16>) {
 
                         independent_face!(
                             4,
-                            block,
+                            textures.side,
                             (x + 0, y + 0, z + 0), // Bottom left
                             (x + 0, y + 1, z + 0), // Top left
                             (x + 1, y + 1, z + 0), // Top right
@@ -433,7 +434,7 @@ pub fn mesh_chunk(c: &ChunkSectionContext) -> (Vec<ChunkVertex>, Vec<u16>) {
 
                         independent_face!(
                             5,
-                            block,
+                            textures.side,
                             (x + 1, y + 0, z + 1), // Bottom left
                             (x + 1, y + 1, z + 1), // Top left
                             (x + 0, y + 1, z + 1), // Top right
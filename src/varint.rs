@@ -2,8 +2,7 @@ use byteorder::{ReadBytesExt, WriteBytesExt};
 use std::fmt::Debug;
 use std::io;
 use std::io::{Read, Write};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 use crate::net::packet_helpers::Serializable;
 
@@ -90,12 +89,8 @@ pub trait WriteProtoExt: Write {
 // Automatically implement WriteProtoExt for everything implementing Write
 impl<W: io::Write + ?Sized> WriteProtoExt for W {}
 
-pub fn varint_len(value: i32) -> usize {
-    (((i32::BITS as usize - value.leading_zeros() as usize) + 6) / 7).max(1)
-}
-
 // * Implementations for AsyncRead/AsyncWrite
-pub async fn read_varint(reader: &mut OwnedReadHalf) -> io::Result<i32> {
+pub async fn read_varint<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<i32> {
     let mut result = 0i32;
     for i in 0..5 {
         let byte = reader.read_u8().await?;
@@ -108,14 +103,14 @@ pub async fn read_varint(reader: &mut OwnedReadHalf) -> io::Result<i32> {
     Ok(result)
 }
 
-pub async fn read_varstring(reader: &mut OwnedReadHalf) -> anyhow::Result<String> {
+pub async fn read_varstring<R: AsyncRead + Unpin>(reader: &mut R) -> anyhow::Result<String> {
     let length = read_varint(reader).await?;
     let mut buf = vec![0u8; length as usize];
     reader.read_exact(&mut buf).await?;
     Ok(String::from_utf8(buf)?)
 }
 
-pub async fn write_varint(writer: &mut OwnedWriteHalf, value: i32) -> io::Result<()> {
+pub async fn write_varint<W: AsyncWrite + Unpin>(writer: &mut W, value: i32) -> io::Result<()> {
     let mut val = value as u32;
     loop {
         let mut temp = (val & 0b1111_1111) as u8;
@@ -132,7 +127,10 @@ pub async fn write_varint(writer: &mut OwnedWriteHalf, value: i32) -> io::Result
     }
 }
 
-pub async fn write_varstring(writer: &mut OwnedWriteHalf, value: &str) -> anyhow::Result<()> {
+pub async fn write_varstring<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    value: &str,
+) -> anyhow::Result<()> {
     write_varint(writer, value.len() as i32).await?;
     writer.write_all(value.as_bytes()).await?;
     Ok(())
@@ -182,6 +180,14 @@ impl Into<isize> for VarInt {
     }
 }
 
+impl TryFrom<isize> for VarInt {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from(value: isize) -> Result<Self, Self::Error> {
+        Ok(Self(value.try_into()?))
+    }
+}
+
 #[derive(Default, Clone, PartialEq)]
 pub struct VarLong(pub i64);
 
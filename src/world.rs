@@ -1,9 +1,27 @@
-use std::io::{Cursor, Read};
+use std::{
+    io::{Cursor, ErrorKind, Read},
+    ops::Range,
+    sync::Arc,
+};
 
-use byteorder::{LittleEndian, ReadBytesExt};
-use fnv::FnvHashMap;
+use anyhow::{ensure, Context};
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
+use flate2::read::ZlibDecoder;
+use fnv::{FnvHashMap, FnvHashSet};
 
-use crate::render::chunk::ChunkRenderData;
+use crate::{
+    block::BlockRegistry,
+    block_entity::BlockEntity,
+    ids::{IdMap, StateName},
+    light,
+    net::{
+        packets::play::clientbound::UpdateLight_735,
+        types::{ChunkBlockEntity, ChunkLight},
+        wrapper::{BlockChangeRecord, ChunkData},
+    },
+    render::chunk::ChunkRenderData,
+    varint::ReadProtoExt,
+};
 
 pub const CHUNK_SECTION_SIZE: usize = 16 * 16 * 16;
 pub const CHUNK_SIZE: usize = CHUNK_SECTION_SIZE * 16;
@@ -16,47 +34,193 @@ macro_rules! chunk_coord {
     };
 }
 
+/// A block id and its metadata, packed the way 1.8-1.12 send them (`id << 4 | meta`)
+///
+/// Versions with a global palette are translated into this layout by [`IdMap`], with new blocks getting ids above 255.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BlockState(pub u16);
+
+impl BlockState {
+    pub const AIR: BlockState = BlockState(0);
+
+    pub const fn new(id: u16, meta: u8) -> Self {
+        Self((id << 4) | (meta & 0x0f) as u16)
+    }
+
+    pub fn id(self) -> u16 {
+        self.0 >> 4
+    }
+
+    pub fn meta(self) -> u8 {
+        (self.0 & 0x0f) as u8
+    }
+
+    pub fn is_air(self) -> bool {
+        self.id() == 0
+    }
+}
+
+/// Information about the world we're in that isn't part of any chunk
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LevelInfo {
+    /// -1: nether, 0: overworld, 1: end
+    pub dimension: i32,
+    pub gamemode: u8,
+    pub difficulty: u8,
+    /// World type, such as `default` or `flat`
+    pub level_kind: String,
+    /// Lowest block y of the dimension, below 0 for 1.18+ worlds
+    pub min_y: i32,
+    /// Lowest block y of each 1.19+ dimension type by name, as sent in JoinGame
+    pub dimension_types: FnvHashMap<String, i32>,
+    pub spawn: (i32, i32, i32),
+    /// Ticks since the world was created
+    pub world_age: i64,
+    pub time_of_day: i64,
+}
+
+/// A block action (note block, piston, chest lid) as sent by the server
+///
+/// The meaning of `action_id` and `action_param` depends on the block.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BlockAction {
+    pub action_id: u8,
+    pub action_param: u8,
+    pub block_id: i32,
+}
+
+/// Break animation of a block being mined by an entity
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BreakProgress {
+    pub entity_id: i32,
+    /// 0-9
+    pub stage: u8,
+}
+
 pub struct ChunkColumn {
-    pub sections: [Option<ChunkSectionData>; 16],
+    /// Indexed by the section's y minus `min_section`, see `get_section` for access by y
+    pub sections: Vec<Option<ChunkSectionData>>,
+    /// Y of the lowest section, below 0 in 1.18+ dimensions that go under y=0
+    pub min_section: i32,
     pub biomes: [u8; CHUNK_SIZE_2D],
+    /// Block entities, keyed by absolute block position
+    pub block_entities: FnvHashMap<(i32, i32, i32), BlockEntity>,
+    /// Last block action received for each block, keyed by absolute block position
+    pub block_actions: FnvHashMap<(i32, i32, i32), BlockAction>,
+    /// Blocks that are being broken, keyed by absolute block position
+    pub break_progress: FnvHashMap<(i32, i32, i32), BreakProgress>,
+    /// Set when the blocks or block entities change, cleared once the world downloader has saved the column
+    pub unsaved: bool,
+    /// Whether the dimension has sky light, this decides if skylight was sent with the column
+    pub skylight: bool,
 }
 
 impl ChunkColumn {
+    /// A column from y=0 to 255, the height of every dimension before 1.18
     pub fn empty() -> Self {
-        const INIT: Option<ChunkSectionData> = None;
+        Self::with_height(0, 16)
+    }
+
+    /// A column of `count` sections, starting with the section at y `min_section`
+    pub fn with_height(min_section: i32, count: usize) -> Self {
         Self {
-            sections: [INIT; 16],
+            sections: (0..count).map(|_| None).collect(),
+            min_section,
             biomes: [0; CHUNK_SIZE_2D],
+            block_entities: FnvHashMap::default(),
+            block_actions: FnvHashMap::default(),
+            break_progress: FnvHashMap::default(),
+            unsaved: true,
+            skylight: false,
         }
     }
 
-    pub fn get_section_mut_or_insert(&mut self, y: u8) -> &mut ChunkSectionData {
-        assert!(y < 16);
+    /// Ys of the sections the column can hold
+    pub fn section_range(&self) -> Range<i32> {
+        self.min_section..self.min_section + self.sections.len() as i32
+    }
 
-        if self.sections[y as usize].is_none() {
-            self.sections[y as usize] = Some(ChunkSectionData::empty());
+    fn section_index(&self, y: i32) -> Option<usize> {
+        self.section_range()
+            .contains(&y)
+            .then(|| (y - self.min_section) as usize)
+    }
+
+    /// Sections that were sent or created, along with their y
+    pub fn iter_sections(&self) -> impl DoubleEndedIterator<Item = (i32, &ChunkSectionData)> {
+        self.section_range()
+            .zip(&self.sections)
+            .filter_map(|(y, s)| Some((y, s.as_ref()?)))
+    }
+
+    /// Removes every section, as a full column only contains the sections that aren't empty
+    pub fn clear_sections(&mut self) {
+        self.sections.iter_mut().for_each(|s| *s = None);
+    }
+
+    /// Panics if `y` is outside of `section_range`
+    pub fn get_section_mut_or_insert(&mut self, y: i32) -> &mut ChunkSectionData {
+        let Some(i) = self.section_index(y) else {
+            panic!("Section {y} is outside of the column");
+        };
+
+        self.sections[i].get_or_insert_with(ChunkSectionData::empty)
+    }
+
+    /// Like `get_section_mut_or_insert`, but new sections get the sky light they implied while missing
+    ///
+    /// Missing sections in between stay consistent by being filled in as well.
+    pub fn get_section_mut_or_insert_lit(&mut self, y: i32) -> &mut ChunkSectionData {
+        let Some(index) = self.section_index(y) else {
+            panic!("Section {y} is outside of the column");
+        };
+
+        if self.sections[index].is_none() && self.implied_skylight(y) == 15 {
+            // Any missing section below this one would otherwise lose its sky
+            for i in (0..index).rev() {
+                if self.sections[i].is_some() {
+                    break;
+                }
+
+                let mut s = ChunkSectionData::empty();
+                s.skylight = [0xff; CHUNK_SECTION_SIZE / 2];
+                self.sections[i] = Some(s);
+            }
+
+            let mut s = ChunkSectionData::empty();
+            s.skylight = [0xff; CHUNK_SECTION_SIZE / 2];
+            self.sections[index] = Some(s);
         }
 
-        self.sections[y as usize].as_mut().unwrap()
+        self.get_section_mut_or_insert(y)
     }
 
-    pub fn get_section_mut(&mut self, y: u8) -> Option<&mut ChunkSectionData> {
-        self.sections[y as usize].as_mut()
+    /// Sky light of the blocks in a section that wasn't sent, which is full sky only if there's no section above it
+    pub fn implied_skylight(&self, y: i32) -> u8 {
+        let above = (y + 1 - self.min_section).clamp(0, self.sections.len() as i32) as usize;
+        if self.skylight && self.sections[above..].iter().all(Option::is_none) {
+            15
+        } else {
+            0
+        }
     }
 
-    pub fn get_section(&self, y: u8) -> Option<&ChunkSectionData> {
-        self.sections[y as usize].as_ref()
+    pub fn get_section_mut(&mut self, y: i32) -> Option<&mut ChunkSectionData> {
+        let i = self.section_index(y)?;
+        self.sections[i].as_mut()
+    }
+
+    pub fn get_section(&self, y: i32) -> Option<&ChunkSectionData> {
+        self.sections[self.section_index(y)?].as_ref()
     }
 }
 
 pub struct ChunkSectionData {
     pub dirty: bool,
     pub renderdata: Option<ChunkRenderData>,
-    pub blocks: [u8; CHUNK_SECTION_SIZE],
-    // pub metadata: NibbleVec<[u8; CHUNK_SIZE]>,
+    pub blocks: [BlockState; CHUNK_SECTION_SIZE],
     pub light: [u8; CHUNK_SECTION_SIZE / 2],
     pub skylight: [u8; CHUNK_SECTION_SIZE / 2],
-    // pub add: NibbleVec<[u8; CHUNK_SIZE]>,
 }
 
 impl ChunkSectionData {
@@ -64,40 +228,26 @@ impl ChunkSectionData {
         ChunkSectionData {
             dirty: true,
             renderdata: None,
-            blocks: [0; CHUNK_SECTION_SIZE],
-            // metadata:(),
+            blocks: [BlockState::AIR; CHUNK_SECTION_SIZE],
             light: [0; CHUNK_SECTION_SIZE / 2],
             skylight: [0; CHUNK_SECTION_SIZE / 2],
-            // add: (),
         }
     }
 
     // TODO: Dedicated position type
 
     /// ! This function does not check if the coordinates are inside of the chunk
-    pub fn get_block(&self, x: i32, y: i32, z: i32) -> u8 {
-        if y < 0 {
-            return 0;
-        }
-
-        return self.blocks[(((y & 0x0f) << 8) | ((z & 0x0f) << 4) | (x & 0x0f)) as usize];
+    pub fn get_block(&self, x: i32, y: i32, z: i32) -> BlockState {
+        self.blocks[block_index(x, y, z)]
     }
 
-    pub fn set_block(&mut self, x: i32, y: i32, z: i32, block: u8) {
-        if y < 0 {
-            return;
-        }
-
-        self.blocks[(((y & 0x0f) << 8) | ((z & 0x0f) << 4) | (x & 0x0f)) as usize] = block;
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, block: BlockState) {
+        self.blocks[block_index(x, y, z)] = block;
         self.dirty = true;
     }
 
     pub fn get_block_light(&self, x: i32, y: i32, z: i32) -> (u8, u8) {
-        if y < 0 {
-            return (0, 0);
-        }
-
-        let index = (((y & 0x0f) << 8) | ((z & 0x0f) << 4) | (x & 0x0f)) as usize;
+        let index = block_index(x, y, z);
         let (light, slight) = (self.light[index / 2], self.skylight[index / 2]);
 
         match index % 2 {
@@ -106,6 +256,14 @@ impl ChunkSectionData {
         }
     }
 
+    pub fn set_block_light(&mut self, x: i32, y: i32, z: i32, light: u8) {
+        set_nibble(&mut self.light, block_index(x, y, z), light);
+    }
+
+    pub fn set_sky_light(&mut self, x: i32, y: i32, z: i32, light: u8) {
+        set_nibble(&mut self.skylight, block_index(x, y, z), light);
+    }
+
     /// Returns (up, down, left, right, front, back)
     pub fn get_neighbors(&self, x: i32, y: i32, z: i32) -> (bool, bool, bool, bool, bool, bool) {
         macro_rules! nb {
@@ -117,7 +275,7 @@ impl ChunkSectionData {
                 } else if tx > 15 || ty > 15 || tz > 15 {
                     false
                 } else {
-                    self.get_block(tx, ty, tz) != 0
+                    !self.get_block(tx, ty, tz).is_air()
                 }
             }};
         }
@@ -134,17 +292,428 @@ impl ChunkSectionData {
     }
 }
 
+/// Index of a block within a section, only the lower 4 bits of each coordinate are used
+fn block_index(x: i32, y: i32, z: i32) -> usize {
+    (((y & 0x0f) << 8) | ((z & 0x0f) << 4) | (x & 0x0f)) as usize
+}
+
+/// Reads a value from a nibble array, where even indices are stored in the low nibble
+fn nibble(data: &[u8], index: usize) -> u8 {
+    match index % 2 {
+        0 => data[index / 2] & 0x0f,
+        _ => (data[index / 2] >> 4) & 0x0f,
+    }
+}
+
+fn set_nibble(data: &mut [u8], index: usize, value: u8) {
+    let b = &mut data[index / 2];
+    *b = match index % 2 {
+        0 => (*b & 0xf0) | (value & 0x0f),
+        _ => (*b & 0x0f) | ((value & 0x0f) << 4),
+    };
+}
+
+/// Adds the section containing a block to `dirty`, along with any neighbouring section that shares a face with the block
+pub(crate) fn affected_sections(
+    dirty: &mut FnvHashSet<(i32, i32, i32)>,
+    bx: i32,
+    by: i32,
+    bz: i32,
+) {
+    let ccoord = chunk_coord!(bx, by, bz);
+    let (rx, ry, rz) = (bx & 0x0f, by & 0x0f, bz & 0x0f);
+    dirty.insert(ccoord);
+    if ry == 0 {
+        dirty.insert((ccoord.0, ccoord.1 - 1, ccoord.2));
+    }
+    if ry == 15 {
+        dirty.insert((ccoord.0, ccoord.1 + 1, ccoord.2));
+    }
+    if rx == 0 {
+        dirty.insert((ccoord.0 - 1, ccoord.1, ccoord.2));
+    }
+    if rx == 15 {
+        dirty.insert((ccoord.0 + 1, ccoord.1, ccoord.2));
+    }
+    if rz == 0 {
+        dirty.insert((ccoord.0, ccoord.1, ccoord.2 - 1));
+    }
+    if rz == 15 {
+        dirty.insert((ccoord.0, ccoord.1, ccoord.2 + 1));
+    }
+}
+
+/// Layout of a paletted container, which changed a few times after 1.9
+struct ContainerFormat {
+    entries: usize,
+    /// Smaller sizes are rounded up to this
+    min_bits: usize,
+    /// Anything larger uses the global palette
+    max_palette_bits: usize,
+    /// Bits per entry of the global palette, `None` if the sent size is used as is
+    global_bits: Option<usize>,
+    /// Whether the palette length is still sent (as 0) for the global palette
+    global_palette_len: bool,
+    /// Whether 0 bits per entry means the palette is a single value for the whole container (1.18+)
+    single_value: bool,
+    /// Whether entries are packed back to back and can span two longs (before 1.16)
+    spanning: bool,
+}
+
+/// Blocks from 1.9 up to 1.12
+const BLOCKS_107: ContainerFormat = ContainerFormat {
+    entries: CHUNK_SECTION_SIZE,
+    min_bits: 4,
+    max_palette_bits: 8,
+    global_bits: Some(13),
+    global_palette_len: true,
+    single_value: false,
+    spanning: true,
+};
+
+/// Blocks in 1.13, where the global palette size depends on the amount of block states
+const BLOCKS_393: ContainerFormat = ContainerFormat {
+    global_bits: None,
+    global_palette_len: false,
+    ..BLOCKS_107
+};
+
+/// Blocks from 1.16, entries no longer span two longs
+const BLOCKS_751: ContainerFormat = ContainerFormat {
+    spanning: false,
+    ..BLOCKS_393
+};
+
+const BLOCKS_757: ContainerFormat = ContainerFormat {
+    single_value: true,
+    ..BLOCKS_751
+};
+
+/// Biomes from 1.18, one per 4x4x4 cell of a section
+const BIOMES_757: ContainerFormat = ContainerFormat {
+    entries: 64,
+    min_bits: 1,
+    max_palette_bits: 3,
+    ..BLOCKS_757
+};
+
+/// Reads a paletted container, returning the palette values of its entries
+fn read_container(cur: &mut Cursor<&[u8]>, format: &ContainerFormat) -> anyhow::Result<Vec<i32>> {
+    let bits = cur.read_u8()? as usize;
+    let (bits, global) = match bits {
+        0 if format.single_value => (0, false),
+        b if b <= format.min_bits => (format.min_bits, false),
+        b if b <= format.max_palette_bits => (b, false),
+        b => (format.global_bits.unwrap_or(b), true),
+    };
+
+    let mut palette = vec![];
+    if !global || format.global_palette_len {
+        let palette_len = if bits == 0 { 1 } else { cur.read_varint()? };
+        ensure!(palette_len >= 0, "Negative palette length {}", palette_len);
+        for _ in 0..palette_len {
+            palette.push(cur.read_varint()?);
+        }
+    }
+
+    let long_count = cur.read_varint()?;
+    let longs_needed = match bits {
+        0 => 0,
+        _ if format.spanning => (format.entries * bits).div_ceil(64),
+        _ => format.entries.div_ceil(64 / bits),
+    };
+    ensure!(
+        long_count as usize >= longs_needed,
+        "Container data too short ({} longs for {} bits per entry)",
+        long_count,
+        bits
+    );
+    let mut longs = Vec::with_capacity(long_count as usize);
+    for _ in 0..long_count {
+        longs.push(cur.read_u64::<BigEndian>()?);
+    }
+
+    if bits == 0 {
+        return Ok(vec![palette[0]; format.entries]);
+    }
+
+    let mask = (1u64 << bits) - 1;
+    (0..format.entries)
+        .map(|i| {
+            let value = if format.spanning {
+                let bit = i * bits;
+                let (start, offset) = (bit / 64, bit % 64);
+                let mut value = longs[start] >> offset;
+                if offset + bits > 64 {
+                    value |= longs[start + 1] << (64 - offset);
+                }
+                value
+            } else {
+                let per_long = 64 / bits;
+                longs[i / per_long] >> ((i % per_long) * bits)
+            };
+            let value = (value & mask) as usize;
+
+            if global {
+                Ok(value as i32)
+            } else {
+                match palette.get(value) {
+                    Some(v) => Ok(*v),
+                    None => {
+                        anyhow::bail!("Palette index {} out of range ({})", value, palette.len())
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+/// Reads the blocks of a paletted section, without any light
+fn read_section_blocks(
+    cur: &mut Cursor<&[u8]>,
+    format: &ContainerFormat,
+    ids: &IdMap,
+) -> anyhow::Result<ChunkSectionData> {
+    let mut s = ChunkSectionData::empty();
+    for (block, state) in s.blocks.iter_mut().zip(read_container(cur, format)?) {
+        *block = ids.block_from_wire(state);
+    }
+
+    Ok(s)
+}
+
+/// Reads a single 1.9-1.13 paletted chunk section
+fn read_paletted_section(
+    cur: &mut Cursor<&[u8]>,
+    skylight: bool,
+    format: &ContainerFormat,
+    ids: &IdMap,
+) -> anyhow::Result<ChunkSectionData> {
+    let mut s = read_section_blocks(cur, format, ids)?;
+    cur.read_exact(&mut s.light)?;
+    if skylight {
+        cur.read_exact(&mut s.skylight)?;
+    }
+
+    Ok(s)
+}
+
+/// Reads all the sections in `bitmask`, along with the offset of the data following them
+fn read_paletted_sections(
+    data: &[u8],
+    bitmask: u16,
+    skylight: bool,
+    format: &ContainerFormat,
+    ids: &IdMap,
+) -> anyhow::Result<(Vec<(i32, ChunkSectionData)>, u64)> {
+    let mut cur = Cursor::new(data);
+    let mut sections = vec![];
+    for i in 0..16 {
+        if bitmask & (1 << i) != 0 {
+            sections.push((i, read_paletted_section(&mut cur, skylight, format, ids)?));
+        }
+    }
+
+    Ok((sections, cur.position()))
+}
+
+/// Converts 1.14-1.16 light, whose masks fit in a single VarInt, to the 1.17+ layout
+fn light_735(p: UpdateLight_735) -> ChunkLight {
+    let sky_count = p.sky_light_mask.0.count_ones() as usize;
+    let mut sky_light = p.light;
+    let block_light = sky_light.split_off(sky_count.min(sky_light.len()));
+    let mask = |m: i32| vec![m as u32 as i64].into();
+
+    ChunkLight {
+        trust_edges: p.trust_edges,
+        sky_light_mask: mask(p.sky_light_mask.0),
+        block_light_mask: mask(p.block_light_mask.0),
+        empty_sky_light_mask: mask(p.empty_sky_light_mask.0),
+        empty_block_light_mask: mask(p.empty_block_light_mask.0),
+        sky_light: sky_light.into(),
+        block_light: block_light.into(),
+    }
+}
+
+/// Copies light arrays from 1.14+ light data into a column
+///
+/// Bit 0 of the masks is the section below the lowest one, `min_section` is the y of the lowest section.
+fn apply_light(chunk: &mut ChunkColumn, light: &ChunkLight, min_section: i32) {
+    let sections = |masks: &[i64]| {
+        masks
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &mask)| {
+                (0..64)
+                    .filter(move |b| mask >> b & 1 != 0)
+                    .map(move |b| (i * 64 + b) as i32 - 1 + min_section)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let sky = sections(&light.sky_light_mask.data);
+    if !sky.is_empty() {
+        chunk.skylight = true;
+    }
+    let range = chunk.section_range();
+    for (y, data) in sky.into_iter().zip(&light.sky_light.data) {
+        if range.contains(&y) && data.data.len() == CHUNK_SECTION_SIZE / 2 {
+            chunk
+                .get_section_mut_or_insert(y)
+                .skylight
+                .copy_from_slice(&data.data);
+        }
+    }
+    for (y, data) in sections(&light.block_light_mask.data)
+        .into_iter()
+        .zip(&light.block_light.data)
+    {
+        if range.contains(&y) && data.data.len() == CHUNK_SECTION_SIZE / 2 {
+            chunk
+                .get_section_mut_or_insert(y)
+                .light
+                .copy_from_slice(&data.data);
+        }
+    }
+
+    for y in sections(&light.empty_sky_light_mask.data) {
+        if let Some(s) = chunk.get_section_mut(y) {
+            s.skylight = [0; CHUNK_SECTION_SIZE / 2];
+        }
+    }
+    for y in sections(&light.empty_block_light_mask.data) {
+        if let Some(s) = chunk.get_section_mut(y) {
+            s.light = [0; CHUNK_SECTION_SIZE / 2];
+        }
+    }
+}
+
+/// Decompresses the zlib chunk data used by 1.7
+fn decompress(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    ZlibDecoder::new(Cursor::new(data))
+        .read_to_end(&mut out)
+        .context("Chunk data failed to decompress")?;
+
+    Ok(out)
+}
+
+/// Single chunk packets before 1.9 only contain skylight in dimensions that have it, so we guess based on the size of the data
+///
+/// `len_without_skylight` is the expected size of the data if no skylight was sent, biomes included.
+fn has_skylight(data_len: usize, len_without_skylight: usize, bitmask: u16) -> bool {
+    data_len >= len_without_skylight + bitmask.count_ones() as usize * CHUNK_SECTION_SIZE / 2
+}
+
+/// Size of the biome array, which is only sent for full columns
+fn biomes_len(ground_up_continuous: bool) -> usize {
+    if ground_up_continuous {
+        CHUNK_SIZE_2D
+    } else {
+        0
+    }
+}
+
+/// The outcome of applying a chunk packet to a [ChunkManager]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ChunkApplyResult {
+    /// Columns that were loaded or updated
+    pub loaded: Vec<(i32, i32)>,
+    /// Columns that were removed
+    pub unloaded: Vec<(i32, i32)>,
+    /// Columns whose data ended early, these may be partially loaded
+    ///
+    /// Nothing after a short column in a chunk batch is loaded.
+    pub short: Vec<(i32, i32)>,
+    /// Amount of bytes left over after the last column
+    pub trailing_bytes: usize,
+}
+
+impl ChunkApplyResult {
+    /// Records the outcome of loading a single column, returning the amount of bytes read or `None` if the data ran short
+    fn record(
+        &mut self,
+        chunks: &ChunkManager,
+        coords: (i32, i32),
+        loaded: anyhow::Result<u64>,
+    ) -> anyhow::Result<Option<u64>> {
+        match loaded {
+            Ok(bytes_read) => {
+                if chunks.chunks.contains_key(&coords) {
+                    self.loaded.push(coords);
+                } else {
+                    self.unloaded.push(coords);
+                }
+
+                Ok(Some(bytes_read))
+            }
+            Err(e)
+                if e.downcast_ref::<std::io::Error>()
+                    .is_some_and(|e| e.kind() == ErrorKind::UnexpectedEof) =>
+            {
+                self.short.push(coords);
+                Ok(None)
+            }
+            Err(e) => Err(e.context(format!("Failed to load chunk {:?}", coords))),
+        }
+    }
+
+    fn record_single(
+        &mut self,
+        chunks: &ChunkManager,
+        coords: (i32, i32),
+        loaded: anyhow::Result<u64>,
+        data_len: usize,
+    ) -> anyhow::Result<()> {
+        if let Some(bytes_read) = self.record(chunks, coords, loaded)? {
+            self.trailing_bytes = data_len - bytes_read as usize;
+        }
+
+        Ok(())
+    }
+}
+
 pub struct ChunkManager {
     pub chunks: FnvHashMap<(i32, i32), ChunkColumn>,
+    /// Block properties for the protocol the chunks were received with
+    pub blocks: Arc<BlockRegistry>,
+    /// Wire ids of the protocol the chunks were received with
+    pub ids: Arc<IdMap>,
+    /// Y of the lowest section sent by 1.18+, from the `min_y` of the dimension
+    min_section: i32,
+    /// 1.14+ light for columns that haven't been loaded yet, the server sends it before the column
+    pending_light: FnvHashMap<(i32, i32), ChunkLight>,
+}
+
+impl Default for ChunkManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ChunkManager {
     pub fn new() -> Self {
         Self {
             chunks: FnvHashMap::default(),
+            blocks: Arc::default(),
+            ids: Arc::default(),
+            min_section: 0,
+            pending_light: FnvHashMap::default(),
         }
     }
 
+    /// Unloads every column and the light waiting for them, for moving to a dimension whose lowest block is at `min_y`
+    pub fn reset(&mut self, min_y: i32) {
+        self.chunks.clear();
+        self.pending_light.clear();
+        self.set_min_y(min_y);
+    }
+
+    /// Sets the lowest block y of the dimension, which is where 1.18+ columns start
+    pub fn set_min_y(&mut self, min_y: i32) {
+        self.min_section = min_y.div_euclid(16);
+    }
+
     // @return the amount of bytes read from the data buffer
     pub fn load_chunk_5(
         &mut self,
@@ -167,6 +736,16 @@ impl ChunkManager {
             self.chunks.insert(coords, ChunkColumn::empty());
             self.chunks.get_mut(&coords).unwrap()
         };
+        chunk.unsaved = true;
+        // Nothing can be inferred from an update without sections
+        if bitmask != 0 {
+            chunk.skylight = skylight;
+        }
+
+        // The server sends the block entities of a full column again after it
+        if ground_up_continuous {
+            chunk.block_entities.clear();
+        }
 
         let mut cur = Cursor::new(data);
         for i in 0..16 {
@@ -177,18 +756,25 @@ impl ChunkManager {
             let mut block_types = vec![0u8; CHUNK_SECTION_SIZE];
             cur.read_exact(&mut block_types)?;
 
-            let s = chunk.get_section_mut_or_insert(i as u8);
+            let s = chunk.get_section_mut_or_insert(i);
             s.dirty = true;
-            s.blocks[..].copy_from_slice(&block_types);
+            for (block, id) in s.blocks.iter_mut().zip(block_types) {
+                *block = BlockState::new(id as u16, 0);
+            }
         }
 
         for i in 0..16 {
             if bitmask & (1 << i) == 0 {
                 continue;
             }
-            // TODO: Nibble array type
+
             let mut block_metadata = vec![0u8; CHUNK_SECTION_SIZE / 2];
             cur.read_exact(&mut block_metadata)?;
+
+            let s = chunk.get_section_mut_or_insert(i);
+            for (index, block) in s.blocks.iter_mut().enumerate() {
+                *block = BlockState::new(block.id(), nibble(&block_metadata, index));
+            }
         }
 
         for i in 0..16 {
@@ -197,7 +783,7 @@ impl ChunkManager {
             }
             // TODO: Nibble array type
 
-            let s = chunk.get_section_mut_or_insert(i as u8);
+            let s = chunk.get_section_mut_or_insert(i);
             s.dirty = true;
             // s.light = NibbleVec::from(block_light);
             cur.read_exact(&mut s.light)?;
@@ -209,7 +795,7 @@ impl ChunkManager {
                     continue;
                 }
                 // TODO: Nibble array type
-                let s = chunk.get_section_mut_or_insert(i as u8);
+                let s = chunk.get_section_mut_or_insert(i);
                 s.dirty = true;
                 // let mut sky_light = vec![0u8; CHUNK_SECTION_SIZE / 2];
                 // cur.read_exact(&mut sky_light)?;
@@ -217,13 +803,22 @@ impl ChunkManager {
             }
         }
 
+        // The add array holds the upper 4 bits of 12-bit block ids, only sent for sections that need it
         for i in 0..16 {
             if bitmask_add & (1 << i) == 0 {
                 continue;
             }
-            // TODO: Nibble array type
+
             let mut block_add = vec![0u8; CHUNK_SECTION_SIZE / 2];
             cur.read_exact(&mut block_add)?;
+
+            // Sections that weren't sent can't have add data, skip them so we don't create empty ones
+            if let Some(s) = chunk.get_section_mut(i) {
+                for (index, block) in s.blocks.iter_mut().enumerate() {
+                    let add = nibble(&block_add, index) as u16;
+                    *block = BlockState::new(block.id() | (add << 8), block.meta());
+                }
+            }
         }
 
         if ground_up_continuous {
@@ -256,6 +851,16 @@ impl ChunkManager {
             self.chunks.insert(coords, ChunkColumn::empty());
             self.chunks.get_mut(&coords).unwrap()
         };
+        chunk.unsaved = true;
+        // Nothing can be inferred from an update without sections
+        if bitmask != 0 {
+            chunk.skylight = skylight;
+        }
+
+        // The server sends the block entities of a full column again after it
+        if ground_up_continuous {
+            chunk.block_entities.clear();
+        }
 
         let mut cur = Cursor::new(data);
         for i in 0..16 {
@@ -263,21 +868,11 @@ impl ChunkManager {
                 continue;
             }
 
-            let mut block_types = vec![0u16; CHUNK_SECTION_SIZE];
-            // cur.read_exact(&mut block_types)?;
-            for i2 in 0..CHUNK_SECTION_SIZE {
-                block_types[i2] = cur.read_u16::<LittleEndian>()?;
-            }
-
-            // FIXME: Truncates 12 bits type to 8
-            let mut block_types_short = vec![0u8; CHUNK_SECTION_SIZE];
-            for i2 in 0..CHUNK_SECTION_SIZE {
-                block_types_short[i2] = (block_types[i2] >> 4) as u8;
-            }
-            let s = chunk.get_section_mut_or_insert(i as u8);
+            let s = chunk.get_section_mut_or_insert(i);
             s.dirty = true;
-            s.blocks[..].copy_from_slice(&block_types_short);
-            // TODO: metadata
+            for block in s.blocks.iter_mut() {
+                *block = BlockState(cur.read_u16::<LittleEndian>()?);
+            }
         }
 
         for i in 0..16 {
@@ -287,7 +882,7 @@ impl ChunkManager {
             // TODO: Nibble array type
             // let mut block_light = vec![0u8; CHUNK_SECTION_SIZE / 2];
 
-            let s = chunk.get_section_mut_or_insert(i as u8);
+            let s = chunk.get_section_mut_or_insert(i);
             s.dirty = true;
             // s.light = NibbleVec::from(block_light);
             cur.read_exact(&mut s.light)?;
@@ -302,7 +897,7 @@ impl ChunkManager {
                 // let mut sky_light = vec![0u8; CHUNK_SECTION_SIZE / 2];
                 // cur.read_exact(&mut sky_light)?;
 
-                let s = chunk.get_section_mut_or_insert(i as u8);
+                let s = chunk.get_section_mut_or_insert(i);
                 s.dirty = true;
                 // s.skylight = NibbleVec::from(sky_light);
                 cur.read_exact(&mut s.skylight)?;
@@ -318,6 +913,425 @@ impl ChunkManager {
         Ok(cur.position())
     }
 
+    /// Loads a 1.9-1.12 chunk column
+    ///
+    /// Whether skylight is sent depends on the dimension, which isn't part of the packet, so it's inferred from the size of the data.
+    // @return the amount of bytes read from the data buffer
+    pub fn load_chunk_107(
+        &mut self,
+        coords: (i32, i32),
+        bitmask: u16,
+        ground_up_continuous: bool,
+        data: &[u8],
+    ) -> anyhow::Result<u64> {
+        let offset = self.load_lit_sections(
+            coords,
+            bitmask,
+            ground_up_continuous,
+            data,
+            &BLOCKS_107,
+            biomes_len(ground_up_continuous),
+        )?;
+
+        let chunk = self.chunks.get_mut(&coords).unwrap();
+        let mut cur = Cursor::new(data);
+        cur.set_position(offset);
+        if ground_up_continuous {
+            cur.read_exact(&mut chunk.biomes)?;
+        }
+
+        Ok(cur.position())
+    }
+
+    /// Loads a 1.13 chunk column, which only differs from 1.12 in the palette and the size of the biomes
+    // @return the amount of bytes read from the data buffer
+    pub fn load_chunk_393(
+        &mut self,
+        coords: (i32, i32),
+        bitmask: u16,
+        ground_up_continuous: bool,
+        data: &[u8],
+    ) -> anyhow::Result<u64> {
+        let offset = self.load_lit_sections(
+            coords,
+            bitmask,
+            ground_up_continuous,
+            data,
+            &BLOCKS_393,
+            biomes_len(ground_up_continuous) * 4,
+        )?;
+
+        let chunk = self.chunks.get_mut(&coords).unwrap();
+        let mut cur = Cursor::new(data);
+        cur.set_position(offset);
+        if ground_up_continuous {
+            for biome in chunk.biomes.iter_mut() {
+                *biome = cur.read_i32::<BigEndian>()? as u8;
+            }
+        }
+
+        Ok(cur.position())
+    }
+
+    /// Loads the sections of a 1.9-1.13 column, which are followed by `biomes_len` bytes of biomes
+    // @return the offset of the biomes
+    fn load_lit_sections(
+        &mut self,
+        coords: (i32, i32),
+        bitmask: u16,
+        ground_up_continuous: bool,
+        data: &[u8],
+        format: &ContainerFormat,
+        biomes_len: usize,
+    ) -> anyhow::Result<u64> {
+        let sections_len = data.len().saturating_sub(biomes_len) as u64;
+        let (sections, offset, skylight) =
+            match read_paletted_sections(data, bitmask, true, format, &self.ids) {
+                Ok((sections, offset)) if offset == sections_len => (sections, offset, true),
+                with_skylight => {
+                    match read_paletted_sections(data, bitmask, false, format, &self.ids) {
+                        Ok((sections, offset)) if offset == sections_len => {
+                            (sections, offset, false)
+                        }
+                        _ => {
+                            let (sections, offset) = with_skylight?;
+                            (sections, offset, true)
+                        }
+                    }
+                }
+            };
+
+        let chunk = self.chunks.entry(coords).or_insert_with(ChunkColumn::empty);
+        chunk.unsaved = true;
+        // Nothing can be inferred from an update without sections
+        if bitmask != 0 {
+            chunk.skylight = skylight;
+        }
+        if ground_up_continuous {
+            // Sections missing from a full column are empty
+            chunk.clear_sections();
+            chunk.block_entities.clear();
+        }
+
+        for (i, s) in sections {
+            *chunk.get_section_mut_or_insert(i) = s;
+        }
+
+        Ok(offset)
+    }
+
+    /// Loads a 1.16 chunk column, its light is sent separately
+    // @return the amount of bytes read from the data buffer
+    pub fn load_chunk_751(
+        &mut self,
+        coords: (i32, i32),
+        bitmask: u16,
+        biomes: Option<&[i32]>,
+        data: &[u8],
+    ) -> anyhow::Result<u64> {
+        let mut cur = Cursor::new(data);
+        let mut sections = vec![];
+        for i in 0..16 {
+            if bitmask & (1 << i) != 0 {
+                // Amount of non-air blocks, which we count ourselves when needed
+                cur.read_i16::<BigEndian>()?;
+                sections.push((i, read_section_blocks(&mut cur, &BLOCKS_751, &self.ids)?));
+            }
+        }
+
+        let chunk = self.chunks.entry(coords).or_insert_with(ChunkColumn::empty);
+        chunk.unsaved = true;
+        if let Some(biomes) = biomes {
+            chunk.clear_sections();
+            chunk.block_entities.clear();
+            // Biomes are 3D now, so use the layer at sea level
+            for (i, biome) in chunk.biomes.iter_mut().enumerate() {
+                let (x, z) = (i % 16, i / 16);
+                *biome = biomes
+                    .get(16 * 16 + (z / 4) * 4 + x / 4)
+                    .copied()
+                    .unwrap_or(0) as u8;
+            }
+        }
+
+        for (i, mut s) in sections {
+            let section = chunk.get_section_mut_or_insert(i);
+            // Light isn't resent for updated sections
+            s.light = section.light;
+            s.skylight = section.skylight;
+            *section = s;
+        }
+
+        if let Some(light) = self.pending_light.remove(&coords) {
+            apply_light(chunk, &light, 0);
+        }
+
+        Ok(cur.position())
+    }
+
+    /// Loads a 1.18+ chunk column, which covers the whole height of the dimension starting at its `min_y`
+    // @return the amount of bytes read from the data buffer
+    pub fn load_chunk_757(
+        &mut self,
+        coords: (i32, i32),
+        data: &[u8],
+        block_entities: Vec<ChunkBlockEntity>,
+        light: &ChunkLight,
+    ) -> anyhow::Result<u64> {
+        let mut cur = Cursor::new(data);
+        let mut sections = vec![];
+        let mut biomes = vec![];
+        while (cur.position() as usize) < data.len() {
+            cur.read_i16::<BigEndian>()?;
+            sections.push(read_section_blocks(&mut cur, &BLOCKS_757, &self.ids)?);
+            biomes.push(read_container(&mut cur, &BIOMES_757)?);
+        }
+
+        let min_section = self.min_section;
+
+        let mut chunk = ChunkColumn::with_height(min_section, sections.len());
+        chunk.sections = sections.into_iter().map(Some).collect();
+
+        // Biomes are 3D registry ids now, so use the layer at sea level
+        if let Some(biomes) = usize::try_from(4 - min_section)
+            .ok()
+            .and_then(|i| biomes.get(i))
+        {
+            for (i, biome) in chunk.biomes.iter_mut().enumerate() {
+                let (x, z) = (i % 16, i / 16);
+                *biome = biomes[(z / 4) * 4 + x / 4] as u8;
+            }
+        }
+
+        for be in block_entities {
+            let Some(mut blob) = be.data.0 else {
+                continue;
+            };
+
+            let x = coords.0 * 16 + (be.packed_xz >> 4) as i32;
+            let z = coords.1 * 16 + (be.packed_xz & 0x0f) as i32;
+            blob.insert("x", x).ok();
+            blob.insert("y", be.y as i32).ok();
+            blob.insert("z", z).ok();
+            chunk
+                .block_entities
+                .insert((x, be.y as i32, z), BlockEntity::new(blob));
+        }
+
+        self.pending_light.remove(&coords);
+        apply_light(&mut chunk, light, min_section);
+        self.chunks.insert(coords, chunk);
+
+        Ok(cur.position())
+    }
+
+    /// Applies a 1.14+ light update, keeping it until the column arrives if it isn't loaded
+    pub fn load_light(&mut self, coords: (i32, i32), light: ChunkLight) -> bool {
+        match self.chunks.get_mut(&coords) {
+            Some(chunk) => {
+                apply_light(chunk, &light, self.min_section);
+                true
+            }
+            None => {
+                self.pending_light.insert(coords, light);
+                false
+            }
+        }
+    }
+
+    /// Stores the block entities sent along with a chunk column
+    pub fn load_block_entities(&mut self, coords: (i32, i32), block_entities: Vec<nbt::Blob>) {
+        let Some(chunk) = self.chunks.get_mut(&coords) else {
+            return;
+        };
+
+        for blob in block_entities {
+            let position = match (blob.get("x"), blob.get("y"), blob.get("z")) {
+                (Some(nbt::Value::Int(x)), Some(nbt::Value::Int(y)), Some(nbt::Value::Int(z))) => {
+                    (*x, *y, *z)
+                }
+                _ => {
+                    warn!("Block entity without a position in chunk {:?}", coords);
+                    continue;
+                }
+            };
+
+            chunk
+                .block_entities
+                .insert(position, BlockEntity::new(blob));
+            chunk.unsaved = true;
+        }
+    }
+
+    pub fn get_block_entity(&self, x: i32, y: i32, z: i32) -> Option<&BlockEntity> {
+        self.get(&(x >> 4, z >> 4))?.block_entities.get(&(x, y, z))
+    }
+
+    /// Replaces the block entity at a position, `None` removes it
+    pub fn set_block_entity(&mut self, x: i32, y: i32, z: i32, nbt: Option<nbt::Blob>) {
+        let Some(chunk) = self.get_mut(&(x >> 4, z >> 4)) else {
+            return;
+        };
+
+        chunk.unsaved = true;
+        match nbt {
+            Some(nbt) => {
+                chunk
+                    .block_entities
+                    .insert((x, y, z), BlockEntity::new(nbt));
+            }
+            None => {
+                chunk.block_entities.remove(&(x, y, z));
+            }
+        }
+    }
+
+    /// Sets the text of a sign, creating its block entity if the server didn't send one
+    pub fn update_sign(&mut self, x: i32, y: i32, z: i32, lines: [String; 4]) {
+        let Some(chunk) = self.get_mut(&(x >> 4, z >> 4)) else {
+            return;
+        };
+
+        chunk.unsaved = true;
+        let sign = chunk.block_entities.entry((x, y, z)).or_insert_with(|| {
+            let mut nbt = nbt::Blob::new();
+            nbt.insert("id", "Sign").ok();
+            nbt.insert("x", x).ok();
+            nbt.insert("y", y).ok();
+            nbt.insert("z", z).ok();
+            BlockEntity::new(nbt)
+        });
+
+        for (key, line) in ["Text1", "Text2", "Text3", "Text4"].into_iter().zip(lines) {
+            sign.nbt.insert(key, line).ok();
+        }
+    }
+
+    /// Applies a chunk packet, loading or unloading the column(s) it contains
+    pub fn apply(&mut self, chunk_data: ChunkData) -> anyhow::Result<ChunkApplyResult> {
+        let mut result = ChunkApplyResult::default();
+        match chunk_data {
+            ChunkData::Bulk_5(p) => {
+                let data = decompress(&p.data)?;
+                let mut data_offset = 0;
+                for cm in p.meta.iter() {
+                    let coords = (cm.chunk_x, cm.chunk_z);
+                    let loaded = self.load_chunk_5(
+                        coords,
+                        cm.primary_bitmap,
+                        cm.add_bitmap,
+                        p.sky_light_sent,
+                        true,
+                        &data[data_offset..],
+                    );
+
+                    // The offset of the next column is unknown once one runs short
+                    match result.record(self, coords, loaded)? {
+                        Some(bytes_read) => data_offset += bytes_read as usize,
+                        None => return Ok(result),
+                    }
+                }
+                result.trailing_bytes = data.len() - data_offset;
+            }
+            ChunkData::Bulk_47(p) => {
+                let mut data_offset = 0;
+                for cm in p.meta.data.iter() {
+                    let coords = (cm.chunk_x, cm.chunk_z);
+                    let loaded = self.load_chunk_47(
+                        coords,
+                        cm.bitmap,
+                        p.sky_light_sent,
+                        true,
+                        &p.data[data_offset..],
+                    );
+
+                    match result.record(self, coords, loaded)? {
+                        Some(bytes_read) => data_offset += bytes_read as usize,
+                        None => return Ok(result),
+                    }
+                }
+                result.trailing_bytes = p.data.len() - data_offset;
+            }
+            ChunkData::Single_5(p) => {
+                let data = decompress(&p.compressed_chunk_data.data)?;
+                // Blocks, metadata and block light, followed by the add arrays
+                let len_without_skylight = p.bit_map.count_ones() as usize * CHUNK_SECTION_SIZE * 2
+                    + p.add_bit_map.count_ones() as usize * CHUNK_SECTION_SIZE / 2
+                    + biomes_len(p.ground_up);
+                let skylight = has_skylight(data.len(), len_without_skylight, p.bit_map);
+                let loaded = self.load_chunk_5(
+                    (p.x, p.z),
+                    p.bit_map,
+                    p.add_bit_map,
+                    skylight,
+                    p.ground_up,
+                    &data,
+                );
+                result.record_single(self, (p.x, p.z), loaded, data.len())?;
+            }
+            ChunkData::Single_47(p) => {
+                let data = &p.chunk_data.data;
+                // 16-bit blocks and block light
+                let len_without_skylight = p.bit_map.count_ones() as usize * CHUNK_SECTION_SIZE * 5
+                    / 2
+                    + biomes_len(p.ground_up);
+                let skylight = has_skylight(data.len(), len_without_skylight, p.bit_map);
+                let loaded = self.load_chunk_47((p.x, p.z), p.bit_map, skylight, p.ground_up, data);
+                result.record_single(self, (p.x, p.z), loaded, data.len())?;
+            }
+            ChunkData::Single_107(p) => {
+                let data = &p.chunk_data.data;
+                let loaded = self.load_chunk_107((p.x, p.z), p.bit_map.0 as u16, p.ground_up, data);
+                result.record_single(self, (p.x, p.z), loaded, data.len())?;
+            }
+            ChunkData::Single_110(p) => {
+                let data = &p.chunk_data.data;
+                let loaded = self.load_chunk_107((p.x, p.z), p.bit_map.0 as u16, p.ground_up, data);
+                result.record_single(self, (p.x, p.z), loaded, data.len())?;
+                self.load_block_entities((p.x, p.z), p.block_entities.data);
+            }
+            ChunkData::Single_393(p) => {
+                let data = &p.chunk_data.data;
+                let loaded = self.load_chunk_393((p.x, p.z), p.bit_map.0 as u16, p.ground_up, data);
+                result.record_single(self, (p.x, p.z), loaded, data.len())?;
+                self.load_block_entities((p.x, p.z), p.block_entities.data);
+            }
+            ChunkData::Single_751(p) => {
+                let data = &p.chunk_data.data;
+                let biomes: Option<Vec<i32>> =
+                    p.biomes.map(|b| b.data.into_iter().map(|b| b.0).collect());
+                let loaded =
+                    self.load_chunk_751((p.x, p.z), p.bit_map.0 as u16, biomes.as_deref(), data);
+                result.record_single(self, (p.x, p.z), loaded, data.len())?;
+                self.load_block_entities((p.x, p.z), p.block_entities.data);
+            }
+            ChunkData::Single_757(p) => {
+                let data = &p.chunk_data.data;
+                let loaded = self.load_chunk_757((p.x, p.z), data, p.block_entities.data, &p.light);
+                result.record_single(self, (p.x, p.z), loaded, data.len())?;
+            }
+            ChunkData::Light_735(p) => {
+                let coords = (p.chunk_x.0, p.chunk_z.0);
+                if self.load_light(coords, light_735(p)) {
+                    result.loaded.push(coords);
+                }
+            }
+            ChunkData::Light_755(p) => {
+                let coords = (p.chunk_x.0, p.chunk_z.0);
+                if self.load_light(coords, p.light) {
+                    result.loaded.push(coords);
+                }
+            }
+            ChunkData::Unload_107(p) => {
+                self.pending_light.remove(&(p.chunk_x, p.chunk_z));
+                self.chunks.remove(&(p.chunk_x, p.chunk_z));
+                result.unloaded.push((p.chunk_x, p.chunk_z));
+            }
+        }
+
+        Ok(result)
+    }
+
     pub fn get(&self, coords: &(i32, i32)) -> Option<&ChunkColumn> {
         self.chunks.get(coords)
     }
@@ -326,81 +1340,144 @@ impl ChunkManager {
         self.chunks.get_mut(coords)
     }
 
-    pub fn get_block(&self, bx: i32, by: i32, bz: i32) -> u8 {
+    pub fn get_block(&self, bx: i32, by: i32, bz: i32) -> BlockState {
         let ccoord = chunk_coord!(bx, by, bz);
         if let Some(chunk) = self.get(&(ccoord.0, ccoord.2)) {
-            if let Some(Some(section)) = chunk.sections.get(ccoord.1 as usize) {
+            if let Some(section) = chunk.get_section(ccoord.1) {
                 section.get_block(bx, by, bz)
             } else {
-                0
+                BlockState::AIR
             }
         } else {
-            0
+            BlockState::AIR
         }
     }
 
+    /// Flattened name of the block, the same for every protocol
+    pub fn get_block_name(&self, bx: i32, by: i32, bz: i32) -> Option<&StateName> {
+        self.ids.block_name(self.get_block(bx, by, bz))
+    }
+
+    /// Returns (block light, sky light), blocks in sections that weren't sent (including above the world) have the sky light those imply
     pub fn get_block_light(&self, x: i32, y: i32, z: i32) -> (u8, u8) {
         let ccoord = chunk_coord!(x, y, z);
-        if let Some(chunk) = self.get(&(ccoord.0, ccoord.2)) {
-            if let Some(Some(section)) = chunk.sections.get(ccoord.1 as usize) {
-                section.get_block_light(x, y, z)
-            } else {
-                (0, 0)
-            }
-        } else {
-            (0, 0)
+        let Some(chunk) = self.get(&(ccoord.0, ccoord.2)) else {
+            return (0, 0);
+        };
+
+        match chunk.get_section(ccoord.1) {
+            Some(section) => section.get_block_light(x, y, z),
+            None if ccoord.1 < chunk.min_section => (0, 0),
+            None => (0, chunk.implied_skylight(ccoord.1)),
         }
     }
 
-    pub fn set_block(&mut self, bx: i32, by: i32, bz: i32, block: u8) {
-        let ccoord = chunk_coord!(bx, by, bz);
-        let (rx, ry, rz) = (bx % 16, by % 16, bz % 16);
-        if let Some(chunk) = self.get_mut(&(ccoord.0, ccoord.2)) {
-            if let Some(section) = chunk.get_section_mut(ccoord.1 as u8) {
-                section.set_block(bx, by, bz, block);
-            }
+    pub fn set_block(&mut self, bx: i32, by: i32, bz: i32, block: BlockState) {
+        self.set_blocks([(bx, by, bz, block)]);
+    }
 
-            if ry == 0 {
-                chunk
-                    .get_section_mut((ccoord.1 - 1) as u8)
-                    .map(|c| c.dirty = true);
+    /// Applies all changes in a `MultiBlockChange`
+    pub fn apply_multi_block_change(
+        &mut self,
+        chunk_x: i32,
+        chunk_z: i32,
+        records: &[BlockChangeRecord],
+    ) {
+        self.set_blocks(records.iter().map(|r| {
+            (
+                chunk_x * 16 + r.x as i32,
+                r.y,
+                chunk_z * 16 + r.z as i32,
+                r.state,
+            )
+        }));
+    }
+
+    /// Sets a batch of blocks and relights around them, marking every affected section (and the neighbours sharing a face with a changed block) dirty only once all changes have been made
+    pub fn set_blocks(&mut self, blocks: impl IntoIterator<Item = (i32, i32, i32, BlockState)>) {
+        let mut dirty = FnvHashSet::default();
+        let mut changed = vec![];
+        for (bx, by, bz, block) in blocks {
+            let ccoord = chunk_coord!(bx, by, bz);
+            let Some(chunk) = self.get_mut(&(ccoord.0, ccoord.2)) else {
+                continue;
+            };
+
+            // Servers don't send sections that are all air, so placing a block may need a new one
+            if !block.is_air() && chunk.section_range().contains(&ccoord.1) {
+                chunk.get_section_mut_or_insert_lit(ccoord.1);
             }
 
-            if ry == 15 {
-                chunk
-                    .get_section_mut((ccoord.1 + 1) as u8)
-                    .map(|c| c.dirty = true);
+            let Some(section) = chunk.get_section_mut(ccoord.1) else {
+                continue;
+            };
+            let previous = std::mem::replace(&mut section.blocks[block_index(bx, by, bz)], block);
+            chunk.unsaved = true;
+            // Whatever was being broken is gone now
+            chunk.break_progress.remove(&(bx, by, bz));
+            // Only the metadata changing (such as a chest being rotated) keeps the block entity
+            if previous.id() != block.id() {
+                chunk.block_entities.remove(&(bx, by, bz));
             }
+
+            affected_sections(&mut dirty, bx, by, bz);
+            changed.push((bx, by, bz));
         }
 
-        if rx == 0 {
-            self.get_mut(&(ccoord.0 - 1, ccoord.2))
-                .map(|c| c.get_section_mut(ccoord.1 as u8).map(|c| c.dirty = true));
+        light::relight(self, &changed, &mut dirty);
+
+        for (cx, cy, cz) in dirty {
+            if let Some(section) = self.get_mut(&(cx, cz)).and_then(|c| c.get_section_mut(cy)) {
+                section.dirty = true;
+            }
         }
+    }
 
-        if rx == 15 {
-            self.get_mut(&(ccoord.0 + 1, ccoord.2))
-                .map(|c| c.get_section_mut(ccoord.1 as u8).map(|c| c.dirty = true));
+    /// Stores the last block action received for a block
+    pub fn set_block_action(&mut self, x: i32, y: i32, z: i32, action: BlockAction) {
+        if let Some(chunk) = self.get_mut(&(x >> 4, z >> 4)) {
+            chunk.block_actions.insert((x, y, z), action);
         }
+    }
 
-        if rz == 0 {
-            self.get_mut(&(ccoord.0, ccoord.2 - 1))
-                .map(|c| c.get_section_mut(ccoord.1 as u8).map(|c| c.dirty = true));
+    pub fn get_block_action(&self, x: i32, y: i32, z: i32) -> Option<&BlockAction> {
+        self.get(&(x >> 4, z >> 4))?.block_actions.get(&(x, y, z))
+    }
+
+    /// Updates the block being broken by an entity, stages outside of 0-9 stop the animation
+    pub fn set_break_progress(&mut self, entity_id: i32, x: i32, y: i32, z: i32, stage: i8) {
+        // Entities only break one block at a time
+        for chunk in self.chunks.values_mut() {
+            chunk.break_progress.retain(|_, p| p.entity_id != entity_id);
         }
 
-        if rz == 15 {
-            self.get_mut(&(ccoord.0, ccoord.2 + 1))
-                .map(|c| c.get_section_mut(ccoord.1 as u8).map(|c| c.dirty = true));
+        if (0..10).contains(&stage) {
+            if let Some(chunk) = self.get_mut(&(x >> 4, z >> 4)) {
+                chunk.break_progress.insert(
+                    (x, y, z),
+                    BreakProgress {
+                        entity_id,
+                        stage: stage as u8,
+                    },
+                );
+            }
         }
     }
 
+    pub fn get_break_progress(&self, x: i32, y: i32, z: i32) -> Option<BreakProgress> {
+        self.get(&(x >> 4, z >> 4))?
+            .break_progress
+            .get(&(x, y, z))
+            .copied()
+    }
+
     /// Returns (up, down, left, right, front, back)
     pub fn get_neighbors(&self, x: i32, y: i32, z: i32) -> (bool, bool, bool, bool, bool, bool) {
         macro_rules! nb {
             ($x:expr, $y:expr, $z:expr) => {{
                 let (tx, ty, tz) = (x + $x, y + $y, z + $z);
 
-                self.get_block(tx, ty, tz) != 0
+                !self.get_block(tx, ty, tz).is_air()
             }};
         }
 
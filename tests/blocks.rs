@@ -0,0 +1,69 @@
+use minecrab::{
+    block::{BlockRegistry, CollisionShape},
+    ids::IdMap,
+    net::ProtocolVersion,
+    world::BlockState,
+};
+
+#[test]
+fn new_blocks_for_each_protocol() {
+    for protocol in [
+        ProtocolVersion::Proto1_13_2,
+        ProtocolVersion::Proto1_16_5,
+        ProtocolVersion::Proto1_19_2,
+    ] {
+        let ids = IdMap::for_protocol(protocol).unwrap();
+        let blocks = BlockRegistry::for_ids(&ids);
+        for (state, name) in ids.new_block_states() {
+            assert_eq!(blocks.get(state).name, name.name, "{protocol:?}");
+        }
+    }
+}
+
+#[test]
+fn new_blocks_1_19_2() {
+    let ids = IdMap::for_protocol(ProtocolVersion::Proto1_19_2).unwrap();
+    let blocks = BlockRegistry::for_ids(&ids);
+    let block = |name: &str| blocks.get(ids.block_state(&name.parse().unwrap()).unwrap());
+
+    // Cave air is plain air, and doesn't take its wire id
+    let cave_air = ids.block_from_wire(10547);
+    assert!(cave_air.is_air());
+    assert_eq!(ids.block_to_wire(BlockState::AIR), Some(0));
+    assert_eq!(
+        ids.block_state(&"minecraft:void_air".parse().unwrap()),
+        Some(BlockState::AIR)
+    );
+
+    let deepslate = blocks.get(ids.block_from_wire(19779));
+    assert_eq!(deepslate.name, "minecraft:deepslate");
+    assert_eq!(deepslate.collision, CollisionShape::Full);
+    assert!(deepslate.opaque);
+
+    let kelp = block("minecraft:kelp_plant");
+    assert_eq!(kelp.collision, CollisionShape::None);
+    assert_eq!(kelp.light_filter, 0);
+
+    assert_eq!(
+        block("minecraft:andesite_slab[type=bottom,waterlogged=false]").collision,
+        CollisionShape::Box([0., 0., 0., 1., 0.5, 1.])
+    );
+    assert_eq!(
+        block("minecraft:andesite_slab[type=top,waterlogged=false]").collision,
+        CollisionShape::Box([0., 0.5, 0., 1., 1., 1.])
+    );
+    assert!(block("minecraft:andesite_slab[type=double,waterlogged=false]").opaque);
+
+    assert_eq!(
+        block("minecraft:oak_sign[rotation=0,waterlogged=false]").collision,
+        CollisionShape::None
+    );
+    assert_eq!(
+        block("minecraft:soul_wall_torch[facing=north]").light_emission,
+        14
+    );
+    assert_eq!(
+        block("minecraft:candle[candles=1,lit=false,waterlogged=false]").light_emission,
+        0
+    );
+}
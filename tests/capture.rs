@@ -0,0 +1,174 @@
+use minecrab::net::{
+    capture::CaptureReader,
+    connection::{ClientConnection, DisconnectReason},
+    wrapper::AbstractPacket,
+    ConnectionState, ProtocolVersion,
+};
+
+// Short sessions in the capture format, one per protocol era: the login, a keep-alive (and its answer),
+// the time, spawn position, position and a chat message, followed by the server kicking us
+const CAPTURE_1_7_6: &[u8] = include_bytes!("fixtures/capture_5.bin");
+const CAPTURE_1_8: &[u8] = include_bytes!("fixtures/capture_47.bin");
+const CAPTURE_1_12_2: &[u8] = include_bytes!("fixtures/capture_340.bin");
+const CAPTURE_1_13_2: &[u8] = include_bytes!("fixtures/capture_404.bin");
+const CAPTURE_1_16_5: &[u8] = include_bytes!("fixtures/capture_754.bin");
+const CAPTURE_1_19_2: &[u8] = include_bytes!("fixtures/capture_760.bin");
+
+const UUID: &str = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
+
+/// Replays a capture, returns the clientbound packets and the reason the connection was closed
+async fn replay(
+    data: &'static [u8],
+    protocol: ProtocolVersion,
+) -> (Vec<AbstractPacket>, DisconnectReason) {
+    let capture = CaptureReader::new(data).unwrap();
+    assert_eq!(capture.protocol, protocol);
+
+    let mut connection = ClientConnection::from_capture(capture, false);
+    let mut packets = vec![];
+    let reason = loop {
+        match connection.recv().await {
+            Ok(p) => packets.push(p),
+            Err(reason) => break reason,
+        }
+    };
+
+    assert_eq!(connection.state(), ConnectionState::Play);
+    (packets, reason)
+}
+
+async fn check_session(data: &'static [u8], protocol: ProtocolVersion) {
+    let (packets, reason) = replay(data, protocol).await;
+
+    // Compression was added in 1.8, teleport ids in 1.9
+    let has_compression = protocol != ProtocolVersion::Proto1_7_6;
+    let teleport_id = match protocol {
+        ProtocolVersion::Proto1_7_6 | ProtocolVersion::Proto1_8 => None,
+        _ => Some(1),
+    };
+
+    let names: Vec<&str> = packets.iter().map(|p| p.into()).collect();
+    let mut expected = vec![
+        "LoginSuccess",
+        "KeepAlive",
+        "UpdateTime",
+        "SpawnPosition",
+        "PositionLookClientBound",
+        "ChatMessage",
+    ];
+    if has_compression {
+        expected.insert(0, "SetCompression");
+    }
+    assert_eq!(names, expected);
+
+    let mut packets = packets.into_iter();
+    if has_compression {
+        let Some(AbstractPacket::SetCompression { threshold }) = packets.next() else {
+            unreachable!()
+        };
+        assert_eq!(threshold.0, 256);
+    }
+
+    let Some(AbstractPacket::LoginSuccess { uuid, username }) = packets.next() else {
+        unreachable!()
+    };
+    assert_eq!(uuid, UUID);
+    assert_eq!(username, "Steve");
+
+    let Some(AbstractPacket::KeepAlive { keep_alive_id }) = packets.next() else {
+        unreachable!()
+    };
+    assert_eq!(keep_alive_id, 42);
+
+    let Some(AbstractPacket::UpdateTime {
+        world_age,
+        time_of_day,
+    }) = packets.next()
+    else {
+        unreachable!()
+    };
+    assert_eq!((world_age, time_of_day), (1000, 6000));
+
+    let Some(AbstractPacket::SpawnPosition { location }) = packets.next() else {
+        unreachable!()
+    };
+    assert_eq!((location.x, location.y, location.z), (8, 64, -8));
+
+    let Some(AbstractPacket::PositionLookClientBound {
+        pos,
+        yaw,
+        pitch,
+        teleport_id: id,
+        ..
+    }) = packets.next()
+    else {
+        unreachable!()
+    };
+    assert_eq!((pos.x, pos.y, pos.z), (8.5, 65.0, -7.5));
+    assert_eq!((yaw, pitch), (90.0, 0.0));
+    assert_eq!(id, teleport_id);
+
+    let Some(AbstractPacket::ChatMessage { message, overlay }) = packets.next() else {
+        unreachable!()
+    };
+    assert_eq!(message.to_string(), "Hello");
+    assert!(!overlay);
+
+    match reason {
+        DisconnectReason::Kicked(reason) => assert_eq!(reason.to_string(), "Server closed"),
+        reason => panic!("Expected to be kicked, got {reason}"),
+    }
+}
+
+#[tokio::test]
+async fn replay_1_7_6() {
+    check_session(CAPTURE_1_7_6, ProtocolVersion::Proto1_7_6).await;
+}
+
+#[tokio::test]
+async fn replay_1_8() {
+    check_session(CAPTURE_1_8, ProtocolVersion::Proto1_8).await;
+}
+
+#[tokio::test]
+async fn replay_1_12_2() {
+    check_session(CAPTURE_1_12_2, ProtocolVersion::Proto1_12_2).await;
+}
+
+#[tokio::test]
+async fn replay_1_13_2() {
+    check_session(CAPTURE_1_13_2, ProtocolVersion::Proto1_13_2).await;
+}
+
+#[tokio::test]
+async fn replay_1_16_5() {
+    check_session(CAPTURE_1_16_5, ProtocolVersion::Proto1_16_5).await;
+}
+
+#[tokio::test]
+async fn replay_1_19_2() {
+    check_session(CAPTURE_1_19_2, ProtocolVersion::Proto1_19_2).await;
+}
+
+#[test]
+fn capture_records_both_directions() {
+    // The serverbound keep-alive answers are recorded too, but not replayed
+    let capture = CaptureReader::new(CAPTURE_1_12_2).unwrap();
+    let packets: Vec<_> = capture.map(|p| p.unwrap()).collect();
+    let serverbound: Vec<_> = packets
+        .iter()
+        .filter(|p| p.direction == minecrab::net::PacketDirection::Server)
+        .map(|p| (p.state, p.packet.id))
+        .collect();
+    assert_eq!(
+        serverbound,
+        [
+            (ConnectionState::Handshaking, 0x00),
+            (ConnectionState::Login, 0x00),
+            (ConnectionState::Play, 0x0b),
+        ]
+    );
+
+    // Timestamps only ever go forward
+    assert!(packets.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+}
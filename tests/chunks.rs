@@ -0,0 +1,493 @@
+use std::sync::Arc;
+
+use minecrab::{
+    block_entity::{BlockEntityKind, BlockEntityView},
+    ids::{IdMap, StateName},
+    net::{
+        packet_helpers::Serializable,
+        packets::play::clientbound::{
+            MapChunkBulk_47, MapChunk_107, MapChunk_47, MapChunk_5, MapChunk_751, MapChunk_757,
+            MultiBlockChange_751, UnloadChunk_107, UpdateLight_735,
+        },
+        packets::Packet,
+        types::{ChunkBlockEntity, ChunkLight, OptionalNbt},
+        wrapper::{AbstractPacket, ChunkData},
+        ProtocolVersion,
+    },
+    varint::{VarInt, VarLong},
+    world::{BlockState, ChunkApplyResult, ChunkManager},
+};
+
+const FLAT_1_12_2: &[u8] = include_bytes!("fixtures/map_chunk_340_flat.bin");
+const NETHER_1_9: &[u8] = include_bytes!("fixtures/map_chunk_107_nether.bin");
+const BULK_1_8: &[u8] = include_bytes!("fixtures/map_chunk_bulk_47_overworld.bin");
+const OVERWORLD_1_8: &[u8] = include_bytes!("fixtures/map_chunk_47_overworld.bin");
+const NETHER_1_7: &[u8] = include_bytes!("fixtures/map_chunk_5_nether.bin");
+
+fn read<T: Serializable>(data: &[u8], version: i32) -> T {
+    T::read_from_versioned(&mut &data[..], version).unwrap()
+}
+
+#[test]
+fn bulk_columns() {
+    let mut chunks = ChunkManager::new();
+    let result = chunks
+        .apply(ChunkData::Bulk_47(read(BULK_1_8, 47)))
+        .unwrap();
+    assert_eq!(
+        result,
+        ChunkApplyResult {
+            loaded: vec![(0, 0), (1, 0)],
+            ..Default::default()
+        }
+    );
+
+    assert_eq!(chunks.get_block(3, 15, 3), BlockState::new(1, 0));
+    assert_eq!(chunks.get_block(16, 0, 0), BlockState::new(7, 0));
+    assert_eq!(chunks.get_block(31, 31, 15), BlockState::new(24, 2));
+    assert_eq!(chunks.get_block_light(31, 31, 15), (3, 15));
+    assert!(chunks.get(&(1, 0)).unwrap().biomes.iter().all(|&b| b == 4));
+}
+
+#[test]
+fn short_bulk_stops_loading() {
+    let mut p: MapChunkBulk_47 = read(BULK_1_8, 47);
+    p.data.truncate(p.data.len() - 300);
+
+    let mut chunks = ChunkManager::new();
+    let result = chunks.apply(ChunkData::Bulk_47(p)).unwrap();
+    assert_eq!(result.loaded, [(0, 0)]);
+    assert_eq!(result.short, [(1, 0)]);
+    assert_eq!(result.trailing_bytes, 0);
+}
+
+#[test]
+fn single_column_skylight_is_inferred() {
+    let mut chunks = ChunkManager::new();
+
+    let result = chunks
+        .apply(ChunkData::Single_47(read(OVERWORLD_1_8, 47)))
+        .unwrap();
+    assert_eq!(result.loaded, [(5, 5)]);
+    assert_eq!(result.trailing_bytes, 0);
+    assert_eq!(chunks.get_block(80, 64, 80), BlockState::new(2, 0));
+    assert_eq!(chunks.get_block_light(80, 64, 80), (0, 7));
+    assert!(chunks.get(&(5, 5)).unwrap().biomes.iter().all(|&b| b == 2));
+
+    let result = chunks
+        .apply(ChunkData::Single_5(read(NETHER_1_7, 4)))
+        .unwrap();
+    assert_eq!(result.loaded, [(-1, -1)]);
+    assert_eq!(result.trailing_bytes, 0);
+    assert_eq!(chunks.get_block(-16, 0, -16), BlockState::new(87, 1));
+    assert_eq!(chunks.get_block(-15, 0, -16), BlockState::new(0x157, 1));
+    assert_eq!(chunks.get_block_light(-15, 0, -16), (0, 0));
+    assert!(chunks
+        .get(&(-1, -1))
+        .unwrap()
+        .biomes
+        .iter()
+        .all(|&b| b == 8));
+}
+
+#[test]
+fn trailing_data() {
+    let mut p: MapChunk_47 = read(OVERWORLD_1_8, 47);
+    p.ground_up = false;
+
+    let mut chunks = ChunkManager::new();
+    let result = chunks.apply(ChunkData::Single_47(p)).unwrap();
+    assert_eq!(result.loaded, [(5, 5)]);
+    assert_eq!(result.trailing_bytes, 256);
+}
+
+#[test]
+fn unloading() {
+    let mut chunks = ChunkManager::new();
+    chunks
+        .apply(ChunkData::Bulk_47(read(BULK_1_8, 47)))
+        .unwrap();
+
+    // Empty full columns unload before 1.9
+    let p = MapChunk_47 {
+        x: 0,
+        z: 0,
+        ground_up: true,
+        ..Default::default()
+    };
+    let result = chunks.apply(ChunkData::Single_47(p)).unwrap();
+    assert_eq!(result.unloaded, [(0, 0)]);
+    assert!(chunks.get(&(0, 0)).is_none());
+
+    let p = UnloadChunk_107 {
+        chunk_x: 1,
+        chunk_z: 0,
+    };
+    let result = chunks.apply(ChunkData::Unload_107(p)).unwrap();
+    assert_eq!(result.unloaded, [(1, 0)]);
+    assert!(chunks.chunks.is_empty());
+}
+
+#[test]
+fn corrupt_compressed_data() {
+    let mut p: MapChunk_5 = read(NETHER_1_7, 4);
+    p.compressed_chunk_data.data[0] ^= 0xff;
+
+    let mut chunks = ChunkManager::new();
+    assert!(chunks.apply(ChunkData::Single_5(p)).is_err());
+    assert!(chunks.chunks.is_empty());
+}
+
+#[test]
+fn paletted_overworld_column() {
+    let mut chunks = ChunkManager::new();
+    let result = chunks
+        .apply(ChunkData::Single_110(read(FLAT_1_12_2, 340)))
+        .unwrap();
+    assert_eq!(result.loaded, [(2, -1)]);
+    assert_eq!(result.trailing_bytes, 0);
+
+    let column = chunks.get(&(2, -1)).unwrap();
+    assert!(column.sections[1..].iter().all(Option::is_none));
+    assert!(column.biomes.iter().all(|&b| b == 1));
+
+    assert_eq!(chunks.get_block(32, 0, -16), BlockState::new(7, 0));
+    assert_eq!(chunks.get_block(40, 2, -5), BlockState::new(3, 0));
+    assert_eq!(chunks.get_block(47, 3, -1), BlockState::new(2, 0));
+    assert_eq!(chunks.get_block(35, 4, -11), BlockState::new(63, 4));
+    assert!(chunks.get_block(36, 4, -11).is_air());
+    assert_eq!(chunks.get_block_light(35, 8, -11), (0, 15));
+
+    let sign = chunks.get_block_entity(35, 4, -11).unwrap();
+    assert_eq!(sign.kind(), BlockEntityKind::Sign);
+    let BlockEntityView::Sign(sign) = sign.view() else {
+        panic!("not a sign");
+    };
+    assert_eq!(sign.lines(), [r#"{"text":"Hello"}"#, "", "", ""]);
+}
+
+#[test]
+fn paletted_column_without_skylight() {
+    let p: MapChunk_107 = read(NETHER_1_9, 107);
+    let mut chunks = ChunkManager::new();
+    chunks.apply(ChunkData::Single_107(p)).unwrap();
+
+    let column = chunks.get(&(-3, 7)).unwrap();
+    let present: Vec<usize> = (0..16).filter(|&i| column.sections[i].is_some()).collect();
+    assert_eq!(present, [1, 4]);
+
+    // 5 bits per block, so some entries span two longs
+    let palette: Vec<BlockState> = [0, 87, 88, 89, 153, 213]
+        .into_iter()
+        .map(|id| BlockState::new(id, 0))
+        .chain((0..16).map(|color| BlockState::new(35, color)))
+        .collect();
+    let section = column.sections[1].as_ref().unwrap();
+    for (i, block) in section.blocks.iter().enumerate() {
+        assert_eq!(*block, palette[(i * 7) % palette.len()], "index {}", i);
+    }
+    assert_eq!(chunks.get_block_light(-48, 16, 112), (1, 0));
+
+    // Global palette
+    let section = column.sections[4].as_ref().unwrap();
+    for (i, block) in section.blocks.iter().enumerate() {
+        assert_eq!(
+            *block,
+            BlockState::new((i % 200) as u16 + 1, (i % 16) as u8)
+        );
+    }
+    assert_eq!(chunks.get_block_light(-47, 64, 112), (15, 0));
+}
+
+/// Packs a 1.16+ container, where entries never span two longs
+fn write_container(data: &mut Vec<u8>, bits: u8, palette: Option<&[i32]>, entries: &[u64]) {
+    data.push(bits);
+    if let Some(palette) = palette {
+        VarInt(palette.len() as i32).write_to(data).unwrap();
+        for v in palette {
+            VarInt(*v).write_to(data).unwrap();
+        }
+    }
+
+    let per_long = 64 / bits as usize;
+    let longs: Vec<u64> = entries
+        .chunks(per_long)
+        .map(|c| {
+            c.iter()
+                .enumerate()
+                .fold(0, |l, (i, v)| l | v << (i * bits as usize))
+        })
+        .collect();
+    VarInt(longs.len() as i32).write_to(data).unwrap();
+    for l in longs {
+        data.extend_from_slice(&l.to_be_bytes());
+    }
+}
+
+/// A 1.18+ container holding a single value
+fn write_single_value(data: &mut Vec<u8>, value: i32) {
+    data.push(0);
+    VarInt(value).write_to(data).unwrap();
+    VarInt(0).write_to(data).unwrap();
+}
+
+/// 1.16.5 state ids of blocks that existed before 1.13, along with their legacy states
+const STATES_1_16_5: [(i32, BlockState); 20] = [
+    (0, BlockState::AIR),
+    (1, BlockState::new(1, 0)),
+    (2, BlockState::new(1, 1)),
+    (3, BlockState::new(1, 2)),
+    (4, BlockState::new(1, 3)),
+    (5, BlockState::new(1, 4)),
+    (6, BlockState::new(1, 5)),
+    (7, BlockState::new(1, 6)),
+    // grass_block[snowy=false]
+    (9, BlockState::new(2, 0)),
+    (10, BlockState::new(3, 0)),
+    (11, BlockState::new(3, 1)),
+    (14, BlockState::new(4, 0)),
+    (15, BlockState::new(5, 0)),
+    (16, BlockState::new(5, 1)),
+    (33, BlockState::new(7, 0)),
+    // water[level=0]
+    (34, BlockState::new(9, 0)),
+    (66, BlockState::new(12, 0)),
+    (67, BlockState::new(12, 1)),
+    (68, BlockState::new(13, 0)),
+    // oak_log[axis=y]
+    (74, BlockState::new(17, 0)),
+];
+
+#[test]
+fn flattened_column_with_separate_light() {
+    let ids = Arc::new(IdMap::for_protocol(ProtocolVersion::Proto1_16_5).unwrap());
+    let nether_gold_ore = ids.block_state(&StateName::new("nether_gold_ore")).unwrap();
+    assert!(nether_gold_ore.id() >= 256);
+    // Blocks gained properties the legacy states don't have, so these map to the closest one
+    let global = [
+        // oak_stairs[facing=east,half=bottom,shape=straight,waterlogged=false]
+        (2025, BlockState::new(53, 0)),
+        (1384, BlockState::new(35, 0)),
+        (1398, BlockState::new(35, 14)),
+        // oak_leaves[distance=7,persistent=false]
+        (158, BlockState::new(18, 0)),
+        (1435, BlockState::new(50, 0)),
+        (72, nether_gold_ore),
+    ];
+
+    let mut data = vec![];
+    // 5 bits per block, so only 12 fit in a long and the last 4 bits of each are unused
+    data.extend_from_slice(&4096i16.to_be_bytes());
+    let palette: Vec<i32> = STATES_1_16_5.iter().map(|&(id, _)| id).collect();
+    let entries: Vec<u64> = (0..4096).map(|i| i % 20).collect();
+    write_container(&mut data, 5, Some(&palette), &entries);
+    // Global palette
+    data.extend_from_slice(&4096i16.to_be_bytes());
+    let entries: Vec<u64> = (0..4096).map(|i| global[i % 6].0 as u64).collect();
+    write_container(&mut data, 15, None, &entries);
+
+    // Biomes are 4x4x4 cells, the layer at y 64 is the one that's kept
+    let biomes: Vec<VarInt> = (0..1024)
+        .map(|i| VarInt(if (256..272).contains(&i) { 4 } else { 1 }))
+        .collect();
+
+    let mut chunks = ChunkManager::new();
+    chunks.ids = ids;
+    // The server sends the light before the column
+    let light = UpdateLight_735 {
+        chunk_x: VarInt(2),
+        chunk_z: VarInt(-3),
+        trust_edges: true,
+        sky_light_mask: VarInt(0b10),
+        block_light_mask: VarInt(0b1000),
+        empty_sky_light_mask: VarInt(0),
+        empty_block_light_mask: VarInt(0),
+        light: vec![vec![0xff; 2048].into(), vec![0x11; 2048].into()],
+    };
+    let result = chunks.apply(ChunkData::Light_735(light)).unwrap();
+    assert!(result.loaded.is_empty());
+
+    let p = MapChunk_751 {
+        x: 2,
+        z: -3,
+        ground_up: true,
+        bit_map: VarInt(0b101),
+        heightmaps: nbt::Blob::new(),
+        biomes: Some(biomes.into()),
+        chunk_data: data.into(),
+        block_entities: vec![].into(),
+    };
+    let result = chunks.apply(ChunkData::Single_751(p.clone())).unwrap();
+    assert_eq!(result.loaded, [(2, -3)]);
+    assert_eq!(result.trailing_bytes, 0);
+
+    let column = chunks.get(&(2, -3)).unwrap();
+    assert!(column.biomes.iter().all(|&b| b == 4));
+    let section = column.sections[0].as_ref().unwrap();
+    for (i, block) in section.blocks.iter().enumerate() {
+        assert_eq!(*block, STATES_1_16_5[i % 20].1, "index {}", i);
+    }
+    let section = column.sections[2].as_ref().unwrap();
+    for (i, block) in section.blocks.iter().enumerate() {
+        assert_eq!(*block, global[i % 6].1, "index {}", i);
+    }
+    assert_eq!(chunks.get_block_light(32, 5, -48), (0, 15));
+    assert_eq!(chunks.get_block_light(32, 37, -48), (1, 0));
+
+    // Updating a section keeps the light it had
+    let p = MapChunk_751 {
+        ground_up: false,
+        bit_map: VarInt(0b1),
+        biomes: None,
+        chunk_data: p.chunk_data.data[..p.chunk_data.data.len() / 2]
+            .to_vec()
+            .into(),
+        ..p
+    };
+    chunks.apply(ChunkData::Single_751(p)).unwrap();
+    assert_eq!(chunks.get_block_light(32, 5, -48), (0, 15));
+}
+
+#[test]
+fn column_with_negative_sections() {
+    // 1.19.2 ids of stone, grass_block[snowy=false] and deepslate[axis=y]
+    const STONE: i32 = 1;
+    const GRASS_BLOCK: i32 = 9;
+    const DEEPSLATE: i32 = 19779;
+
+    let ids = Arc::new(IdMap::for_protocol(ProtocolVersion::Proto1_19_2).unwrap());
+    let deepslate = ids
+        .block_state(&"minecraft:deepslate[axis=y]".parse().unwrap())
+        .unwrap();
+    assert_eq!(ids.block_from_wire(DEEPSLATE), deepslate);
+
+    let mut data = vec![];
+    for y in -4..20 {
+        data.extend_from_slice(&4096i16.to_be_bytes());
+        if y == 1 {
+            let entries: Vec<u64> = (0..4096).map(|i| i % 2).collect();
+            write_container(&mut data, 4, Some(&[0, GRASS_BLOCK]), &entries);
+        } else {
+            let value = match y {
+                ..=-1 => DEEPSLATE,
+                0 => STONE,
+                _ => 0,
+            };
+            write_single_value(&mut data, value);
+        }
+        write_single_value(&mut data, if y == 4 { 7 } else { 1 });
+    }
+
+    let mut sign = nbt::Blob::new();
+    sign.insert("id", "minecraft:sign").unwrap();
+    sign.insert("Text1", r#"{"text":"Hi"}"#).unwrap();
+    let p = MapChunk_757 {
+        x: 1,
+        z: 0,
+        heightmaps: nbt::Blob::new(),
+        chunk_data: data.into(),
+        block_entities: vec![ChunkBlockEntity {
+            packed_xz: 0x12,
+            y: 33,
+            kind: VarInt(7),
+            data: OptionalNbt(Some(sign)),
+        }]
+        .into(),
+        // Bit 0 is the section below y -64
+        light: ChunkLight {
+            trust_edges: true,
+            sky_light_mask: vec![1 << 20].into(),
+            block_light_mask: vec![1 << 7].into(),
+            empty_sky_light_mask: vec![].into(),
+            empty_block_light_mask: vec![].into(),
+            sky_light: vec![vec![0xff; 2048].into()].into(),
+            block_light: vec![vec![0x22; 2048].into()].into(),
+        },
+    };
+
+    let mut chunks = ChunkManager::new();
+    chunks.ids = ids.clone();
+    // From the dimension type in JoinGame
+    chunks.set_min_y(-64);
+    let result = chunks.apply(ChunkData::Single_757(Box::new(p))).unwrap();
+    assert_eq!(result.loaded, [(1, 0)]);
+    assert_eq!(result.trailing_bytes, 0);
+
+    let column = chunks.get(&(1, 0)).unwrap();
+    assert!(column.biomes.iter().all(|&b| b == 7));
+    assert!(column.skylight);
+    assert_eq!(column.section_range(), -4..20);
+    assert_eq!(chunks.get_block(16, -64, 0), deepslate);
+    assert_eq!(chunks.get_block(16, -1, 0), deepslate);
+    assert!(chunks.get_block(16, -65, 0).is_air());
+    assert_eq!(chunks.get_block(16, 0, 0), BlockState::new(1, 0));
+    assert_eq!(chunks.get_block(16, 16, 0), BlockState::AIR);
+    assert_eq!(chunks.get_block(17, 16, 0), BlockState::new(2, 0));
+    assert!(chunks.get_block(16, 32, 0).is_air());
+    assert_eq!(chunks.get_block_light(16, 32, 0), (2, 0));
+    assert_eq!(chunks.get_block_light(16, 250, 0), (0, 15));
+
+    let sign = chunks.get_block_entity(17, 33, 2).unwrap();
+    assert_eq!(sign.kind(), BlockEntityKind::Sign);
+
+    // Changes below y=0 and above y=255 are kept as well
+    for (section_y, y) in [(-4i64, -60), (19, 310)] {
+        let p = Packet::MultiBlockChange_751(MultiBlockChange_751 {
+            section: (1 << 42) | (section_y & 0xfffff),
+            suppress_light_updates: false,
+            records: vec![VarLong((STONE as i64) << 12 | (y & 0xf))].into(),
+        });
+        let Some(AbstractPacket::MultiBlockChange {
+            chunk_x,
+            chunk_z,
+            records,
+        }) = AbstractPacket::from_packet(p, &ids)
+        else {
+            panic!("Not a multi block change");
+        };
+        assert_eq!(records[0].y, y as i32);
+        chunks.apply_multi_block_change(chunk_x, chunk_z, &records);
+        assert_eq!(chunks.get_block(16, y as i32, 0), BlockState::new(1, 0));
+    }
+
+    // Light spreads below y=0 too
+    chunks.set_block(16, -30, 0, BlockState::new(50, 5));
+    assert_eq!(chunks.get_block_light(16, -30, 0).0, 14);
+}
+
+#[test]
+fn reset_forgets_pending_light() {
+    let mut chunks = ChunkManager::new();
+    chunks.ids = Arc::new(IdMap::for_protocol(ProtocolVersion::Proto1_16_5).unwrap());
+    let light = UpdateLight_735 {
+        chunk_x: VarInt(0),
+        chunk_z: VarInt(0),
+        trust_edges: true,
+        sky_light_mask: VarInt(0b10),
+        block_light_mask: VarInt(0b10),
+        empty_sky_light_mask: VarInt(0),
+        empty_block_light_mask: VarInt(0),
+        light: vec![vec![0xff; 2048].into(), vec![0xff; 2048].into()],
+    };
+    chunks.apply(ChunkData::Light_735(light)).unwrap();
+
+    // Respawning in another dimension, the light was meant for the old one
+    chunks.reset(0);
+
+    let mut data = vec![];
+    data.extend_from_slice(&4096i16.to_be_bytes());
+    write_container(&mut data, 4, Some(&[0, 1]), &[1; 4096]);
+    let p = MapChunk_751 {
+        x: 0,
+        z: 0,
+        ground_up: true,
+        bit_map: VarInt(0b1),
+        heightmaps: nbt::Blob::new(),
+        biomes: Some(vec![VarInt(1); 1024].into()),
+        chunk_data: data.into(),
+        block_entities: vec![].into(),
+    };
+    chunks.apply(ChunkData::Single_751(p)).unwrap();
+    assert_eq!(chunks.get_block(0, 0, 0), BlockState::new(1, 0));
+    assert_eq!(chunks.get_block_light(0, 0, 0), (0, 0));
+}
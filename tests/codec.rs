@@ -0,0 +1,99 @@
+use minecrab::{
+    net::codec::{read_frame, MinecraftCodec, RawPacket, MAX_DATA_LEN, MAX_FRAME_LEN},
+    varint::WriteProtoExt,
+};
+
+const COMPRESSED: &[u8] = include_bytes!("fixtures/frame_compressed_256.bin");
+const UNCOMPRESSED: &[u8] = include_bytes!("fixtures/frame_uncompressed_256.bin");
+
+fn codec(threshold: i32) -> MinecraftCodec {
+    let mut codec = MinecraftCodec::default();
+    codec.set_compression(threshold);
+    codec
+}
+
+#[test]
+fn below_threshold_round_trip() {
+    let codec = codec(256);
+    let p = codec.decode_frame(UNCOMPRESSED).unwrap();
+    assert_eq!(p.id, 0x0f);
+    assert_eq!(p.data, b"hello");
+
+    assert_eq!(codec.encode_frame(&p).unwrap(), UNCOMPRESSED);
+}
+
+#[test]
+fn above_threshold_round_trip() {
+    let codec = codec(256);
+    let p = codec.decode_frame(COMPRESSED).unwrap();
+    assert_eq!(p.id, 0x21);
+    assert_eq!(p.data, (0..300).map(|i| (i % 7) as u8).collect::<Vec<_>>());
+
+    // Our deflate output may differ from the fixture's, so only check that it decodes to the same packet
+    let frame = codec.encode_frame(&p).unwrap();
+    assert_eq!(&frame[..2], &COMPRESSED[..2], "declared data length");
+    let decoded = codec.decode_frame(&frame).unwrap();
+    assert_eq!((decoded.id, decoded.data), (p.id, p.data));
+}
+
+#[test]
+fn uncompressed_without_threshold() {
+    let codec = MinecraftCodec::default();
+    let p = RawPacket {
+        id: 0x00,
+        data: vec![1, 2, 3],
+    };
+    let frame = codec.encode_frame(&p).unwrap();
+    assert_eq!(frame, [0x00, 1, 2, 3]);
+    assert_eq!(codec.decode_frame(&frame).unwrap().data, [1, 2, 3]);
+}
+
+#[test]
+fn compressed_below_threshold_is_rejected() {
+    // The same packet is fine with a lower threshold, but 256 requires it to be sent uncompressed
+    assert!(codec(16).decode_frame(COMPRESSED).is_ok());
+    assert!(codec(512).decode_frame(COMPRESSED).is_err());
+}
+
+#[test]
+fn wrong_declared_length_is_rejected() {
+    let codec = codec(256);
+    // Declared as 302, one byte more than the data inflates to
+    let mut frame = COMPRESSED.to_vec();
+    assert_eq!(&frame[..2], &[0xad, 0x02]);
+    frame[..2].copy_from_slice(&[0xae, 0x02]);
+    assert!(codec.decode_frame(&frame).is_err());
+
+    // Declared as 257, so inflating stops early instead of reading everything
+    frame[..2].copy_from_slice(&[0x81, 0x02]);
+    assert!(codec.decode_frame(&frame).is_err());
+}
+
+#[test]
+fn oversized_data_length_is_rejected() {
+    // Checked before inflating, so nothing is allocated for it
+    let mut frame = vec![];
+    frame.write_varint(MAX_DATA_LEN + 1).unwrap();
+    frame.extend_from_slice(&COMPRESSED[2..]);
+    assert!(codec(256).decode_frame(&frame).is_err());
+}
+
+#[tokio::test]
+async fn read_frame_limits() {
+    let mut buf = vec![];
+    buf.write_varint(UNCOMPRESSED.len() as i32).unwrap();
+    buf.extend_from_slice(UNCOMPRESSED);
+    let frame = read_frame(&mut &buf[..]).await.unwrap();
+    assert_eq!(frame.as_deref(), Some(UNCOMPRESSED));
+
+    // Closed between frames
+    assert!(read_frame(&mut &[][..]).await.unwrap().is_none());
+
+    // A length past the maximum is rejected before anything is allocated or read
+    let mut buf = vec![];
+    buf.write_varint(MAX_FRAME_LEN + 1).unwrap();
+    assert!(read_frame(&mut &buf[..]).await.is_err());
+    let mut buf = vec![];
+    buf.write_varint(0).unwrap();
+    assert!(read_frame(&mut &buf[..]).await.is_err());
+}
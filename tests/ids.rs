@@ -0,0 +1,186 @@
+use minecrab::{
+    ids::{IdMap, ItemStack, StateName},
+    net::{types::Slot, ProtocolVersion},
+    world::BlockState,
+};
+
+const IDS_1_13_2: &str = include_str!("../data/ids/1.13.2.json");
+
+/// Checks that a wire id has the expected name, maps to `legacy` and goes back to the same id
+fn check_block(ids: &IdMap, id: i32, name: &str, legacy: BlockState) {
+    let state = ids.block_from_wire(id);
+    assert_eq!(state, legacy, "{name}");
+    assert_eq!(ids.block_to_wire(state), Some(id), "{name}");
+    assert_eq!(
+        ids.block_state(&name.parse().unwrap()),
+        Some(legacy),
+        "{name}"
+    );
+}
+
+#[test]
+fn flattened_table_round_trip() {
+    let ids = IdMap::with_table(IDS_1_13_2).unwrap();
+
+    check_block(&ids, 0, "minecraft:air", BlockState::AIR);
+    check_block(&ids, 1, "minecraft:stone", BlockState::new(1, 0));
+    check_block(&ids, 2, "minecraft:granite", BlockState::new(1, 1));
+    check_block(
+        &ids,
+        9,
+        "minecraft:grass_block[snowy=false]",
+        BlockState::new(2, 0),
+    );
+    check_block(&ids, 10, "minecraft:dirt", BlockState::new(3, 0));
+    check_block(
+        &ids,
+        73,
+        "minecraft:oak_log[axis=y]",
+        BlockState::new(17, 0),
+    );
+    check_block(
+        &ids,
+        72,
+        "minecraft:oak_log[axis=x]",
+        BlockState::new(17, 4),
+    );
+    check_block(
+        &ids,
+        75,
+        "minecraft:spruce_log[axis=x]",
+        BlockState::new(17, 5),
+    );
+
+    // Snowy grass didn't have its own state, it shares the plain one but doesn't get its id
+    assert_eq!(ids.block_from_wire(8), BlockState::new(2, 0));
+
+    // Stairs gained shapes and waterlogging, which are all mapped to the legacy state with the same facing and half
+    let stairs = ids.block_from_wire(1649);
+    assert_eq!(
+        ids.block_name(stairs).unwrap().to_string(),
+        "minecraft:oak_stairs[facing=north,half=top]"
+    );
+    assert_eq!(ids.block_to_wire(stairs), Some(1649));
+
+    // New blocks get states of their own
+    let kelp = ids.block_state(&StateName::new("kelp_plant")).unwrap();
+    assert!(kelp.id() >= 256);
+    assert_eq!(
+        ids.block_name(ids.block_from_wire(8590))
+            .unwrap()
+            .to_string(),
+        "minecraft:conduit[waterlogged=false]"
+    );
+    assert_eq!(
+        ids.block_name(ids.block_from_wire(8598))
+            .unwrap()
+            .to_string(),
+        "minecraft:structure_block[mode=data]"
+    );
+
+    // Out of range ids are air
+    assert_eq!(ids.block_from_wire(8599), BlockState::AIR);
+    assert_eq!(ids.block_from_wire(-1), BlockState::AIR);
+}
+
+#[test]
+fn tables_for_each_protocol() {
+    let ids = IdMap::for_protocol(ProtocolVersion::Proto1_12_2).unwrap();
+    assert_eq!(ids.block_from_wire(17 << 4), BlockState::new(17, 0));
+
+    let ids = IdMap::for_protocol(ProtocolVersion::Proto1_16_5).unwrap();
+    check_block(&ids, 1, "minecraft:stone", BlockState::new(1, 0));
+    check_block(
+        &ids,
+        9,
+        "minecraft:grass_block[snowy=false]",
+        BlockState::new(2, 0),
+    );
+    check_block(&ids, 34, "minecraft:water[level=0]", BlockState::new(9, 0));
+    check_block(
+        &ids,
+        74,
+        "minecraft:oak_log[axis=y]",
+        BlockState::new(17, 0),
+    );
+    let name = |ids: &IdMap, id| ids.block_name(ids.block_from_wire(id)).unwrap().to_string();
+    assert_eq!(name(&ids, 72), "minecraft:nether_gold_ore");
+    assert_eq!(name(&ids, 249), "minecraft:note_block");
+    assert_eq!(name(&ids, 17107), "minecraft:quartz_bricks");
+
+    let ids = IdMap::for_protocol(ProtocolVersion::Proto1_19_2).unwrap();
+    check_block(&ids, 1, "minecraft:stone", BlockState::new(1, 0));
+    check_block(
+        &ids,
+        9,
+        "minecraft:grass_block[snowy=false]",
+        BlockState::new(2, 0),
+    );
+    check_block(&ids, 75, "minecraft:water[level=0]", BlockState::new(9, 0));
+    check_block(
+        &ids,
+        118,
+        "minecraft:oak_log[axis=y]",
+        BlockState::new(17, 0),
+    );
+    assert_eq!(name(&ids, 21), "minecraft:mangrove_planks");
+    assert_eq!(name(&ids, 111), "minecraft:deepslate_gold_ore");
+}
+
+#[test]
+fn items_for_each_protocol() {
+    for protocol in [
+        ProtocolVersion::Proto1_13_2,
+        ProtocolVersion::Proto1_16_5,
+        ProtocolVersion::Proto1_19_2,
+    ] {
+        let ids = IdMap::for_protocol(protocol).unwrap();
+
+        let slot = Slot {
+            item_id: 1,
+            item_count: Some(3),
+            item_damage: None,
+            data: None,
+        };
+        let stack = ids.item_from_wire(&slot).unwrap();
+        assert_eq!(
+            stack,
+            ItemStack {
+                item: "minecraft:stone".to_string(),
+                count: 3,
+                damage: 0,
+                nbt: None,
+            },
+            "{protocol:?}"
+        );
+        assert_eq!(ids.item_to_wire(&stack).unwrap().item_id, 1, "{protocol:?}");
+    }
+
+    let ids = IdMap::for_protocol(ProtocolVersion::Proto1_13_2).unwrap();
+    let name = |ids: &IdMap, id| {
+        let slot = Slot {
+            item_id: id,
+            item_count: None,
+            item_damage: None,
+            data: None,
+        };
+        ids.item_from_wire(&slot).unwrap().item
+    };
+    assert_eq!(name(&ids, 789), "minecraft:heart_of_the_sea");
+
+    // Ids past the end of the table keep their number
+    let unknown = ItemStack {
+        item: name(&ids, 2000),
+        count: 1,
+        damage: 0,
+        nbt: None,
+    };
+    assert_eq!(unknown.item, "unknown:2000");
+    assert_eq!(ids.item_to_wire(&unknown).unwrap().item_id, 2000);
+
+    let ids = IdMap::for_protocol(ProtocolVersion::Proto1_16_5).unwrap();
+    assert_eq!(name(&ids, 975), "minecraft:respawn_anchor");
+
+    let ids = IdMap::for_protocol(ProtocolVersion::Proto1_19_2).unwrap();
+    assert_eq!(name(&ids, 1151), "minecraft:echo_shard");
+}
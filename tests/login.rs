@@ -0,0 +1,263 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::Arc,
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use minecrab::{
+    net::{
+        auth::SessionServerAuthenticator,
+        codec::{MinecraftCodec, RawPacket},
+        connection::ClientConnection,
+        encryption::{server_hash, EncryptedReader, EncryptedWriter},
+        ProtocolVersion,
+    },
+    varint::{ReadProtoExt, WriteProtoExt},
+};
+use rsa::{pkcs8::EncodePublicKey, PaddingScheme, RsaPrivateKey, RsaPublicKey};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+const ACCESS_TOKEN: &str = "0123456789abcdef";
+const PROFILE_UUID: &str = "069a79f444e94726a5befca90e38aaf5";
+const VERIFY_TOKEN: [u8; 4] = [1, 2, 3, 4];
+
+#[test]
+fn server_hash_known_vectors() {
+    assert_eq!(
+        server_hash("Notch", &[], &[]),
+        "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48"
+    );
+    // Negative digests are printed with a minus sign instead of in two's complement
+    assert_eq!(
+        server_hash("jeb_", &[], &[]),
+        "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1"
+    );
+    // Leading zeroes are dropped
+    assert_eq!(
+        server_hash("simon", &[], &[]),
+        "88e16a1019277b15d58faf0541e11910eb756f6"
+    );
+
+    // The server id, shared secret and public key are hashed as one message
+    assert_eq!(
+        server_hash("No", b"tc", b"h"),
+        server_hash("Notch", &[], &[])
+    );
+}
+
+#[tokio::test]
+async fn cfb8_round_trip() {
+    let shared_secret: [u8; 16] = std::array::from_fn(|i| i as u8);
+    let (client, server) = tokio::io::duplex(4096);
+    let (mut server_read, mut server_write) = tokio::io::split(server);
+    let (client_read, client_write) = tokio::io::split(client);
+    let mut writer = EncryptedWriter::new(client_write);
+    let mut reader = EncryptedReader::new(client_read);
+
+    // Nothing is encrypted before encryption is enabled
+    writer.write_all(b"plain").await.unwrap();
+    let mut buf = [0u8; 5];
+    server_read.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"plain");
+
+    writer.enable_encryption(&shared_secret);
+    reader.enable_encryption(&shared_secret);
+
+    // AES-128/CFB8 with the shared secret as both key and IV
+    let data: Vec<u8> = (0..1000).map(|i| (i * 31 % 256) as u8).collect();
+    writer.write_all(b"mine").await.unwrap();
+    writer.write_all(b"crab").await.unwrap();
+    writer.write_all(&data).await.unwrap();
+    writer.flush().await.unwrap();
+    let mut encrypted = vec![0u8; 8 + data.len()];
+    server_read.read_exact(&mut encrypted).await.unwrap();
+    assert_eq!(encrypted[..8], [103, 161, 53, 250, 102, 221, 10, 215]);
+
+    // Decrypting is done byte by byte as well, so it doesn't matter how the data is split up
+    for chunk in encrypted.chunks(7) {
+        server_write.write_all(chunk).await.unwrap();
+    }
+
+    let mut decrypted = vec![0u8; encrypted.len()];
+    reader.read_exact(&mut decrypted).await.unwrap();
+    assert_eq!(&decrypted[..8], b"minecrab");
+    assert_eq!(&decrypted[8..], data);
+}
+
+/// Answers a single request with `status`, returns the request's JSON body
+fn mock_session_server(status: u16) -> (String, JoinHandle<serde_json::Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        assert_eq!(
+            request_line.trim_end(),
+            "POST /session/minecraft/join HTTP/1.1"
+        );
+
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+        }
+
+        let mut body = vec![0u8; content_length];
+        reader.read_exact(&mut body).unwrap();
+
+        let mut stream = stream;
+        write!(
+            stream,
+            "HTTP/1.1 {status} Mock\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        )
+        .unwrap();
+
+        serde_json::from_slice(&body).unwrap()
+    });
+
+    (url, handle)
+}
+
+/// Plays the server side of an online-mode 1.12.2 login, returns the hash the client should have sent to the session server
+///
+/// If `accept` isn't set, the server waits for the client to give up after the encryption request
+async fn mock_game_server(listener: tokio::net::TcpListener, accept: bool) -> String {
+    let (stream, _) = listener.accept().await.unwrap();
+    let (read_half, write_half) = stream.into_split();
+    let mut reader = EncryptedReader::new(read_half);
+    let mut writer = EncryptedWriter::new(write_half);
+    let codec = MinecraftCodec::default();
+
+    let handshake = codec.read(&mut reader).await.unwrap();
+    assert_eq!(handshake.id, 0x00);
+    let login_start = codec.read(&mut reader).await.unwrap();
+    assert_eq!(login_start.id, 0x00);
+    assert_eq!(
+        login_start.data.as_slice().read_varstring().unwrap(),
+        "Steve"
+    );
+
+    let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+    let public_key = RsaPublicKey::from(&private_key)
+        .to_public_key_der()
+        .unwrap()
+        .as_ref()
+        .to_vec();
+
+    let mut data = vec![];
+    data.write_varstring("").unwrap();
+    data.write_varint(public_key.len() as i32).unwrap();
+    data.extend_from_slice(&public_key);
+    data.write_varint(VERIFY_TOKEN.len() as i32).unwrap();
+    data.extend_from_slice(&VERIFY_TOKEN);
+    codec
+        .write(&mut writer, &RawPacket { id: 0x01, data })
+        .await
+        .unwrap();
+
+    if !accept {
+        // The client closes the connection once the session server turns it down
+        assert!(codec.read(&mut reader).await.is_err());
+        return String::new();
+    }
+
+    let response = codec.read(&mut reader).await.unwrap();
+    assert_eq!(response.id, 0x01);
+    let mut c = response.data.as_slice();
+    let mut encrypted_secret = vec![0u8; c.read_varint().unwrap() as usize];
+    Read::read_exact(&mut c, &mut encrypted_secret).unwrap();
+    let mut encrypted_token = vec![0u8; c.read_varint().unwrap() as usize];
+    Read::read_exact(&mut c, &mut encrypted_token).unwrap();
+
+    let shared_secret: [u8; 16] = private_key
+        .decrypt(PaddingScheme::new_pkcs1v15_encrypt(), &encrypted_secret)
+        .unwrap()
+        .try_into()
+        .unwrap();
+    let token = private_key
+        .decrypt(PaddingScheme::new_pkcs1v15_encrypt(), &encrypted_token)
+        .unwrap();
+    assert_eq!(token, VERIFY_TOKEN);
+
+    reader.enable_encryption(&shared_secret);
+    writer.enable_encryption(&shared_secret);
+
+    let mut data = vec![];
+    data.write_varstring("069a79f4-44e9-4726-a5be-fca90e38aaf5")
+        .unwrap();
+    data.write_varstring("Steve").unwrap();
+    codec
+        .write(&mut writer, &RawPacket { id: 0x02, data })
+        .await
+        .unwrap();
+
+    server_hash("", &shared_secret, &public_key)
+}
+
+async fn login(
+    session_status: u16,
+    accept: bool,
+) -> (anyhow::Result<()>, String, serde_json::Value) {
+    let (session_server, session_handle) = mock_session_server(session_status);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(mock_game_server(listener, accept));
+
+    let tcp = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let mut connection = ClientConnection::from_stream_with_auth(
+        tcp,
+        ProtocolVersion::Proto1_12_2,
+        Arc::new(SessionServerAuthenticator {
+            session_server,
+            access_token: ACCESS_TOKEN.to_string(),
+            profile_uuid: PROFILE_UUID.to_string(),
+        }),
+    );
+
+    let result = tokio::time::timeout(
+        Duration::from_secs(10),
+        connection.login(addr.ip().to_string(), addr.port(), "Steve".to_string()),
+    )
+    .await
+    .expect("login timed out");
+
+    // Closes the connection, so a server waiting for more data notices
+    drop(connection);
+    let expected_hash = server.await.unwrap();
+    let join_request = session_handle.join().unwrap();
+
+    (result, expected_hash, join_request)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn online_login_with_mock_session_server() {
+    let (result, expected_hash, join_request) = login(204, true).await;
+    result.unwrap();
+
+    assert_eq!(join_request["accessToken"], ACCESS_TOKEN);
+    assert_eq!(join_request["selectedProfile"], PROFILE_UUID);
+    assert_eq!(join_request["serverId"], expected_hash.as_str());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn online_login_rejected_by_session_server() {
+    let (result, _, join_request) = login(403, false).await;
+    assert!(result.is_err());
+    assert_eq!(join_request["accessToken"], ACCESS_TOKEN);
+}
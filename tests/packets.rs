@@ -0,0 +1,589 @@
+use minecrab::{
+    ids::IdMap,
+    net::{
+        codec::RawPacket,
+        packet_helpers::Serializable,
+        packets::{
+            login::{clientbound::*, serverbound::*},
+            play::{clientbound::*, serverbound::*},
+            Packet,
+        },
+        types::*,
+        versions, ConnectionState, PacketDirection, ProtocolVersion,
+    },
+    varint::{VarInt, VarLong},
+};
+
+/// Encodes `packet`, checks that it decodes back to the same packet and returns the raw packet
+fn round_trip(
+    protocol: ProtocolVersion,
+    state: ConnectionState,
+    dir: PacketDirection,
+    packet: Packet,
+) -> RawPacket {
+    let raw = versions::encode_packet(protocol, &packet, state, dir).unwrap();
+    let decoded = versions::decode_packet(protocol, &raw, state, dir).unwrap();
+    assert_eq!(decoded, packet);
+    raw
+}
+
+fn position_bytes(position: &Position, version: i32) -> Vec<u8> {
+    let mut data = vec![];
+    position.write_to_versioned(&mut data, version).unwrap();
+    data
+}
+
+#[test]
+fn position_layout() {
+    // Example from wiki.vg, x (26 bits), z (26 bits) and then y (12 bits)
+    let data = 0x4607632C15B4833Fu64.to_be_bytes();
+    let position = Position::read_from_versioned(&mut &data[..], versions::PROTO_1_16_5).unwrap();
+    assert_eq!(
+        position,
+        Position {
+            x: 18357644,
+            y: 831,
+            z: -20882616
+        }
+    );
+    assert_eq!(position_bytes(&position, versions::PROTO_1_19_2), data);
+
+    // Before 18w43a y sat between x and z
+    let position = Position {
+        x: 18357644,
+        y: 83,
+        z: -20882616,
+    };
+    let data = position_bytes(&position, versions::PROTO_1_13_2);
+    assert_eq!(
+        u64::from_be_bytes(data.clone().try_into().unwrap()),
+        (18357644u64 << 38) | (83 << 26) | (-20882616i64 as u64 & 0x3FFFFFF)
+    );
+    assert_eq!(
+        Position::read_from_versioned(&mut &data[..], versions::PROTO_1_13_2).unwrap(),
+        position
+    );
+}
+
+#[test]
+fn login_plugin_request() {
+    for protocol in [
+        ProtocolVersion::Proto1_13_2,
+        ProtocolVersion::Proto1_16_5,
+        ProtocolVersion::Proto1_19_2,
+    ] {
+        let raw = round_trip(
+            protocol,
+            ConnectionState::Login,
+            PacketDirection::Client,
+            Packet::LoginPluginRequest_393(LoginPluginRequest_393 {
+                message_id: VarInt(7),
+                channel: "velocity:player_info".to_string(),
+                data: vec![1, 2, 3],
+            }),
+        );
+        assert_eq!(raw.id, 0x04);
+
+        let raw = round_trip(
+            protocol,
+            ConnectionState::Login,
+            PacketDirection::Server,
+            Packet::LoginPluginResponse_393(LoginPluginResponse_393 {
+                message_id: VarInt(7),
+                successful: false,
+                data: vec![],
+            }),
+        );
+        assert_eq!(raw.id, 0x02);
+    }
+}
+
+#[test]
+fn protocol_1_13_2() {
+    let protocol = ProtocolVersion::Proto1_13_2;
+    let play = ConnectionState::Play;
+
+    let raw = round_trip(
+        protocol,
+        play,
+        PacketDirection::Client,
+        Packet::Chat_47(Chat_47 {
+            message: r#"{"text":"Hello"}"#.to_string(),
+            position: 0,
+        }),
+    );
+    assert_eq!(raw.id, 0x0e);
+
+    round_trip(
+        protocol,
+        play,
+        PacketDirection::Client,
+        Packet::BlockChange_47(BlockChange_47 {
+            location: Position {
+                x: -5,
+                y: 70,
+                z: 12,
+            },
+            kind: VarInt(1),
+        }),
+    );
+
+    // 1.13.2 items have a presence flag and no damage
+    let raw = round_trip(
+        protocol,
+        play,
+        PacketDirection::Client,
+        Packet::EntityEquipment_107(EntityEquipment_107 {
+            entity_id: VarInt(3),
+            slot: VarInt(0),
+            item: Slot {
+                item_id: 1,
+                item_count: Some(64),
+                item_damage: None,
+                data: None,
+            },
+        }),
+    );
+    assert_eq!(raw.data, [3, 0, 1, 1, 64, 0]);
+
+    round_trip(
+        protocol,
+        play,
+        PacketDirection::Client,
+        Packet::MapChunk_393(MapChunk_393 {
+            x: 1,
+            z: -1,
+            ground_up: true,
+            bit_map: VarInt(0),
+            chunk_data: vec![0; 1024].into(),
+            block_entities: vec![].into(),
+        }),
+    );
+
+    let raw = round_trip(
+        protocol,
+        play,
+        PacketDirection::Server,
+        Packet::ChatServerbound_5(ChatServerbound_5 {
+            message: "hi".to_string(),
+        }),
+    );
+    assert_eq!(raw.id, 0x02);
+}
+
+#[test]
+fn protocol_1_16_5() {
+    let protocol = ProtocolVersion::Proto1_16_5;
+    let play = ConnectionState::Play;
+
+    round_trip(
+        protocol,
+        ConnectionState::Login,
+        PacketDirection::Client,
+        Packet::Success_735(Success_735 {
+            uuid: uuid::Uuid::from_u128(0x1234),
+            username: "Steve".to_string(),
+        }),
+    );
+
+    let raw = round_trip(
+        protocol,
+        play,
+        PacketDirection::Client,
+        Packet::Chat_735(Chat_735 {
+            message: r#"{"translate":"chat.type.text","with":["Steve","hi"]}"#.to_string(),
+            position: 0,
+            sender: uuid::Uuid::from_u128(0x1234),
+        }),
+    );
+    assert_eq!(raw.id, 0x0e);
+
+    let mut dimension = nbt::Blob::new();
+    dimension.insert("natural", 1i8).unwrap();
+    round_trip(
+        protocol,
+        play,
+        PacketDirection::Client,
+        Packet::Login_751(Login_751 {
+            entity_id: 42,
+            is_hardcore: false,
+            game_mode: 1,
+            previous_game_mode: -1,
+            world_names: vec!["minecraft:overworld".to_string()].into(),
+            dimension_codec: nbt::Blob::new(),
+            dimension,
+            world_name: "minecraft:overworld".to_string(),
+            hashed_seed: 123,
+            max_players: VarInt(20),
+            view_distance: VarInt(10),
+            reduced_debug_info: false,
+            enable_respawn_screen: true,
+            is_debug: false,
+            is_flat: true,
+        }),
+    );
+
+    round_trip(
+        protocol,
+        play,
+        PacketDirection::Client,
+        Packet::MultiBlockChange_751(MultiBlockChange_751 {
+            section: (-2i64 << 42) | ((3 & 0x3FFFFF) << 20) | 4,
+            suppress_light_updates: false,
+            records: vec![VarLong(1 << 12 | 0x123)].into(),
+        }),
+    );
+
+    round_trip(
+        protocol,
+        play,
+        PacketDirection::Client,
+        Packet::UpdateLight_735(UpdateLight_735 {
+            chunk_x: VarInt(1),
+            chunk_z: VarInt(2),
+            trust_edges: true,
+            sky_light_mask: VarInt(0b10),
+            block_light_mask: VarInt(0b10),
+            empty_sky_light_mask: VarInt(0),
+            empty_block_light_mask: VarInt(0),
+            light: vec![vec![0xff; 2048].into(), vec![0x11; 2048].into()],
+        }),
+    );
+
+    // The top bit of the slot says whether another entry follows
+    let empty = Slot {
+        item_id: -1,
+        ..Default::default()
+    };
+    let raw = round_trip(
+        protocol,
+        play,
+        PacketDirection::Client,
+        Packet::EntityEquipment_735(EntityEquipment_735 {
+            entity_id: VarInt(3),
+            equipment: EquipmentList(vec![(0, empty.clone()), (5, empty)]),
+        }),
+    );
+    assert_eq!(raw.data, [3, 0x80, 0, 5, 0]);
+}
+
+#[test]
+fn protocol_1_19_2() {
+    let protocol = ProtocolVersion::Proto1_19_2;
+    let play = ConnectionState::Play;
+
+    round_trip(
+        protocol,
+        ConnectionState::Login,
+        PacketDirection::Server,
+        Packet::LoginStart_760(LoginStart_760 {
+            username: "Steve".to_string(),
+            has_sig_data: false,
+            timestamp: None,
+            public_key: None,
+            signature: None,
+            has_player_uuid: true,
+            player_uuid: Some(uuid::Uuid::from_u128(0x1234)),
+        }),
+    );
+
+    round_trip(
+        protocol,
+        ConnectionState::Login,
+        PacketDirection::Client,
+        Packet::Success_759(Success_759 {
+            uuid: uuid::Uuid::from_u128(0x1234),
+            username: "Steve".to_string(),
+            properties: vec![GameProfileProperty {
+                name: "textures".to_string(),
+                value: "e30=".to_string(),
+                signature: None,
+            }]
+            .into(),
+        }),
+    );
+
+    let raw = round_trip(
+        protocol,
+        play,
+        PacketDirection::Client,
+        Packet::SystemChat_760(SystemChat_760 {
+            content: r#"{"text":"Welcome"}"#.to_string(),
+            overlay: false,
+        }),
+    );
+    assert_eq!(raw.id, 0x62);
+
+    let raw = round_trip(
+        protocol,
+        play,
+        PacketDirection::Client,
+        Packet::PlayerChat_760(PlayerChat_760 {
+            has_previous_signature: false,
+            previous_signature: None,
+            sender: uuid::Uuid::from_u128(0x1234),
+            header_signature: vec![1; 256].into(),
+            plain_message: "hi".to_string(),
+            has_formatted_message: false,
+            formatted_message: None,
+            timestamp: 1_660_000_000_000,
+            salt: 5,
+            previous_messages: vec![PreviousMessage {
+                sender: uuid::Uuid::from_u128(0x5678),
+                signature: vec![2; 256].into(),
+            }]
+            .into(),
+            has_unsigned_content: false,
+            unsigned_content: None,
+            filter_type: VarInt(2),
+            filter_type_bits: Some(vec![0b1].into()),
+            chat_type: VarInt(0),
+            network_name: r#"{"text":"Steve"}"#.to_string(),
+            has_network_target_name: false,
+            network_target_name: None,
+        }),
+    );
+    assert_eq!(raw.id, 0x33);
+
+    let raw = round_trip(
+        protocol,
+        play,
+        PacketDirection::Server,
+        Packet::ChatMessage_760(ChatMessage_760 {
+            message: "hi".to_string(),
+            timestamp: 1_660_000_000_000,
+            salt: 0,
+            signature: vec![].into(),
+            signed_preview: false,
+            previous_messages: vec![].into(),
+            has_last_received: false,
+            last_received: None,
+        }),
+    );
+    assert_eq!(raw.id, 0x05);
+
+    round_trip(
+        protocol,
+        play,
+        PacketDirection::Client,
+        Packet::SpawnPosition_755(SpawnPosition_755 {
+            location: Position {
+                x: 100,
+                y: -60,
+                z: -100,
+            },
+            angle: 0.,
+        }),
+    );
+
+    round_trip(
+        protocol,
+        play,
+        PacketDirection::Client,
+        Packet::MapChunk_757(MapChunk_757 {
+            x: 0,
+            z: 0,
+            heightmaps: nbt::Blob::new(),
+            chunk_data: vec![].into(),
+            block_entities: vec![ChunkBlockEntity {
+                packed_xz: 0x12,
+                y: -10,
+                kind: VarInt(7),
+                data: OptionalNbt(None),
+            }]
+            .into(),
+            light: ChunkLight {
+                trust_edges: true,
+                sky_light_mask: vec![0b100].into(),
+                block_light_mask: vec![].into(),
+                empty_sky_light_mask: vec![0b11].into(),
+                empty_block_light_mask: vec![].into(),
+                sky_light: vec![vec![0xff; 2048].into()].into(),
+                block_light: vec![].into(),
+            },
+        }),
+    );
+
+    let mut metadata = EntityMeta::default();
+    metadata.meta.insert(0, MetadataField::Byte(0x20));
+    metadata.meta.insert(6, MetadataField::Pose(5));
+    metadata.meta.insert(7, MetadataField::OptVarInt(Some(3)));
+    metadata.meta.insert(
+        8,
+        MetadataField::OptGlobalPos(Some((
+            "minecraft:overworld".to_string(),
+            Position { x: 1, y: -5, z: 3 },
+        ))),
+    );
+    round_trip(
+        protocol,
+        play,
+        PacketDirection::Client,
+        Packet::EntityMetadata_47(EntityMetadata_47 {
+            entity_id: VarInt(3),
+            metadata,
+        }),
+    );
+}
+
+#[test]
+fn unsupported_packets_are_reported() {
+    // The 1.12.2 chat struct has no id in 1.19.2
+    let packet = Packet::Chat_47(Chat_47 {
+        message: String::new(),
+        position: 0,
+    });
+    assert!(versions::encode_packet(
+        ProtocolVersion::Proto1_19_2,
+        &packet,
+        ConnectionState::Play,
+        PacketDirection::Client
+    )
+    .is_err());
+
+    assert!(ProtocolVersion::from_protocol(versions::PROTO_1_16_5).is_ok());
+    assert!(ProtocolVersion::from_protocol(versions::PROTO_1_15_2).is_err());
+}
+
+#[test]
+fn chat_translation() {
+    use minecrab::net::wrapper::AbstractPacket;
+
+    let ids = IdMap::default();
+    let p = Packet::Chat_735(Chat_735 {
+        message: r#"{"text":"Saved","color":"gray"}"#.to_string(),
+        position: 2,
+        sender: uuid::Uuid::nil(),
+    });
+    let Some(AbstractPacket::ChatMessage { message, overlay }) =
+        AbstractPacket::from_packet(p, &ids)
+    else {
+        panic!("not a chat message");
+    };
+    assert_eq!(message.to_plain_text(), "Saved");
+    assert!(overlay);
+
+    let p = AbstractPacket::ChatServerbound("hi".to_string())
+        .to_packet(versions::PROTO_1_19_2)
+        .unwrap();
+    assert!(
+        matches!(p, Packet::ChatMessage_760(ChatMessage_760 { ref message, .. }) if message == "hi")
+    );
+}
+
+#[test]
+fn dimension_min_y() {
+    use minecrab::net::wrapper::AbstractPacket;
+    use nbt::Value;
+
+    let dimension_type = |name: &str, min_y: i32| {
+        let element = [("min_y".to_string(), Value::Int(min_y))];
+        Value::Compound(
+            [
+                ("name".to_string(), Value::String(name.to_string())),
+                ("id".to_string(), Value::Int(0)),
+                (
+                    "element".to_string(),
+                    Value::Compound(element.into_iter().collect()),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    };
+    let registry = [(
+        "value".to_string(),
+        Value::List(vec![
+            dimension_type("minecraft:overworld", -64),
+            dimension_type("minecraft:the_nether", 0),
+        ]),
+    )];
+    let mut registry_codec = nbt::Blob::new();
+    registry_codec
+        .insert(
+            "minecraft:dimension_type",
+            Value::Compound(registry.into_iter().collect()),
+        )
+        .unwrap();
+
+    let ids = IdMap::default();
+    let p = Packet::Login_759(Login_759 {
+        entity_id: 42,
+        is_hardcore: false,
+        game_mode: 0,
+        previous_game_mode: -1,
+        world_names: vec!["minecraft:overworld".to_string()].into(),
+        registry_codec,
+        dimension_type: "minecraft:overworld".to_string(),
+        world_name: "minecraft:overworld".to_string(),
+        hashed_seed: 0,
+        max_players: VarInt(20),
+        view_distance: VarInt(10),
+        simulation_distance: VarInt(10),
+        reduced_debug_info: false,
+        enable_respawn_screen: true,
+        is_debug: false,
+        is_flat: false,
+        has_death_location: false,
+        death_dimension: None,
+        death_location: None,
+    });
+    let Some(AbstractPacket::JoinGame {
+        min_y,
+        mut dimension_types,
+        ..
+    }) = AbstractPacket::from_packet(p, &ids)
+    else {
+        panic!("not a join game");
+    };
+    assert_eq!(min_y, -64);
+    dimension_types.sort();
+    assert_eq!(
+        dimension_types,
+        [
+            ("minecraft:overworld".to_string(), -64),
+            ("minecraft:the_nether".to_string(), 0)
+        ]
+    );
+
+    // 1.19+ only names the dimension type when respawning
+    let p = Packet::Respawn_759(Respawn_759 {
+        dimension_type: "minecraft:the_nether".to_string(),
+        world_name: "minecraft:the_nether".to_string(),
+        hashed_seed: 0,
+        gamemode: 0,
+        previous_gamemode: -1,
+        is_debug: false,
+        is_flat: false,
+        copy_metadata: false,
+        has_death_location: false,
+        death_dimension: None,
+        death_location: None,
+    });
+    let Some(AbstractPacket::Respawn {
+        min_y,
+        dimension_type,
+        ..
+    }) = AbstractPacket::from_packet(p, &ids)
+    else {
+        panic!("not a respawn");
+    };
+    assert_eq!(min_y, None);
+    assert_eq!(dimension_type.as_deref(), Some("minecraft:the_nether"));
+
+    // 1.16 sends the dimension type itself, which doesn't go below 0 yet
+    let p = Packet::Respawn_751(Respawn_751 {
+        dimension: nbt::Blob::new(),
+        world_name: "minecraft:overworld".to_string(),
+        hashed_seed: 0,
+        gamemode: 0,
+        previous_gamemode: -1,
+        is_debug: false,
+        is_flat: false,
+        copy_metadata: false,
+    });
+    let Some(AbstractPacket::Respawn { min_y, .. }) = AbstractPacket::from_packet(p, &ids) else {
+        panic!("not a respawn");
+    };
+    assert_eq!(min_y, Some(0));
+}